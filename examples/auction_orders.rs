@@ -0,0 +1,81 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    account::Account,
+    bars::BarInterval,
+    market::{HistoricalDataPoint, SimpleMarketDataProvider},
+    replay::BarReplayProvider,
+    Auction, Clock, Config, Order, OrderSide, Price, Quantity, SimulatedClock, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Auction Orders Example");
+
+    let start = Utc.with_ymd_and_hms(2024, 6, 3, 13, 30, 0).unwrap();
+    let clock = SimulatedClock::new(start - Duration::days(1));
+    let spy = Symbol::new("SPY");
+
+    // Daily bars: each bar's open and close are the session's auction prices
+    let ohlc = [
+        (529.02, 529.31, 522.60, 527.80),
+        (526.46, 529.15, 524.96, 528.39),
+        (530.77, 534.69, 528.73, 534.67),
+        (534.98, 535.42, 532.68, 534.66),
+    ];
+    let bars = ohlc
+        .iter()
+        .enumerate()
+        .map(|(i, (open, high, low, close))| HistoricalDataPoint {
+            symbol: spy.clone(),
+            open: Price::from_f64(*open),
+            high: Price::from_f64(*high),
+            low: Price::from_f64(*low),
+            close: Price::from_f64(*close),
+            volume: 50_000_000.0,
+            timestamp: start + Duration::days(i as i64),
+        })
+        .collect();
+    let mut market_data = BarReplayProvider::new()
+        .with_clock(clock.shared())
+        .with_auction_prints(true);
+    market_data.add_series(&spy, BarInterval::Days(1), bars);
+
+    let config = Config {
+        clock: Some(clock.shared()),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Auction Demo", "USD", Decimal::from(100_000), config);
+
+    // Rebalance into SPY on the first close, and try to buy more at the next open below $528
+    account.submit_order(Order::market_on_close(spy.clone(), OrderSide::Buy, Quantity::from_f64(100.0)))?;
+
+    while let Some(now) = market_data.advance(&clock) {
+        account.process_open_orders(&market_data)?;
+        println!("\n{}", market_data.current_bar(&spy)?.timestamp.format("%Y-%m-%d"));
+        for order in account.order_history.iter().filter(|order| order.updated_at == now) {
+            match order.average_fill_price() {
+                Some(price) => println!("  {:?} {:?} filled at ${}", order.order_type, order.side, price),
+                None => println!("  {:?} {:?} {:?}", order.order_type, order.side, order.status),
+            }
+        }
+
+        if account.open_orders().is_empty() && account.order_history.len() == 1 {
+            account.submit_order(Order::limit_on_open(spy.clone(), OrderSide::Buy, Quantity::from_f64(50.0), Price::from_f64(528.0)))?;
+            account.submit_order(Order::limit_on_close(spy.clone(), OrderSide::Sell, Quantity::from_f64(100.0), Price::from_f64(534.0)))?;
+        }
+    }
+
+    // Live feeds publish explicit auction prints instead
+    let mut live = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    live.set_price(spy.clone(), Price::from_f64(535.10));
+    account.submit_order(Order::market_on_close(spy.clone(), OrderSide::Buy, Quantity::from_f64(10.0)))?;
+    clock.advance(Duration::hours(6));
+    live.set_auction_price(spy.clone(), Auction::Close, Price::from_f64(535.25));
+    account.process_open_orders(&live)?;
+    if let Some(price) = account.order_history.last().and_then(|order| order.average_fill_price()) {
+        println!("\nClosing print at {}: MarketOnClose filled at ${}", clock.now().format("%H:%M"), price);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    account::Account,
+    bars::BarInterval,
+    market::HistoricalDataPoint,
+    replay::{BarReplayProvider, IntrabarPath},
+    Config, Order, OrderSide, Price, Quantity, SimulatedClock, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Bar Replay Example");
+
+    let start = Utc.with_ymd_and_hms(2019, 1, 2, 21, 0, 0).unwrap();
+    let clock = SimulatedClock::new(start - Duration::days(1));
+
+    // Daily bars: (open, high, low, close)
+    let spy = Symbol::new("SPY");
+    let ohlc = [
+        (245.98, 251.21, 245.95, 250.18),
+        (248.23, 248.57, 243.67, 244.21),
+        (247.59, 253.11, 247.17, 252.39),
+        (252.69, 255.95, 251.69, 254.38),
+        (256.82, 257.31, 254.00, 256.77),
+        (257.56, 259.16, 256.30, 257.97),
+        (256.26, 258.91, 255.80, 258.88),
+    ];
+    let bars = ohlc
+        .iter()
+        .enumerate()
+        .map(|(i, (open, high, low, close))| HistoricalDataPoint {
+            symbol: spy.clone(),
+            open: Price::from_f64(*open),
+            high: Price::from_f64(*high),
+            low: Price::from_f64(*low),
+            close: Price::from_f64(*close),
+            volume: 100_000_000.0,
+            timestamp: start + Duration::days(i as i64),
+        })
+        .collect();
+
+    let mut market_data = BarReplayProvider::new()
+        .with_clock(clock.shared())
+        .with_spread(Decimal::new(2, 4))
+        .with_intrabar_path(IntrabarPath::OpenHighLowClose);
+    market_data.add_series(&spy, BarInterval::Days(1), bars);
+
+    let config = Config {
+        clock: Some(clock.shared()),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Bar Replay Demo", "USD", Decimal::from(100_000), config);
+
+    // Rest a limit buy below the market before the first bar
+    let limit_buy = Order::limit(spy.clone(), OrderSide::Buy, Quantity::from_f64(100.0), Price::from_f64(244.0));
+    account.submit_order(limit_buy)?;
+
+    let mut stop_placed = false;
+    while market_data.advance(&clock).is_some() {
+        let bar = market_data.current_bar(&spy)?;
+        println!(
+            "\n{}: O {} H {} L {} C {}",
+            bar.timestamp.format("%Y-%m-%d"),
+            bar.open,
+            bar.high,
+            bar.low,
+            bar.close
+        );
+
+        account.process_open_orders(&market_data)?;
+
+        // Lock in gains with a stop once the position is well in profit
+        let in_position = account.get_position(&spy).is_some_and(|p| !p.is_flat());
+        if !stop_placed && in_position && bar.close.0 > Decimal::from(257) {
+            let stop = Order::stop(spy.clone(), OrderSide::Sell, Quantity::from_f64(100.0), Price::from_f64(256.5));
+            account.submit_order(stop)?;
+            println!("Placed sell stop at $256.50");
+            stop_placed = true;
+        }
+
+        println!("Equity: ${}", account.equity(&market_data)?.round_dp(2));
+    }
+
+    println!("\n--- Trades ---");
+    for order in &account.order_history {
+        for trade in &order.trades {
+            println!(
+                "{} {:?} {} {} at ${}",
+                trade.timestamp.format("%Y-%m-%d"),
+                trade.side,
+                trade.quantity,
+                trade.symbol,
+                trade.price
+            );
+        }
+    }
+
+    Ok(())
+}
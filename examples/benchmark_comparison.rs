@@ -0,0 +1,70 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    account::Account,
+    market::{SimpleHistoricalDataProvider, SimpleMarketDataProvider},
+    synthetic::{PriceModel, SyntheticAsset, SyntheticMarket},
+    BarInterval, Config, Order, OrderSide, Quantity, RiskAnalytics, SimulatedClock, SnapshotKind, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Benchmark Comparison Example");
+
+    // A year of daily bars for a benchmark and a correlated, more volatile stock
+    let start = Utc.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap();
+    let mut market = SyntheticMarket::new(11, start, Duration::days(1))
+        .with_asset(SyntheticAsset::new("SPY", 380.0, PriceModel::GeometricBrownianMotion { drift: 0.10, volatility: 0.16 }))?
+        .with_asset(SyntheticAsset::new("NVDA", 150.0, PriceModel::GeometricBrownianMotion { drift: 0.25, volatility: 0.45 }))?
+        .with_correlation(vec![vec![1.0, 0.7], vec![0.7, 1.0]])?;
+    let series = market.generate_bars(365);
+    let spy = Symbol::new("SPY");
+    let nvda = Symbol::new("NVDA");
+
+    let mut benchmark = SimpleHistoricalDataProvider::new();
+    benchmark.add_data(&spy, series[0].clone());
+
+    // Hold a 60/40 mix of the stock and cash, snapshotting at each daily close
+    let clock = SimulatedClock::new(start);
+    let config = Config {
+        clock: Some(clock.shared()),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Benchmark Demo", "USD", Decimal::from(100_000), config);
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+
+    for (day, bar) in series[1].iter().enumerate() {
+        clock.set(bar.timestamp + Duration::hours(20));
+        market_data.set_price(nvda.clone(), bar.close);
+        if day == 0 {
+            account.submit_order(Order::market(nvda.clone(), OrderSide::Buy, Quantity::from_f64(400.0)))?;
+        }
+        account.process_open_orders(&market_data)?;
+        account.record_snapshot(SnapshotKind::Manual, &market_data)?;
+    }
+
+    let analytics = RiskAnalytics::new().with_risk_free_rate(0.04).with_periods_per_year(365.0);
+    let report = analytics
+        .compare(&account.equity_history, &benchmark, &spy, BarInterval::ONE_DAY)?
+        .ok_or("not enough overlapping history")?;
+
+    println!("\nAgainst {} from {} to {}:", report.benchmark, report.start.date_naive(), report.end.date_naive());
+    println!("Account return:    {:>7.2}%", report.total_return * 100.0);
+    println!("Benchmark return:  {:>7.2}%", report.benchmark_return * 100.0);
+    println!("Alpha:             {:>7.2}%", report.alpha.unwrap_or_default() * 100.0);
+    println!("Beta:              {:>7.2}", report.beta.unwrap_or_default());
+    println!("Correlation:       {:>7.2}", report.correlation.unwrap_or_default());
+    println!("Tracking error:    {:>7.2}%", report.tracking_error * 100.0);
+    println!("Information ratio: {:>7.2}", report.information_ratio.unwrap_or_default());
+    println!("Up capture:        {:>7.2}", report.up_capture.unwrap_or_default());
+    println!("Down capture:      {:>7.2}", report.down_capture.unwrap_or_default());
+
+    println!("\nRelative performance (first rows):");
+    let mut csv = Vec::new();
+    report.write_series_csv(&mut csv)?;
+    for line in String::from_utf8(csv)?.lines().take(5) {
+        println!("  {}", line);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    analytics::returns_between,
+    market::SimpleMarketDataProvider,
+    AccountManager, Clock, Config, Order, OrderSide, Price, Quantity, SimulatedClock, SnapshotKind, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Cash Flows Example");
+
+    let start = Utc.with_ymd_and_hms(2024, 1, 2, 21, 0, 0).unwrap();
+    let clock = SimulatedClock::new(start);
+    let config = Config {
+        clock: Some(clock.shared()),
+        snapshot_interval: Some(Duration::days(7)),
+        ..Config::default()
+    };
+
+    let mut manager = AccountManager::new();
+    let growth = manager.create_account_with_config("Growth", "USD", Decimal::from(100_000), config.clone())?;
+    let reserve = manager.create_account_with_config("Reserve", "USD", Decimal::from(200_000), config)?;
+
+    let qqq = Symbol::new("QQQ");
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    market_data.set_price(qqq.clone(), Price::from_f64(400.0));
+
+    let account = manager.get_account_mut(&growth).ok_or("missing account")?;
+    account.submit_order(Order::market(qqq.clone(), OrderSide::Buy, Quantity::from_f64(200.0)))?;
+    account.process_open_orders(&market_data)?;
+
+    // Weekly closes; cash moves in after a rally and out after a sell-off
+    let closes = [410.0, 425.0, 440.0, 430.0, 405.0, 395.0, 410.0, 420.0];
+    for (week, close) in closes.iter().enumerate() {
+        clock.advance(Duration::days(7));
+        market_data.set_price(qqq.clone(), Price::from_f64(*close));
+        manager.get_account_mut(&growth).ok_or("missing account")?.process_open_orders(&market_data)?;
+
+        if week == 2 {
+            println!("Week {}: transfer $50,000 in from Reserve and buy more", week + 1);
+            manager.transfer(&reserve, &growth, Decimal::from(50_000))?;
+            let account = manager.get_account_mut(&growth).ok_or("missing account")?;
+            account.submit_order(Order::market(qqq.clone(), OrderSide::Buy, Quantity::from_f64(110.0)))?;
+            account.process_open_orders(&market_data)?;
+        }
+        if week == 5 {
+            println!("Week {}: sell 100 shares and withdraw $40,000", week + 1);
+            let account = manager.get_account_mut(&growth).ok_or("missing account")?;
+            account.submit_order(Order::market(qqq.clone(), OrderSide::Sell, Quantity::from_f64(100.0)))?;
+            account.process_open_orders(&market_data)?;
+            account.withdraw(Decimal::from(40_000))?;
+            account.record_snapshot(SnapshotKind::Manual, &market_data)?;
+        }
+    }
+
+    let account = manager.get_account(&growth).ok_or("missing account")?;
+    println!("\nLedger:");
+    for entry in account.cash_ledger.iter().filter(|entry| entry.is_external_flow()) {
+        println!("  {} {:?} {}", entry.timestamp.date_naive(), entry.kind, entry.amount);
+    }
+
+    let performance = account.performance(&market_data)?;
+    println!("\nEquity ${:.2}, net external flows ${}", performance.equity, performance.net_external_flows);
+    println!("ROI against contributed capital: {:.2}%", performance.roi);
+
+    let report = returns_between(&account.equity_history, &account.cash_ledger, start, clock.now()).ok_or("not enough history")?;
+    println!("Time-weighted return:   {:>7.2}%", report.time_weighted_return * 100.0);
+    println!("Money-weighted return:  {:>7.2}% annualized", report.money_weighted_return.unwrap_or_default() * 100.0);
+
+    // Any sub-range works, e.g. the weeks after the transfer
+    let after = returns_between(&account.equity_history, &account.cash_ledger, start + Duration::days(21), clock.now()).ok_or("not enough history")?;
+    println!(
+        "From {}: TWR {:.2}%, net flows ${}",
+        after.start.date_naive(),
+        after.time_weighted_return * 100.0,
+        after.net_external_flows
+    );
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use na_paper_account::{
+    account::Account,
+    error::Error as PaperError,
+    market::SimpleMarketDataProvider,
+    Order, OrderSide, Price, Quantity, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Client Order IDs Example");
+
+    let mut account = Account::new("Tagged Orders", "USD", Decimal::from(100_000));
+    let aapl = Symbol::new("AAPL");
+    let mut market_data = SimpleMarketDataProvider::new();
+    market_data.set_price(aapl.clone(), Price::from_f64(190.0));
+
+    // Tag orders with a client ID, a strategy and free-form metadata
+    let entry = Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(100.0))
+        .with_client_order_id("breakout-0001")
+        .with_strategy("breakout")
+        .with_metadata("signal", "52w-high")
+        .with_metadata("desk", "equities");
+    account.submit_order(entry)?;
+
+    // Client order IDs are unique within the account
+    let duplicate = Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(10.0))
+        .with_client_order_id("breakout-0001");
+    match account.submit_order(duplicate) {
+        Err(PaperError::DuplicateClientOrderId { client_order_id }) => {
+            println!("Rejected duplicate client order ID: {}", client_order_id);
+        }
+        other => println!("Unexpected result: {:?}", other),
+    }
+
+    account.process_open_orders(&market_data)?;
+
+    market_data.set_price(aapl.clone(), Price::from_f64(194.0));
+    let exit = Order::limit(aapl.clone(), OrderSide::Sell, Quantity::from_f64(100.0), Price::from_f64(193.0))
+        .with_client_order_id("breakout-0002")
+        .with_strategy("breakout")
+        .with_metadata("signal", "target");
+    account.submit_order(exit)?;
+    account.process_open_orders(&market_data)?;
+
+    // Look orders and trades up by their tags
+    if let Some(order) = account.get_order_by_client_id("breakout-0001") {
+        println!("\nOrder breakout-0001: {} {:?} {} ({:?})", order.id, order.side, order.quantity, order.status);
+    }
+    for trade in account.trades_for_client_order_id("breakout-0002") {
+        println!(
+            "Trade {}: {:?} {} @ {} client ID {:?} strategy {:?} metadata {:?}",
+            trade.id, trade.side, trade.quantity, trade.price, trade.client_order_id, trade.strategy, trade.metadata
+        );
+    }
+    println!("Orders for strategy 'breakout': {}", account.orders_for_strategy("breakout").len());
+    println!("Orders with signal=52w-high: {}", account.orders_with_metadata("signal", "52w-high").len());
+
+    // Tags survive a save and load, and uniqueness is still enforced afterwards
+    let saved = serde_json::to_string(&account)?;
+    let mut reloaded: Account = serde_json::from_str(&saved)?;
+    let retry = Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(1.0))
+        .with_client_order_id("breakout-0002");
+    println!(
+        "\nReloaded account: breakout-0002 found = {}, resubmitting it fails = {}",
+        reloaded.get_order_by_client_id("breakout-0002").is_some(),
+        reloaded.submit_order(retry).is_err()
+    );
+
+    Ok(())
+}
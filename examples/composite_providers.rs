@@ -0,0 +1,55 @@
+use chrono::Duration;
+use na_paper_account::{
+    account::Account,
+    composite::{CachedProvider, FallbackProvider, OverrideProvider, SymbolRouter},
+    market::{MarketDataProvider, SimpleMarketDataProvider},
+    Order, OrderSide, Price, Quantity, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Composite Providers Example");
+
+    let aapl = Symbol::new("AAPL");
+    let msft = Symbol::new("MSFT");
+    let btc = Symbol::new("BTC-USD");
+
+    // Equities come from a primary feed, with a backup for symbols it lacks
+    let mut primary = SimpleMarketDataProvider::new();
+    primary.set_price(aapl.clone(), Price::from_f64(175.50));
+    let mut backup = SimpleMarketDataProvider::new();
+    backup.set_price(aapl.clone(), Price::from_f64(175.40));
+    backup.set_price(msft.clone(), Price::from_f64(420.10));
+    let equities = FallbackProvider::new().with_provider(primary).with_provider(backup);
+
+    // Crypto comes from its own feed
+    let mut crypto = SimpleMarketDataProvider::new();
+    crypto.set_price(btc.clone(), Price::from_f64(64_250.0));
+
+    let router = SymbolRouter::new()
+        .with_route(|symbol: &Symbol| symbol.0.ends_with("-USD"), crypto)
+        .with_default(equities);
+
+    // Cache quotes for a second and allow manual test prices on top
+    let mut market_data = OverrideProvider::new(CachedProvider::new(router, Duration::seconds(1)));
+
+    for symbol in [&aapl, &msft, &btc] {
+        println!("{}: ${}", symbol, market_data.get_quote(symbol)?.last);
+    }
+
+    let mut account = Account::new("Composite Demo", "USD", Decimal::from(100_000));
+    account.submit_order(Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(10.0)))?;
+    account.submit_order(Order::market(btc.clone(), OrderSide::Buy, Quantity::from_f64(0.5)))?;
+    account.process_open_orders(&market_data)?;
+    println!("\nEquity: ${}", account.equity(&market_data)?.round_dp(2));
+
+    // Pin AAPL to a test price without touching the feeds
+    market_data.set_price(&aapl, Price::from_f64(150.0));
+    println!("Equity with AAPL overridden to $150: ${}", account.equity(&market_data)?.round_dp(2));
+
+    market_data.clear_override(&aapl);
+    println!("Equity with the override cleared: ${}", account.equity(&market_data)?.round_dp(2));
+
+    Ok(())
+}
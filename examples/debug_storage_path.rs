@@ -1,5 +1,4 @@
 use rust_decimal_macros::dec;
-use std::path::PathBuf;
 
 use na_paper_account::{
     AccountManager, Config, init_with_config, init_logger
@@ -0,0 +1,64 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    account::Account,
+    market::SimpleMarketDataProvider,
+    Config, Order, OrderSide, Price, Quantity, SimulatedClock, SnapshotKind, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Equity Curve Example");
+
+    let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap());
+    let config = Config {
+        clock: Some(clock.shared()),
+        snapshot_on_fill: true,
+        snapshot_interval: Some(Duration::days(1)),
+        max_snapshots: Some(1_000),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Equity Curve Demo", "USD", Decimal::from(50_000), config);
+
+    let aapl = Symbol::new("AAPL");
+    let msft = Symbol::new("MSFT");
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    market_data.set_price(aapl.clone(), Price::from_f64(185.0));
+    market_data.set_price(msft.clone(), Price::from_f64(370.0));
+
+    // Fills are snapshotted as they happen
+    account.submit_order(Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(100.0)))?;
+    account.submit_order(Order::market(msft.clone(), OrderSide::Buy, Quantity::from_f64(40.0)))?;
+    account.process_open_orders(&market_data)?;
+
+    // Daily snapshots follow the account clock
+    let closes = [(184.2, 367.8), (181.9, 372.1), (181.2, 368.5), (185.6, 375.0), (186.2, 382.8)];
+    for (day, (aapl_close, msft_close)) in closes.iter().enumerate() {
+        clock.advance(Duration::days(1));
+        market_data.set_price(aapl.clone(), Price::from_f64(*aapl_close));
+        market_data.set_price(msft.clone(), Price::from_f64(*msft_close));
+        if day == 3 {
+            account.submit_order(Order::market(aapl.clone(), OrderSide::Sell, Quantity::from_f64(50.0)))?;
+        }
+        account.process_open_orders(&market_data)?;
+    }
+    account.record_snapshot(SnapshotKind::Manual, &market_data)?;
+
+    println!("\nEquity curve:");
+    for snapshot in account.equity_history.snapshots() {
+        println!(
+            "  {} {:<8} equity ${:.2} (cash ${:.2}, gross ${:.2}, net ${:.2})",
+            snapshot.timestamp.format("%Y-%m-%d %H:%M"),
+            format!("{:?}", snapshot.kind),
+            snapshot.equity,
+            snapshot.cash,
+            snapshot.gross_exposure,
+            snapshot.net_exposure
+        );
+    }
+
+    println!("\nCSV export:");
+    account.equity_history.write_csv(std::io::stdout())?;
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use na_paper_account::{
+    account::Account,
+    market::SimpleMarketDataProvider,
+    Config, Instrument, InstrumentRegistry, Order, OrderSide, Price, Quantity, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+use std::sync::Arc;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Exposure Report Example");
+
+    // Instrument metadata drives the asset class and sector groupings
+    let instruments = InstrumentRegistry::new()
+        .with_instrument(Instrument::new(Symbol::new("AAPL")).with_asset_class("equity").with_sector("technology"))
+        .with_instrument(Instrument::new(Symbol::new("MSFT")).with_asset_class("equity").with_sector("technology"))
+        .with_instrument(Instrument::new(Symbol::new("XOM")).with_asset_class("equity").with_sector("energy"))
+        .with_instrument(Instrument::new(Symbol::new("BTC/USD")).with_asset_class("crypto"));
+    let config = Config {
+        instruments: Some(Arc::new(instruments)),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Multi-Asset", "USD", Decimal::from(150_000), config);
+
+    let mut market_data = SimpleMarketDataProvider::new();
+    let prices = [("AAPL", 190.0), ("MSFT", 420.0), ("XOM", 115.0), ("BTC/USD", 65_000.0)];
+    for (symbol, price) in prices {
+        market_data.set_price(Symbol::new(symbol), Price::from_f64(price));
+    }
+
+    let orders = [
+        ("AAPL", OrderSide::Buy, 200.0),
+        ("MSFT", OrderSide::Buy, 100.0),
+        ("XOM", OrderSide::Buy, 250.0),
+        ("BTC/USD", OrderSide::Buy, 0.5),
+    ];
+    for (symbol, side, quantity) in orders {
+        account.submit_order(Order::market(Symbol::new(symbol), side, Quantity::from_f64(quantity)))?;
+    }
+    account.process_open_orders(&market_data)?;
+
+    // Reprice and rerun the report, as a risk monitor would on each tick
+    for (tick, move_pct) in [0.0, 2.0, -3.0].into_iter().enumerate() {
+        for (symbol, price) in prices {
+            market_data.set_price(Symbol::new(symbol), Price::from_f64(price * (1.0 + move_pct / 100.0)));
+        }
+        let report = account.exposure(&market_data)?;
+        println!(
+            "\nTick {}: equity {:.2}  long {:.2}  short {:.2}  gross {:.2}  net {:.2}",
+            tick,
+            report.equity,
+            report.total.long_market_value,
+            report.total.short_market_value,
+            report.total.gross_exposure,
+            report.total.net_exposure
+        );
+        println!(
+            "  leverage {:.2}x  largest {} at {:.1}% of equity",
+            report.leverage.unwrap_or_default(),
+            report.largest_position.as_ref().map_or("-".to_string(), |symbol| symbol.to_string()),
+            report.concentration.unwrap_or_default()
+        );
+    }
+
+    let report = account.exposure(&market_data)?;
+    println!("\nPositions:");
+    for position in &report.positions {
+        println!(
+            "  {:<8} {:>8} @ {:>10.2}  value {:>11.2}  weight {:>6.1}%",
+            position.symbol.to_string(), position.quantity.to_string(), position.price.0, position.market_value, position.weight
+        );
+    }
+    println!("\nBy asset class:");
+    for (asset_class, group) in &report.by_asset_class {
+        println!("  {:<12} gross {:>11.2}  net {:>11.2}  {:>6.1}%", asset_class, group.gross_exposure, group.net_exposure, group.percent_of_equity);
+    }
+    println!("\nBy sector:");
+    for (sector, group) in &report.by_sector {
+        println!("  {:<12} gross {:>11.2}  net {:>11.2}  {:>6.1}%", sector, group.gross_exposure, group.net_exposure, group.percent_of_equity);
+    }
+
+    Ok(())
+}
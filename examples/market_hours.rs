@@ -0,0 +1,85 @@
+use std::sync::Arc;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use na_paper_account::{
+    account::Account,
+    market::SimpleMarketDataProvider,
+    Clock, ClosedMarketAction, Config, Instrument, InstrumentRegistry, Order, OrderSide, Price, Quantity,
+    SimulatedClock, Symbol, TimeInForce, TradingCalendar,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn new_york(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    New_York
+        .with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn print_orders(account: &Account) {
+    println!(
+        "  open: {}, history: {:?}",
+        account.open_orders().len(),
+        account
+            .order_history
+            .iter()
+            .map(|order| (order.order_type, order.status))
+            .collect::<Vec<_>>()
+    );
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Market Hours Example");
+
+    let calendar = Arc::new(TradingCalendar::us_equities().with_holidays_file("examples/data/us_holidays.txt")?);
+    let aapl = Symbol::new("AAPL");
+    let btc = Symbol::new("BTC-USD");
+    let instruments = InstrumentRegistry::new()
+        .with_instrument(Instrument::new(aapl.clone()).with_calendar(calendar.clone()))
+        .with_instrument(Instrument::new(btc.clone()));
+
+    // Wednesday July 3rd 2024 closes early at 13:00, and the 4th is a holiday
+    let clock = SimulatedClock::new(new_york(2024, 7, 3, 8, 0));
+    let config = Config {
+        clock: Some(clock.shared()),
+        instruments: Some(Arc::new(instruments)),
+        closed_market_action: ClosedMarketAction::Queue,
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Market Hours Demo", "USD", Decimal::from(100_000), config);
+
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    market_data.set_price(aapl.clone(), Price::from_f64(220.0));
+    market_data.set_price(btc.clone(), Price::from_f64(60_000.0));
+
+    println!("\n08:00 pre-market ({:?})", calendar.phase_at(clock.now()));
+    account.submit_order(Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(10.0)))?;
+    account.submit_order(Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(5.0)).with_extended_hours(true))?;
+    account.submit_order(Order::market(btc.clone(), OrderSide::Buy, Quantity::from_f64(0.1)))?;
+    account.submit_order(
+        Order::limit(aapl.clone(), OrderSide::Buy, Quantity::from_f64(10.0), Price::from_f64(200.0))
+            .with_time_in_force(TimeInForce::Day),
+    )?;
+    account.process_open_orders(&market_data)?;
+    println!("Extended-hours and crypto orders fill, the regular market order waits:");
+    print_orders(&account);
+
+    clock.set(new_york(2024, 7, 3, 9, 30));
+    account.process_open_orders(&market_data)?;
+    println!("\n09:30 open: the queued market order fills");
+    print_orders(&account);
+
+    clock.set(new_york(2024, 7, 3, 13, 0));
+    account.process_open_orders(&market_data)?;
+    println!("\n13:00 early close: the DAY limit order expires");
+    print_orders(&account);
+
+    if let Some(open) = calendar.next_open(clock.now(), false) {
+        println!("\nNext regular open: {}", open.with_timezone(&New_York));
+    }
+    clock.advance(Duration::days(1));
+    println!("Independence Day phase: {:?}", calendar.phase_at(clock.now()));
+
+    Ok(())
+}
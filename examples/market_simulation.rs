@@ -1,7 +1,9 @@
+use chrono::{Duration as ChronoDuration, Utc};
 use na_paper_account::{
     account::Account,
     market::SimpleMarketDataProvider,
     order::{Order, OrderSide},
+    synthetic::{PriceModel, SyntheticAsset, SyntheticMarket},
     types::{Symbol, Price, Quantity},
 };
 use rust_decimal::Decimal;
@@ -47,19 +49,24 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Simulate market price movements
     println!("\n--- Simulating market price movements ---");
     
-    // Define price points for our simulation
-    let price_points = vec![
-        49800.0, 49600.0, 49400.0, 49200.0, 49000.0, 
-        49300.0, 49700.0, 50100.0, 50500.0, 51200.0,
-        51500.0, 51300.0, 50800.0, 50200.0, 49900.0,
-    ];
+    // Generate hourly prices from a seeded geometric Brownian motion
+    let mut generator = SyntheticMarket::new(6, Utc::now(), ChronoDuration::hours(1)).with_asset(
+        SyntheticAsset::new(
+            "BTC/USD",
+            50000.0,
+            PriceModel::GeometricBrownianMotion {
+                drift: 0.0,
+                volatility: 0.8,
+            },
+        ),
+    )?;
     
     let mut sell_order_placed = false;
     
     // Run the simulation
-    for (i, price) in price_points.iter().enumerate() {
+    for i in 0..15 {
         // Update the market price
-        let current_price = Price::from_f64(*price);
+        let current_price = generator.next_quotes()[0].last;
         market_data.set_price(btc.clone(), current_price);
         
         println!("\nTime step {}: BTC/USD price is now ${}", i + 1, current_price);
@@ -15,6 +15,7 @@ fn main() -> na_paper_account::error::Result<()> {
         commission_rate: Decimal::from_str("0.002")?,    // 0.2% default commission
         log_level: "info".to_string(),
         storage_path: None,
+        ..Config::default()
     };
     
     // Initialize the library with custom config
@@ -0,0 +1,69 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    account::Account,
+    book::{BookSide, OrderBook, OrderBookProvider, Replenishment, SyntheticBookConfig},
+    Config, Order, OrderSide, Price, Quantity, SimulatedClock, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn print_asks(book: &OrderBook) {
+    for level in book.levels(BookSide::Ask).iter().take(4) {
+        println!("  ask {} x {}", level.price, level.size);
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Order Book Example");
+
+    let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 14, 30, 0).unwrap());
+    let aapl = Symbol::new("AAPL");
+
+    // Ten levels per side, 100 shares at the touch and 50 more per level
+    let book_config = SyntheticBookConfig {
+        tick_size: Decimal::new(5, 2),
+        ..SyntheticBookConfig::default()
+    };
+    let market_data = OrderBookProvider::new()
+        .with_clock(clock.shared())
+        .with_replenishment(Replenishment::Rate {
+            fraction_per_second: Decimal::new(1, 1),
+        });
+    market_data.set_book(OrderBook::synthetic(aapl.clone(), Price::from_f64(180.0), &book_config));
+
+    let config = Config {
+        clock: Some(clock.shared()),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Order Book Demo", "USD", Decimal::from(100_000), config);
+
+    println!("\nBook before the order:");
+    print_asks(&market_data.book(&aapl)?);
+
+    // A large market buy sweeps several levels
+    let order_id = account.submit_order(Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(400.0)))?;
+    account.process_open_orders(&market_data)?;
+
+    let order = account
+        .order_history
+        .iter()
+        .find(|order| order.id == order_id)
+        .ok_or("order was not filled")?;
+    println!("\nFills:");
+    for trade in &order.trades {
+        println!("  {} at ${}", trade.quantity, trade.price);
+    }
+    if let Some(average) = order.average_fill_price() {
+        println!("Average fill: ${}", average.0.round_dp(4));
+    }
+
+    println!("\nBook after the sweep:");
+    print_asks(&market_data.book(&aapl)?);
+
+    // Liquidity comes back at 10% per second
+    clock.advance(Duration::seconds(5));
+    println!("\nBook five seconds later:");
+    print_asks(&market_data.book(&aapl)?);
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    market::SimpleMarketDataProvider,
+    AccountManager, Config, Order, OrderQuery, OrderSide, OrderStatus, OrderType, Price, Quantity,
+    SimulatedClock, SortDirection, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Order Queries Example");
+
+    let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 6, 3, 14, 0, 0).unwrap());
+    let config = Config {
+        clock: Some(clock.shared()),
+        ..Config::default()
+    };
+    let mut manager = AccountManager::new();
+    let growth = manager.create_account_with_config("Growth", "USD", Decimal::from(100_000), config.clone())?;
+    let value = manager.create_account_with_config("Value", "USD", Decimal::from(100_000), config)?;
+
+    let symbols = [Symbol::new("AAPL"), Symbol::new("MSFT"), Symbol::new("XOM")];
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+
+    // Trade a few symbols in both accounts over a week
+    for day in 0..5 {
+        for (i, symbol) in symbols.iter().enumerate() {
+            market_data.set_price(symbol.clone(), Price::from_f64(100.0 + (day * 3 + i as i64) as f64));
+        }
+        for (account_id, strategy) in [(growth, "momentum"), (value, "value")] {
+            let account = manager.get_account_mut(&account_id).unwrap();
+            let symbol = &symbols[(day as usize / 2) % symbols.len()];
+            let side = if day % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell };
+            account.submit_order(
+                Order::market(symbol.clone(), side, Quantity::from_f64(10.0)).with_strategy(strategy),
+            )?;
+            // A resting limit order that never fills
+            account.submit_order(
+                Order::limit(symbol.clone(), OrderSide::Buy, Quantity::from_f64(5.0), Price::from_f64(50.0))
+                    .with_strategy(strategy),
+            )?;
+            account.process_open_orders(&market_data)?;
+        }
+        clock.advance(Duration::days(1));
+    }
+
+    // Filled market orders for one strategy, newest first
+    let account = manager.get_account(&growth).unwrap();
+    let query = OrderQuery::new()
+        .with_strategy("momentum")
+        .with_status(OrderStatus::Filled)
+        .with_order_type(OrderType::Market)
+        .with_direction(SortDirection::Descending);
+    println!("\nFilled momentum orders (newest first):");
+    for order in account.query_orders(&query).items {
+        println!("  {} {:?} {} {}", order.created_at.date_naive(), order.side, order.quantity, order.symbol);
+    }
+
+    // Page through every trade in the manager, two at a time
+    println!("\nAll trades, two per page:");
+    let mut query = OrderQuery::new().with_limit(2);
+    let mut page_number = 1;
+    loop {
+        let page = manager.query_trades(&query);
+        for (account_id, trade) in &page.items {
+            let name = &manager.get_account(account_id).unwrap().name;
+            println!(
+                "  page {} {:<6} {} {:?} {} {} @ {}",
+                page_number, name, trade.timestamp.date_naive(), trade.side, trade.quantity, trade.symbol, trade.price
+            );
+        }
+        match page.next_cursor {
+            Some(cursor) => query = query.with_cursor(cursor),
+            None => break,
+        }
+        page_number += 1;
+    }
+
+    // Open buy limit orders for one symbol in a time range, across accounts
+    let start = Utc.with_ymd_and_hms(2024, 6, 4, 0, 0, 0).unwrap();
+    let query = OrderQuery::new()
+        .with_symbol(Symbol::new("MSFT"))
+        .with_side(OrderSide::Buy)
+        .with_order_type(OrderType::Limit)
+        .with_since(start)
+        .with_until(start + Duration::days(3));
+    println!("\nResting MSFT limit orders from {}:", start.date_naive());
+    for (account_id, order) in manager.query_orders(&query).items {
+        let name = &manager.get_account(&account_id).unwrap().name;
+        println!("  {:<6} {} {:?} @ {}", name, order.created_at.date_naive(), order.status, order.limit_price.unwrap());
+    }
+
+    let traded: Decimal = manager
+        .get_account(&value)
+        .unwrap()
+        .trades()
+        .map(|trade| trade.quantity.0)
+        .sum();
+    println!("\nShares traded by the value account: {}", traded);
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use chrono::{Duration, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use na_paper_account::{
+    account::Account,
+    market::SimpleMarketDataProvider,
+    Config, Order, OrderSide, Price, Quantity, SimulatedClock, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("P&L Attribution Example");
+
+    let start = Utc.with_ymd_and_hms(2024, 5, 6, 14, 0, 0).unwrap();
+    let clock = SimulatedClock::new(start);
+    let config = Config {
+        clock: Some(clock.shared()),
+        commission_rate: Decimal::new(5, 4),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Multi-Strategy", "USD", Decimal::from(250_000), config);
+
+    let aapl = Symbol::new("AAPL");
+    let tsla = Symbol::new("TSLA");
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    market_data.set_price(aapl.clone(), Price::from_f64(182.0));
+    market_data.set_price(tsla.clone(), Price::from_f64(180.0));
+
+    // Two strategies share the account; one manual trade is untagged
+    let buy = |symbol: &Symbol, quantity: f64| Order::market(symbol.clone(), OrderSide::Buy, Quantity::from_f64(quantity));
+    let sell = |symbol: &Symbol, quantity: f64| Order::market(symbol.clone(), OrderSide::Sell, Quantity::from_f64(quantity));
+
+    account.submit_order(buy(&aapl, 200.0).with_strategy("momentum"))?;
+    account.submit_order(buy(&tsla, 100.0).with_strategy("mean-reversion"))?;
+    account.submit_order(buy(&aapl, 50.0))?;
+    account.process_open_orders(&market_data)?;
+
+    clock.advance(Duration::days(1));
+    market_data.set_price(aapl.clone(), Price::from_f64(185.5));
+    market_data.set_price(tsla.clone(), Price::from_f64(171.0));
+    account.submit_order(sell(&aapl, 100.0).with_strategy("momentum"))?;
+    account.submit_order(buy(&tsla, 100.0).with_strategy("mean-reversion"))?;
+    account.process_open_orders(&market_data)?;
+
+    clock.advance(Duration::days(1));
+    market_data.set_price(aapl.clone(), Price::from_f64(184.0));
+    market_data.set_price(tsla.clone(), Price::from_f64(168.0));
+    account.submit_order(sell(&tsla, 150.0).with_strategy("mean-reversion"))?;
+    account.process_open_orders(&market_data)?;
+
+    let attribution = account.pnl_attribution_in(&market_data, New_York)?;
+    let row = |name: &str, breakdown: &na_paper_account::PnlBreakdown| {
+        println!(
+            "  {:<16} realized {:>10.2}  unrealized {:>10.2}  commission {:>7.2}  net {:>10.2}",
+            name, breakdown.realized_pnl, breakdown.unrealized_pnl, breakdown.commission, breakdown.net_pnl()
+        );
+    };
+
+    println!("\nBy strategy:");
+    for (strategy, breakdown) in &attribution.by_strategy {
+        row(strategy, breakdown);
+    }
+    row("(untagged)", &attribution.untagged);
+
+    println!("\nBy symbol:");
+    for (symbol, breakdown) in &attribution.by_symbol {
+        row(symbol, breakdown);
+    }
+
+    println!("\nBy side:");
+    row("long", &attribution.long);
+    row("short", &attribution.short);
+
+    println!("\nBy day (New York, realized and commission only):");
+    for (day, breakdown) in &attribution.by_day {
+        row(&day.to_string(), breakdown);
+    }
+
+    println!();
+    row("total", &attribution.total);
+    println!("  account total P&L {:.2}", account.total_realized_pnl() + account.total_unrealized_pnl(&market_data)?);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use na_paper_account::{
+    market::{Quote, SimpleMarketDataProvider},
+    tick::TickEvent,
+    AccountManager, Order, OrderSide, Price, Quantity, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Price Subscriptions Example");
+
+    let aapl = Symbol::new("AAPL");
+    let msft = Symbol::new("MSFT");
+
+    let mut manager = AccountManager::new();
+    let momentum = manager.create_account("Momentum", "USD", Decimal::from(50_000))?;
+    let value = manager.create_account("Value", "USD", Decimal::from(50_000))?;
+
+    // Each account only hears about the symbols it trades
+    manager.subscribe(&momentum, &aapl)?;
+    manager.subscribe(&value, &aapl)?;
+    manager.subscribe(&value, &msft)?;
+
+    let mut market_data = SimpleMarketDataProvider::new();
+    market_data.set_price(aapl.clone(), Price::from_f64(175.0));
+    market_data.set_price(msft.clone(), Price::from_f64(420.0));
+
+    if let Some(account) = manager.get_account_mut(&momentum) {
+        account.submit_order(Order::stop(aapl.clone(), OrderSide::Buy, Quantity::from_f64(50.0), Price::from_f64(178.0)))?;
+    }
+    if let Some(account) = manager.get_account_mut(&value) {
+        account.submit_order(Order::limit(aapl.clone(), OrderSide::Buy, Quantity::from_f64(50.0), Price::from_f64(172.0)))?;
+        account.submit_order(Order::limit(msft.clone(), OrderSide::Buy, Quantity::from_f64(20.0), Price::from_f64(415.0)))?;
+    }
+
+    // Publishing a quote evaluates only the subscribers' open orders for that symbol
+    let updates = [(&aapl, 178.5), (&msft, 414.0), (&aapl, 171.5)];
+    for (symbol, price) in updates {
+        let price = Price::from_f64(price);
+        let event = TickEvent::quote(Quote::new(symbol.clone(), price, price, price));
+        let processed = manager.publish_tick(&mut market_data, &event)?;
+        println!("\n{} -> ${} ({} accounts notified)", symbol, price, processed);
+
+        for (name, id) in [("Momentum", &momentum), ("Value", &value)] {
+            let account = manager.get_account(id).ok_or("account not found")?;
+            println!(
+                "  {}: {} open orders, {} filled",
+                name,
+                account.open_orders().len(),
+                account.order_history.len()
+            );
+        }
+    }
+
+    Ok(())
+}
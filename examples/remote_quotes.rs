@@ -0,0 +1,60 @@
+use std::time::Duration;
+use na_paper_account::{
+    account::Account,
+    loader::TimestampFormat,
+    market::MarketDataProvider,
+    remote::{HttpQuoteProvider, QuoteFields, QuoteRecording, StubServer, WebSocketQuoteProvider},
+    Order, OrderSide, Quantity, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Remote Quotes Example");
+
+    // The recorded feed uses short field names and millisecond timestamps
+    let fields = QuoteFields {
+        symbol: "s".to_string(),
+        bid: "b".to_string(),
+        ask: "a".to_string(),
+        last: Some("p".to_string()),
+        timestamp: Some("t".to_string()),
+        timestamp_format: TimestampFormat::UnixMillis,
+        ..QuoteFields::default()
+    };
+
+    let recording = QuoteRecording::from_file("examples/data/recorded_quotes.jsonl")?;
+    let server = StubServer::start(recording, &fields)?;
+    println!("Stub server listening on {}", server.address());
+
+    let aapl = Symbol::new("AAPL");
+    let msft = Symbol::new("MSFT");
+
+    // Polling: each request returns the symbol's next recorded quote
+    let http = HttpQuoteProvider::new(server.quote_url()).with_fields(fields.clone());
+    println!("\nHTTP:");
+    for _ in 0..3 {
+        let quote = http.get_quote(&aapl)?;
+        println!("  {} {} / {} at {}", quote.symbol, quote.bid, quote.ask, quote.timestamp);
+    }
+
+    // Streaming: the provider keeps the latest quote per symbol
+    let stream = WebSocketQuoteProvider::connect(&server.stream_url(), fields, None)?;
+    stream.wait_for(&msft, Duration::from_secs(5))?;
+    while stream.is_connected() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    println!("\nWebSocket (latest):");
+    for symbol in [&aapl, &msft] {
+        let quote = stream.get_quote(symbol)?;
+        println!("  {} {} / {} at {}", quote.symbol, quote.bid, quote.ask, quote.timestamp);
+    }
+
+    // Accounts trade against either provider unchanged
+    let mut account = Account::new("Remote Demo", "USD", Decimal::from(50_000));
+    account.submit_order(Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(25.0)))?;
+    account.process_open_orders(&stream)?;
+    println!("\nEquity: ${}", account.equity(&stream)?.round_dp(2));
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    market::SimpleMarketDataProvider,
+    AccountManager, Config, Order, OrderSide, Price, Quantity, SequentialIdGenerator,
+    SimulatedClock, Symbol,
+};
+use rust_decimal_macros::dec;
+
+/// Run a small backtest with a simulated clock and sequential IDs and return the saved JSON
+fn run_backtest() -> Result<String, Box<dyn std::error::Error>> {
+    let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2019, 1, 2, 14, 30, 0).unwrap());
+    let config = Config {
+        clock: Some(clock.shared()),
+        ..Config::default()
+    };
+
+    let mut manager = AccountManager::new()
+        .with_id_generator(Arc::new(SequentialIdGenerator::default()));
+    let account_id = manager.create_account_with_config("Backtest", "USD", dec!(10000), config)?;
+
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    let aapl = Symbol::new("AAPL");
+
+    for price in [157.92, 142.19, 148.26] {
+        market_data.set_price(aapl.clone(), Price::from_f64(price));
+
+        let account = manager.get_account_mut(&account_id).unwrap();
+        // Submitted orders and their trades take IDs from the manager's generator
+        let order_id = account.submit_order(Order::market(aapl.clone(), OrderSide::Buy, Quantity::from_f64(5.0)))?;
+        account.execute_market_order(&order_id, &market_data)?;
+
+        clock.advance(Duration::days(1));
+    }
+
+    Ok(serde_json::to_string_pretty(&manager)?)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("Reproducible Runs Example");
+
+    let first = run_backtest()?;
+    let second = run_backtest()?;
+
+    println!("{}", first);
+    println!("\nIdentical output across runs: {}", first == second);
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    account::Account,
+    market::SimpleMarketDataProvider,
+    synthetic::{PriceModel, SyntheticAsset, SyntheticMarket},
+    Config, Order, OrderSide, Quantity, RiskAnalytics, SimulatedClock, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Risk Report Example");
+
+    let start = Utc.with_ymd_and_hms(2023, 1, 3, 21, 0, 0).unwrap();
+    let clock = SimulatedClock::new(start);
+    let config = Config {
+        clock: Some(clock.shared()),
+        snapshot_interval: Some(Duration::days(1)),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Risk Demo", "USD", Decimal::from(100_000), config);
+
+    // One year of daily prices for a volatile asset
+    let spy = Symbol::new("SPY");
+    let model = PriceModel::GeometricBrownianMotion { drift: 0.08, volatility: 0.25 };
+    let mut market = SyntheticMarket::new(7, start, Duration::days(1))
+        .with_asset(SyntheticAsset::new("SPY", 400.0, model))?;
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    for quote in market.next_quotes() {
+        market_data.set_quote(quote);
+    }
+
+    account.submit_order(Order::market(spy.clone(), OrderSide::Buy, Quantity::from_f64(200.0)))?;
+    for _ in 0..365 {
+        account.process_open_orders(&market_data)?;
+        for quote in market.next_quotes() {
+            market_data.set_quote(quote);
+        }
+        clock.set(market.time());
+    }
+    account.process_open_orders(&market_data)?;
+
+    let analytics = RiskAnalytics::new().with_risk_free_rate(0.04).with_periods_per_year(365.0);
+    let report = analytics.analyze(&account.equity_history)?.ok_or("not enough equity history")?;
+
+    println!("\n{} to {} ({} periods)", report.start.date_naive(), report.end.date_naive(), report.periods);
+    println!("Total return:          {:>8.2}%", report.total_return * 100.0);
+    println!("Annualized return:     {:>8.2}%", report.annualized_return * 100.0);
+    println!("Annualized volatility: {:>8.2}%", report.annualized_volatility * 100.0);
+    println!("Sharpe ratio:          {:>8.2}", report.sharpe_ratio.unwrap_or_default());
+    println!("Sortino ratio:         {:>8.2}", report.sortino_ratio.unwrap_or_default());
+    println!("Calmar ratio:          {:>8.2}", report.calmar_ratio.unwrap_or_default());
+    if let Some(drawdown) = &report.max_drawdown {
+        println!(
+            "Max drawdown:          {:>8.2}% from {} to {}",
+            drawdown.depth * 100.0,
+            drawdown.peak_at.date_naive(),
+            drawdown.trough_at.date_naive()
+        );
+        match drawdown.recovery_time {
+            Some(recovery) => println!("Recovered in {} days ({} days peak to recovery)", recovery.num_days(), drawdown.duration.num_days()),
+            None => println!("Not recovered after {} days", drawdown.duration.num_days()),
+        }
+    }
+
+    println!("\nJSON for dashboards:\n{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
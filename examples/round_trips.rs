@@ -0,0 +1,104 @@
+use chrono::{Duration, TimeZone, Utc};
+use na_paper_account::{
+    account::Account,
+    market::{HistoricalDataPoint, SimpleHistoricalDataProvider, SimpleMarketDataProvider},
+    BarInterval, Config, Order, OrderSide, Price, Quantity, SimulatedClock, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Round Trips Example");
+
+    let start = Utc.with_ymd_and_hms(2024, 3, 4, 20, 0, 0).unwrap();
+    let clock = SimulatedClock::new(start);
+    let config = Config {
+        clock: Some(clock.shared()),
+        commission_rate: Decimal::new(5, 4),
+        ..Config::default()
+    };
+    let mut account = Account::new_with_config("Round Trip Demo", "USD", Decimal::from(100_000), config);
+
+    // Daily (low, high, close) bars
+    let aapl = Symbol::new("AAPL");
+    let msft = Symbol::new("MSFT");
+    let aapl_bars = [(173.0, 176.0, 175.0), (169.0, 174.0, 170.0), (168.5, 172.0, 171.0), (170.0, 174.5, 174.0),
+        (173.0, 178.0, 177.5), (176.0, 180.0, 179.0), (172.0, 178.5, 173.0), (171.0, 175.0, 174.5)];
+    let msft_bars = [(410.0, 416.0, 415.0), (408.0, 417.0, 409.0), (400.0, 410.0, 402.0), (398.0, 406.0, 405.0),
+        (404.0, 412.0, 411.0), (409.0, 418.0, 417.0), (414.0, 421.0, 420.0), (415.0, 422.0, 416.0)];
+
+    let mut history = SimpleHistoricalDataProvider::new();
+    for (symbol, bars) in [(&aapl, &aapl_bars), (&msft, &msft_bars)] {
+        let points = bars
+            .iter()
+            .enumerate()
+            .map(|(day, (low, high, close))| HistoricalDataPoint {
+                symbol: symbol.clone(),
+                open: Price::from_f64(*close),
+                high: Price::from_f64(*high),
+                low: Price::from_f64(*low),
+                close: Price::from_f64(*close),
+                volume: 1_000_000.0,
+                timestamp: start + Duration::days(day as i64),
+            })
+            .collect();
+        history.add_data(symbol, points);
+    }
+
+    // (day, symbol, side, quantity): scale into AAPL, exit in two pieces, then trade MSFT twice
+    let orders = [
+        (0, &aapl, OrderSide::Buy, 100.0),
+        (1, &aapl, OrderSide::Buy, 50.0),
+        (1, &msft, OrderSide::Buy, 40.0),
+        (2, &msft, OrderSide::Sell, 40.0),
+        (3, &msft, OrderSide::Buy, 30.0),
+        (5, &aapl, OrderSide::Sell, 100.0),
+        (6, &msft, OrderSide::Sell, 30.0),
+        (6, &aapl, OrderSide::Sell, 50.0),
+        (7, &aapl, OrderSide::Buy, 20.0),
+    ];
+
+    let mut market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+    for day in 0..aapl_bars.len() {
+        clock.set(start + Duration::days(day as i64));
+        market_data.set_price(aapl.clone(), Price::from_f64(aapl_bars[day].2));
+        market_data.set_price(msft.clone(), Price::from_f64(msft_bars[day].2));
+        for (_, symbol, side, quantity) in orders.iter().filter(|order| order.0 == day) {
+            account.submit_order(Order::market((*symbol).clone(), *side, Quantity::from_f64(*quantity)))?;
+        }
+        account.process_open_orders(&market_data)?;
+    }
+
+    let report = account.round_trips_with_excursions(&history, BarInterval::ONE_DAY)?;
+
+    println!("\nRound trips:");
+    for trip in &report.round_trips {
+        println!(
+            "  {} {:?} {} -> {}: {} units, entry ${:.2}, exit {}, net ${:.2}, MAE {:?}, MFE {:?}",
+            trip.symbol,
+            trip.side,
+            trip.opened_at.date_naive(),
+            trip.closed_at.map_or("open".to_string(), |closed_at| closed_at.date_naive().to_string()),
+            trip.entry_quantity,
+            trip.average_entry_price().map_or(Decimal::ZERO, |price| price.0),
+            trip.average_exit_price().map_or("-".to_string(), |price| format!("${:.2}", price.0)),
+            trip.net_pnl(),
+            trip.mae.map(|mae| mae.round_dp(2)),
+            trip.mfe.map(|mfe| mfe.round_dp(2)),
+        );
+    }
+
+    let stats = &report.stats;
+    println!("\nOverall: {} closed, win rate {:.0}%", stats.count, stats.win_rate * 100.0);
+    println!("  Average win ${:.2}, average loss ${:.2}, expectancy ${:.2}", stats.average_win, stats.average_loss, stats.expectancy);
+    println!("  Profit factor {:.2}", stats.profit_factor.unwrap_or_default());
+    println!("  Longest streaks: {} winning, {} losing", stats.longest_winning_streak, stats.longest_losing_streak);
+    println!("  Average holding period: {} days", stats.average_holding_period.num_days());
+
+    println!("\nBy symbol:");
+    for (symbol, stats) in &report.by_symbol {
+        println!("  {}: {} closed, net ${:.2}", symbol, stats.count, stats.total_net_pnl);
+    }
+
+    Ok(())
+}
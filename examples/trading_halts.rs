@@ -0,0 +1,54 @@
+use na_paper_account::{
+    account::Account,
+    market::SimpleMarketDataProvider,
+    Order, OrderSide, Price, PriceBands, Quantity, Symbol,
+};
+use rust_decimal::Decimal;
+use std::error::Error;
+
+fn print_orders(account: &Account) {
+    println!("  open orders: {}", account.open_orders().len());
+    for order in &account.order_history {
+        match (order.average_fill_price(), &order.reject_reason) {
+            (Some(price), _) => println!("  {:?} {:?} filled at ${}", order.order_type, order.side, price),
+            (None, Some(reason)) => println!("  {:?} {:?} rejected: {}", order.order_type, order.side, reason),
+            (None, None) => println!("  {:?} {:?} {:?}", order.order_type, order.side, order.status),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Trading Halts Example");
+
+    let mut account = Account::new("Halt Demo", "USD", Decimal::from(100_000));
+    let gme = Symbol::new("GME");
+
+    let mut market_data = SimpleMarketDataProvider::new();
+    market_data.set_price(gme.clone(), Price::from_f64(25.0));
+
+    // Limit-up/limit-down bands 10% either side of the reference price
+    market_data.set_price_bands(&gme, PriceBands::around(Price::from_f64(25.0), Decimal::new(10, 2)));
+
+    println!("\nGME halted for volatility");
+    market_data.halt(&gme, "LULD pause");
+    account.submit_order(Order::market(gme.clone(), OrderSide::Buy, Quantity::from_f64(100.0)))?;
+    account.submit_order_to(Order::limit(gme.clone(), OrderSide::Buy, Quantity::from_f64(100.0), Price::from_f64(30.0)), &market_data)?;
+    account.process_open_orders(&market_data)?;
+    print_orders(&account);
+
+    println!("\nTrading resumes at $26.50 with bands re-centred");
+    market_data.resume(&gme);
+    market_data.set_price(gme.clone(), Price::from_f64(26.5));
+    market_data.set_price_bands(&gme, PriceBands::around(Price::from_f64(26.5), Decimal::new(10, 2)));
+    account.process_open_orders(&market_data)?;
+    print_orders(&account);
+
+    println!("\nA limit order inside the bands rests until the price reaches it");
+    account.submit_order_to(Order::limit(gme.clone(), OrderSide::Sell, Quantity::from_f64(100.0), Price::from_f64(28.0)), &market_data)?;
+    account.process_open_orders(&market_data)?;
+    market_data.set_price(gme.clone(), Price::from_f64(28.5));
+    account.process_open_orders(&market_data)?;
+    print_orders(&account);
+
+    Ok(())
+}
@@ -1,14 +1,26 @@
-use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use crate::error::{Result, Error};
-use crate::market::MarketDataProvider;
-use crate::order::{Order, OrderSide, OrderType, Trade};
+use crate::ledger::{CashEntry, CashEntryKind};
+use crate::halt::PriceBands;
+use crate::market::{HistoricalDataProvider, MarketDataProvider};
+use crate::order::{Auction, Order, OrderSide, OrderStatus, OrderType, Trade};
 use crate::position::Position;
-use crate::types::{AccountId, OrderId, Price, Symbol};
-use crate::config::Config;
+use crate::snapshot::{EquityHistory, EquitySnapshot, SnapshotKind};
+use crate::bars::BarInterval;
+use crate::roundtrip::{self, RoundTripReport};
+use crate::attribution::PnlAttribution;
+use crate::query::{OrderQuery, Page};
+use crate::exposure::{ExposureReport, PositionExposure};
+use crate::types::{serialize_sorted, AccountId, OrderId, Price, Quantity, Symbol, TradeId};
+use crate::config::{ClosedMarketAction, Config, InvalidQuoteAction};
+use crate::clock;
+use crate::id::SharedIdGenerator;
 
 /// Represents a paper trading account
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,11 +36,27 @@ pub struct Account {
     /// Initial deposit
     pub initial_deposit: Decimal,
     /// Map of positions by symbol
+    #[serde(serialize_with = "serialize_sorted")]
     pub positions: HashMap<String, Position>,
-    /// Map of open orders by order ID
-    pub open_orders: HashMap<String, Order>,
+    /// Open orders by order ID, indexed by symbol
+    open_orders: OpenOrders,
     /// List of closed orders
     pub order_history: Vec<Order>,
+    /// Order IDs by client order ID, built from the orders on first use
+    #[serde(skip)]
+    client_order_index: Option<HashMap<String, OrderId>>,
+    /// Cash entries posted outside of trading (interest, fees)
+    #[serde(default)]
+    pub cash_ledger: Vec<CashEntry>,
+    /// Timestamp up to which interest has been accrued
+    #[serde(default)]
+    pub last_accrual: Option<DateTime<Utc>>,
+    /// Equity snapshots taken on fills, at intervals and on request
+    #[serde(default)]
+    pub equity_history: EquityHistory,
+    /// Whether an order has filled since the last fill snapshot
+    #[serde(skip)]
+    filled_since_snapshot: bool,
     /// Creation timestamp
     pub created_at: DateTime<Utc>,
     /// Last updated timestamp
@@ -36,12 +64,18 @@ pub struct Account {
     /// Account-specific configuration
     #[serde(skip)]
     pub config: Option<Config>,
+    /// Generator for order and trade IDs (random if none is set)
+    #[serde(skip)]
+    pub id_generator: Option<SharedIdGenerator>,
+    /// Order IDs handed out by `new_order` that haven't been submitted yet
+    #[serde(skip)]
+    issued_order_ids: HashSet<OrderId>,
 }
 
 impl Account {
     /// Create a new paper trading account with an initial deposit
     pub fn new<S: Into<String>>(name: S, base_currency: S, initial_deposit: Decimal) -> Self {
-        let now = Utc::now();
+        let now = clock::now();
         Self {
             id: AccountId::new(),
             name: name.into(),
@@ -49,30 +83,80 @@ impl Account {
             cash_balance: initial_deposit,
             initial_deposit,
             positions: HashMap::new(),
-            open_orders: HashMap::new(),
+            open_orders: OpenOrders::default(),
             order_history: Vec::new(),
+            client_order_index: None,
+            cash_ledger: Vec::new(),
+            last_accrual: None,
+            equity_history: EquityHistory::new(),
+            filled_since_snapshot: false,
             created_at: now,
             updated_at: now,
             config: None,
+            id_generator: None,
+            issued_order_ids: HashSet::new(),
         }
     }
 
+    /// Create a new paper trading account with account-specific configuration
+    ///
+    /// Unlike `Account::new(..).with_config(..)`, the creation timestamp is
+    /// taken from the configuration's clock.
+    pub fn new_with_config<S: Into<String>>(
+        name: S,
+        base_currency: S,
+        initial_deposit: Decimal,
+        config: Config,
+    ) -> Self {
+        let now = config.now();
+        let mut account = Self::new(name, base_currency, initial_deposit).with_config(config);
+        account.created_at = now;
+        account.updated_at = now;
+        account
+    }
+
     /// Set account-specific configuration
     pub fn with_config(mut self, config: Config) -> Self {
         self.config = Some(config);
         self
     }
     
+    /// Get the current time from the account's clock
+    pub fn now(&self) -> DateTime<Utc> {
+        self.get_config().now()
+    }
+    
+    /// Set the generator used for order and trade IDs
+    ///
+    /// Trades and newly created orders get IDs from the generator when they are
+    /// submitted; use `new_order` to know an order's ID before submitting it.
+    pub fn with_id_generator(mut self, id_generator: SharedIdGenerator) -> Self {
+        self.id_generator = Some(id_generator);
+        self
+    }
+
+    /// Prepare an order for this account, assigning its ID from the account's generator
+    ///
+    /// Without a generator the order keeps its random ID. The ID is final, so it
+    /// can be read before the order is submitted.
+    pub fn new_order(&mut self, mut order: Order) -> Order {
+        if let Some(id_generator) = &self.id_generator {
+            order.id = OrderId::generate(id_generator.as_ref());
+            self.issued_order_ids.insert(order.id);
+        }
+        order
+    }
+    
     /// Get the account's configuration, or the global configuration if none is set
     pub fn get_config(&self) -> Config {
-        self.config.clone().unwrap_or_else(|| crate::config::get())
+        self.config.clone().unwrap_or_else(crate::config::get)
     }
 
     /// Get the total equity value of the account (cash + positions)
     pub fn equity<M: MarketDataProvider>(&self, market_data: &M) -> Result<Decimal> {
         let mut equity = self.cash_balance;
 
-        for (_, position) in &self.positions {
+        for position in self.positions.values() {
             if !position.is_flat() {
                 let quote = market_data.get_quote(&position.symbol)?;
                 let position_value = position.market_value(quote.mid());
@@ -83,6 +167,32 @@ impl Account {
         Ok(equity)
     }
 
+    /// Get gross, net, long and short exposure, leverage and concentration
+    ///
+    /// Positions are marked at the quote midpoint and grouped by the asset class
+    /// and sector of the configured instruments.
+    pub fn exposure<M: MarketDataProvider>(&self, market_data: &M) -> Result<ExposureReport> {
+        let instruments = self.get_config().instruments;
+        let mut positions = Vec::with_capacity(self.positions.len());
+        for position in self.positions.values() {
+            if position.is_flat() {
+                continue;
+            }
+            let price = market_data.get_quote(&position.symbol)?.mid();
+            let instrument = instruments.as_ref().and_then(|instruments| instruments.get(&position.symbol));
+            positions.push(PositionExposure {
+                symbol: position.symbol.clone(),
+                quantity: position.quantity,
+                price,
+                market_value: position.market_value(price),
+                weight: Decimal::ZERO,
+                asset_class: instrument.and_then(|instrument| instrument.asset_class.clone()),
+                sector: instrument.and_then(|instrument| instrument.sector.clone()),
+            });
+        }
+        Ok(ExposureReport::new(self.now(), self.cash_balance, positions))
+    }
+
     /// Get a position by symbol
     pub fn get_position(&self, symbol: &Symbol) -> Option<&Position> {
         self.positions.get(&symbol.0)
@@ -107,59 +217,221 @@ impl Account {
         self.open_orders.get(&order_id.0.to_string())
     }
 
-    /// Get a mutable order by ID
-    pub fn get_order_mut(&mut self, order_id: &OrderId) -> Option<&mut Order> {
-        self.open_orders.get_mut(&order_id.0.to_string())
+    /// Get an open or closed order by its client order ID
+    pub fn get_order_by_client_id(&self, client_order_id: &str) -> Option<&Order> {
+        match &self.client_order_index {
+            Some(index) => {
+                let order_id = index.get(client_order_id)?;
+                self.open_orders
+                    .get(&order_id.0.to_string())
+                    .or_else(|| self.order_history.iter().rev().find(|order| order.id == *order_id))
+            }
+            None => self
+                .all_orders()
+                .find(|order| order.client_order_id.as_deref() == Some(client_order_id)),
+        }
+    }
+
+    /// Get the open and closed orders tagged with a strategy
+    pub fn orders_for_strategy(&self, strategy: &str) -> Vec<&Order> {
+        self.all_orders()
+            .filter(|order| order.strategy.as_deref() == Some(strategy))
+            .collect()
+    }
+
+    /// Get the open and closed orders with a metadata entry
+    pub fn orders_with_metadata(&self, key: &str, value: &str) -> Vec<&Order> {
+        self.all_orders()
+            .filter(|order| order.metadata.get(key).is_some_and(|v| v == value))
+            .collect()
+    }
+
+    /// Get the trades executed for a client order ID
+    pub fn trades_for_client_order_id(&self, client_order_id: &str) -> Vec<&Trade> {
+        self.get_order_by_client_id(client_order_id)
+            .map(|order| order.trades.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Find open and closed orders matching a query
+    pub fn query_orders(&self, query: &OrderQuery) -> Page<&Order> {
+        let page = query.select_orders(self.all_orders().map(|order| ((), order)));
+        Page {
+            items: page.items.into_iter().map(|(_, order)| order).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+
+    /// Find trades matching a query
+    pub fn query_trades(&self, query: &OrderQuery) -> Page<&Trade> {
+        let page = query.select_trades(self.all_orders().map(|order| ((), order)));
+        Page {
+            items: page.items.into_iter().map(|(_, trade)| trade).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+
+    /// Iterate over closed orders, then open orders
+    pub(crate) fn all_orders(&self) -> impl Iterator<Item = &Order> {
+        self.order_history.iter().chain(self.open_orders.values())
+    }
+
+    /// Get the open orders by order ID
+    pub fn open_orders(&self) -> &HashMap<String, Order> {
+        &self.open_orders.orders
+    }
+
+    /// Get a mutable open order by ID
+    ///
+    /// The symbol index is updated when the returned guard is dropped.
+    pub fn get_order_mut(&mut self, order_id: &OrderId) -> Option<OrderMut<'_>> {
+        self.open_orders.guard(&order_id.0.to_string())
+    }
+
+    /// Get the open orders for a symbol
+    pub fn open_orders_for_symbol(&self, symbol: &Symbol) -> Vec<&Order> {
+        self.open_orders.for_symbol(symbol).collect()
+    }
+
+    /// Check if the account has open orders for a symbol
+    pub fn has_open_orders_for(&self, symbol: &Symbol) -> bool {
+        !self.open_orders_for_symbol(symbol).is_empty()
+    }
+
+    /// Get the IDs of the open orders in the order they were created (then by ID)
+    fn open_order_ids(&self) -> Vec<OrderId> {
+        creation_order(self.open_orders.values())
+    }
+
+    /// Get the client order ID index, building it from the orders if needed
+    ///
+    /// The index isn't serialized, so it's rebuilt after loading an account.
+    fn client_order_index(&mut self) -> &mut HashMap<String, OrderId> {
+        let orders = self.order_history.iter().chain(self.open_orders.values());
+        self.client_order_index.get_or_insert_with(|| {
+            orders
+                .filter_map(|order| Some((order.client_order_id.clone()?, order.id)))
+                .collect()
+        })
+    }
+
+    /// Remove an order from the open orders
+    fn remove_open_order(&mut self, order_id: &str) -> Option<Order> {
+        self.open_orders.remove(order_id)
     }
 
     /// Submit a new order
+    ///
+    /// Orders in the `Created` state get their ID from the account's generator,
+    /// if one is set, unless the ID was already assigned by `new_order`.
     pub fn submit_order(&mut self, mut order: Order) -> Result<OrderId> {
         // Validate the order
         self.validate_order(&order)?;
+        if let Some(client_order_id) = &order.client_order_id {
+            if self.client_order_index().contains_key(client_order_id) {
+                return Err(Error::DuplicateClientOrderId {
+                    client_order_id: client_order_id.clone(),
+                });
+            }
+        }
 
-        // Update order status
-        order.submit();
+        // Stamp the order with the account clock and update its status
+        let now = self.now();
+        if order.status == OrderStatus::Created {
+            order.created_at = now;
+            if let Some(id_generator) = &self.id_generator {
+                if !self.issued_order_ids.remove(&order.id) {
+                    order.id = OrderId::generate(id_generator.as_ref());
+                }
+            }
+        }
+        order.submit_at(now);
 
         // Store the order
         let order_id = order.id;
-        self.open_orders.insert(order_id.0.to_string(), order);
-        self.updated_at = Utc::now();
+        if let Some(client_order_id) = &order.client_order_id {
+            self.client_order_index().insert(client_order_id.clone(), order_id);
+        }
+        self.open_orders.insert(order);
+        self.updated_at = now;
 
         Ok(order_id)
     }
 
+    /// Submit a new order, rejecting it if its limit price is outside the symbol's price bands
+    ///
+    /// This is how an exchange treats the order on arrival: a rejected order goes
+    /// straight to the order history with the reason. Once accepted, the order
+    /// rests even if the bands later move past its limit price; it just can't
+    /// fill outside them. Auction orders are exempt, as the bands don't apply to auctions.
+    pub fn submit_order_to<M: MarketDataProvider>(&mut self, order: Order, market_data: &M) -> Result<OrderId> {
+        let bands = market_data.price_bands(&order.symbol);
+        let outside = match (bands, order.limit_price) {
+            (Some(bands), Some(limit_price)) if order.order_type.auction().is_none() => {
+                (!bands.contains(limit_price)).then_some((bands, limit_price))
+            }
+            _ => None,
+        };
+
+        let order_id = self.submit_order(order)?;
+        if let Some((bands, limit_price)) = outside {
+            let symbol = &self.open_orders.orders[&order_id.0.to_string()].symbol;
+            let reason = format!(
+                "Limit price {} is outside the price bands {} - {} for {}",
+                limit_price, bands.lower, bands.upper, symbol
+            );
+            self.reject_open_order(&order_id, &reason);
+        }
+        Ok(order_id)
+    }
+
     /// Cancel an order
     pub fn cancel_order(&mut self, order_id: &OrderId) -> Result<()> {
+        let now = self.now();
         let order = self
-            .get_order_mut(order_id)
-            .ok_or_else(|| Error::OrderNotFound {
+            .open_orders
+            .get_mut(&order_id.0.to_string())
+            .ok_or(Error::OrderNotFound {
                 order_id: *order_id,
             })?;
 
-        if order.cancel() {
+        if order.cancel_at(now) {
             // Move to order history
             let order_id_str = order_id.0.to_string();
-            if let Some(order) = self.open_orders.remove(&order_id_str) {
+            if let Some(order) = self.remove_open_order(&order_id_str) {
                 self.order_history.push(order);
             }
-            self.updated_at = Utc::now();
+            self.updated_at = now;
         }
 
         Ok(())
     }
 
     /// Process a market order execution
+    ///
+    /// Records a fill snapshot if the order filled and `snapshot_on_fill` is set.
     pub fn execute_market_order<M: MarketDataProvider>(
         &mut self,
         order_id: &OrderId,
         market_data: &M,
+    ) -> Result<()> {
+        self.fill_market_order(order_id, market_data)?;
+        self.record_fill_snapshot(market_data);
+        Ok(())
+    }
+
+    /// Execute a market order, without recording a fill snapshot
+    fn fill_market_order<M: MarketDataProvider>(
+        &mut self,
+        order_id: &OrderId,
+        market_data: &M,
     ) -> Result<()> {
         let order_id_copy = *order_id;
         
         // Get the order
         let order = self
             .get_order(order_id)
-            .ok_or_else(|| Error::OrderNotFound {
+            .ok_or(Error::OrderNotFound {
                 order_id: order_id_copy,
             })?
             .clone();
@@ -174,6 +446,19 @@ impl Account {
             return Ok(());
         }
 
+        // Don't trade while the market is closed or halted, or against stale or broken quotes
+        if !self.check_session(&order)
+            || !self.check_trading_status(&order, market_data)
+            || !self.check_quote(&order, market_data)?
+        {
+            return Ok(());
+        }
+
+        // Walk the order book if the provider has depth
+        if self.fill_from_book(&order, None, market_data)?.is_some() {
+            return Ok(());
+        }
+
         // Get current market price
         let quote = market_data.get_quote(&order.symbol)?;
         let base_price = match order.side {
@@ -181,13 +466,14 @@ impl Account {
             OrderSide::Sell => quote.bid,
         };
         
+        // Wait for the price to return inside the bands, and keep slippage inside them
+        let bands = market_data.price_bands(&order.symbol);
+        if bands.is_some_and(|bands| !bands.contains(base_price)) {
+            return Ok(());
+        }
+
         // Apply slippage from configuration
-        let config = self.get_config();
-        let slippage_adjustment = Price(base_price.0 * config.default_slippage);
-        let execution_price = match order.side {
-            OrderSide::Buy => Price(base_price.0 + slippage_adjustment.0),
-            OrderSide::Sell => Price(base_price.0 - slippage_adjustment.0),
-        };
+        let execution_price = clamp_to_bands(self.apply_slippage(order.side, base_price), bands);
 
         // Execute the order at market price with slippage
         self.execute_order_at_price(&order_id_copy, execution_price)?;
@@ -196,17 +482,30 @@ impl Account {
     }
 
     /// Process a limit order against the current market price
+    ///
+    /// Records a fill snapshot if the order filled and `snapshot_on_fill` is set.
     pub fn process_limit_order<M: MarketDataProvider>(
         &mut self,
         order_id: &OrderId,
         market_data: &M,
+    ) -> Result<bool> {
+        let result = self.fill_limit_order(order_id, market_data)?;
+        self.record_fill_snapshot(market_data);
+        Ok(result)
+    }
+
+    /// Fill a limit order if the market reaches it, without recording a fill snapshot
+    fn fill_limit_order<M: MarketDataProvider>(
+        &mut self,
+        order_id: &OrderId,
+        market_data: &M,
     ) -> Result<bool> {
         let order_id_copy = *order_id;
         
         // Get the order
         let order = self
             .get_order(order_id)
-            .ok_or_else(|| Error::OrderNotFound {
+            .ok_or(Error::OrderNotFound {
                 order_id: order_id_copy,
             })?
             .clone();
@@ -221,141 +520,554 @@ impl Account {
             return Ok(false);
         }
 
-        // Get current market price
-        let quote = market_data.get_quote(&order.symbol)?;
-
         // Get limit price (should always be present for limit orders)
         let limit_price = order.limit_price.ok_or_else(|| Error::InvalidOrder {
             reason: "Limit order without limit price".to_string(),
         })?;
 
-        // Check if the order can be executed
-        let can_execute = match order.side {
-            OrderSide::Buy => quote.ask.0 <= limit_price.0,
-            OrderSide::Sell => quote.bid.0 >= limit_price.0,
-        };
+        if !self.check_session(&order)
+            || !self.check_trading_status(&order, market_data)
+            || !self.check_quote(&order, market_data)?
+        {
+            return Ok(false);
+        }
 
-        if can_execute {
-            // Execute at the limit price
-            self.execute_order_at_price(&order_id_copy, limit_price)?;
-            Ok(true)
-        } else {
-            Ok(false)
+        // Marketable limit orders walk the book up to the limit price
+        if let Some(filled) = self.fill_from_book(&order, Some(limit_price), market_data)? {
+            return Ok(filled);
+        }
+
+        // Check if the order can be executed
+        let (prices, from_path) = self.evaluation_prices(&order, market_data)?;
+        match limit_fill_price(&prices, order.side, limit_price, from_path) {
+            Some(price) => {
+                self.execute_order_at_price(&order_id_copy, price)?;
+                Ok(true)
+            }
+            None => Ok(false),
         }
     }
 
-    /// Execute an order at a specific price
-    fn execute_order_at_price(&mut self, order_id: &OrderId, price: Price) -> Result<()> {
-        // First, clone the order to avoid borrowing issues
-        let order = match self.get_order(order_id) {
-            Some(order) => order.clone(),
-            None => return Err(Error::OrderNotFound { order_id: *order_id }),
-        };
+    /// Process a stop or stop-limit order against the current market price
+    ///
+    /// Stop orders execute like market orders once the stop price is reached.
+    /// Stop-limit orders are marked as triggered and then behave like limit orders.
+    ///
+    /// Records a fill snapshot if the order filled and `snapshot_on_fill` is set.
+    pub fn process_stop_order<M: MarketDataProvider>(
+        &mut self,
+        order_id: &OrderId,
+        market_data: &M,
+    ) -> Result<bool> {
+        let result = self.fill_stop_order(order_id, market_data)?;
+        self.record_fill_snapshot(market_data);
+        Ok(result)
+    }
 
-        // Check if order is active
+    /// Trigger and fill a stop or stop-limit order, without recording a fill snapshot
+    fn fill_stop_order<M: MarketDataProvider>(
+        &mut self,
+        order_id: &OrderId,
+        market_data: &M,
+    ) -> Result<bool> {
+        let order_id_copy = *order_id;
+        
+        // Get the order
+        let order = self
+            .get_order(order_id)
+            .ok_or(Error::OrderNotFound {
+                order_id: order_id_copy,
+            })?
+            .clone();
+
+        // Only process stop and stop-limit orders
+        if !matches!(order.order_type, OrderType::Stop | OrderType::StopLimit) {
+            return Ok(false);
+        }
+
+        // Only process active orders
         if !order.is_active() {
-            return Ok(());
+            return Ok(false);
         }
 
-        // Get configuration
-        let config = self.get_config();
+        let stop_price = order.stop_price.ok_or_else(|| Error::InvalidOrder {
+            reason: "Stop order without stop price".to_string(),
+        })?;
 
-        // Calculate the trade value and commission
-        let quantity = order.quantity;
-        let value = price.0 * quantity.0;
-        let commission = value * config.commission_rate;
+        if !self.check_session(&order)
+            || !self.check_trading_status(&order, market_data)
+            || !self.check_quote(&order, market_data)?
+        {
+            return Ok(false);
+        }
 
-        // Process order based on side
-        match order.side {
-            OrderSide::Buy => {
-                // Check if we have enough cash
-                let total_cost = value + commission;
-                if self.cash_balance < total_cost {
-                    return Err(Error::InsufficientFunds {
-                        required: total_cost,
-                        available: self.cash_balance,
-                    });
-                }
+        let (prices, from_path) = self.evaluation_prices(&order, market_data)?;
 
-                // Update cash balance
-                self.cash_balance -= total_cost;
+        // A triggered stop-limit order is a plain limit order from here on
+        let remaining = if order.triggered_at.is_some() {
+            prices
+        } else {
+            // Buy stops trigger on rising prices, sell stops on falling prices
+            let rising = order.side == OrderSide::Buy;
+            let (index, trigger_price) = match first_crossing(&prices, stop_price, rising) {
+                Some(crossing) => crossing,
+                None => return Ok(false),
+            };
 
-                // Update position
-                let symbol = order.symbol.clone();
-                self.get_or_create_position(symbol).add(quantity, price);
+            if order.order_type == OrderType::Stop {
+                if let Some(filled) = self.fill_from_book(&order, None, market_data)? {
+                    return Ok(filled);
+                }
+                let bands = market_data.price_bands(&order.symbol);
+                let execution_price = clamp_to_bands(self.apply_slippage(order.side, trigger_price), bands);
+                self.execute_order_at_price(&order_id_copy, execution_price)?;
+                return Ok(true);
             }
-            OrderSide::Sell => {
-                // Check if we have enough of the asset
-                let symbol = order.symbol.clone();
-                let position_quantity = match self.get_position(&symbol) {
-                    Some(position) => position.quantity,
-                    None => {
-                        return Err(Error::InsufficientPosition {
-                            symbol,
-                            required: quantity.0,
-                            available: Decimal::ZERO,
-                        });
-                    }
-                };
 
-                if position_quantity < quantity {
-                    return Err(Error::InsufficientPosition {
-                        symbol,
-                        required: quantity.0,
-                        available: position_quantity.0,
-                    });
-                }
+            let now = self.now();
+            if let Some(order) = self.open_orders.get_mut(&order_id_copy.0.to_string()) {
+                order.trigger_at(now);
+            }
 
-                // Update position
-                self.get_position_mut(&symbol).unwrap().remove(quantity, price);
+            // Continue along the path from the point the stop was reached
+            let mut remaining = vec![trigger_price];
+            remaining.extend_from_slice(&prices[index + 1..]);
+            remaining
+        };
 
-                // Update cash balance
-                self.cash_balance += value - commission;
+        let limit_price = order.limit_price.ok_or_else(|| Error::InvalidOrder {
+            reason: "Stop-limit order without limit price".to_string(),
+        })?;
+
+        if let Some(filled) = self.fill_from_book(&order, Some(limit_price), market_data)? {
+            return Ok(filled);
+        }
+
+        match limit_fill_price(&remaining, order.side, limit_price, from_path) {
+            Some(price) => {
+                self.execute_order_at_price(&order_id_copy, price)?;
+                Ok(true)
             }
+            None => Ok(false),
         }
+    }
 
-        // Create a trade record
-        let trade = Trade::new(
-            *order_id,
-            order.symbol.clone(),
-            order.side,
-            quantity,
-            price,
-            commission,
-        );
+    /// Process an auction order (market/limit on open/close) against its auction
+    ///
+    /// The order executes at the price of the first auction print after it was
+    /// submitted (opening prints at the time the order was submitted count too).
+    /// Limit auction orders that the auction price doesn't satisfy expire, as they
+    /// would at an exchange. Orders whose time in force has elapsed expire only
+    /// after their auction has had the chance to fill them.
+    ///
+    /// Records a fill snapshot if the order filled and `snapshot_on_fill` is set.
+    pub fn process_auction_order<M: MarketDataProvider>(
+        &mut self,
+        order_id: &OrderId,
+        market_data: &M,
+    ) -> Result<bool> {
+        let filled = self.fill_auction_order(order_id, market_data)?;
+        if !filled {
+            self.expire_elapsed_order(order_id);
+        }
+        self.record_fill_snapshot(market_data);
+        Ok(filled)
+    }
 
-        // Update the order
-        if let Some(order) = self.get_order_mut(order_id) {
-            order.execute(trade);
-            
-            // If the order is complete, move it to history
-            if order.is_complete() {
-                let order_id_str = order_id.0.to_string();
-                let order = self.open_orders.remove(&order_id_str);
-                if let Some(order) = order {
+    /// Expire an open order if its time in force has elapsed on the account clock
+    fn expire_elapsed_order(&mut self, order_id: &OrderId) {
+        let config = self.get_config();
+        let now = config.now();
+        let order_id_str = order_id.0.to_string();
+        let elapsed = self
+            .open_orders
+            .get(&order_id_str)
+            .is_some_and(|order| order.is_expired_in(now, config.calendar(&order.symbol)));
+        if elapsed {
+            if let Some(mut order) = self.remove_open_order(&order_id_str) {
+                order.expire_at(now);
+                self.order_history.push(order);
+                self.updated_at = now;
+            }
+        }
+    }
+
+    /// Fill an auction order at its auction, without recording a fill snapshot
+    fn fill_auction_order<M: MarketDataProvider>(
+        &mut self,
+        order_id: &OrderId,
+        market_data: &M,
+    ) -> Result<bool> {
+        let order_id_copy = *order_id;
+
+        // Get the order
+        let order = self
+            .get_order(order_id)
+            .ok_or(Error::OrderNotFound {
+                order_id: order_id_copy,
+            })?
+            .clone();
+
+        // Only process auction orders
+        let auction = match order.order_type.auction() {
+            Some(auction) => auction,
+            None => return Ok(false),
+        };
+
+        // Only process active orders
+        if !order.is_active() {
+            return Ok(false);
+        }
+
+        // Auctions don't run while the symbol is halted
+        if !self.check_trading_status(&order, market_data) {
+            return Ok(false);
+        }
+
+        // Wait for the first auction after the order was submitted; an order
+        // submitted as the session starts is in time for its opening auction
+        let print = match market_data.auction_print(&order.symbol, auction) {
+            Some(print) if print.timestamp > order.created_at => print,
+            Some(print) if auction == Auction::Open && print.timestamp == order.created_at => print,
+            _ => return Ok(false),
+        };
+        if print.price.0 <= Decimal::ZERO {
+            return Err(Error::MarketDataError {
+                reason: format!("Invalid {:?} auction price {} for {}", auction, print.price, order.symbol),
+            });
+        }
+
+        if let Some(limit_price) = order.limit_price {
+            let within_limit = match order.side {
+                OrderSide::Buy => print.price.0 <= limit_price.0,
+                OrderSide::Sell => print.price.0 >= limit_price.0,
+            };
+            if !within_limit {
+                let now = self.now();
+                if let Some(mut order) = self.remove_open_order(&order_id_copy.0.to_string()) {
+                    order.expire_at(now);
                     self.order_history.push(order);
+                    self.updated_at = now;
                 }
+                return Ok(false);
             }
         }
 
-        self.updated_at = Utc::now();
+        self.execute_order_at_price(&order_id_copy, print.price)?;
+        Ok(true)
+    }
 
-        Ok(())
+    /// Validate the current quote for an order's symbol
+    ///
+    /// Returns `false` if the order should not be evaluated. Invalid quotes are
+    /// returned as errors, unless the configuration rejects market orders instead,
+    /// in which case market orders are rejected and resting orders are left open.
+    fn check_quote<M: MarketDataProvider>(&mut self, order: &Order, market_data: &M) -> Result<bool> {
+        let config = self.get_config();
+        let now = self.now();
+        let error = match market_data
+            .get_quote(&order.symbol)
+            .and_then(|quote| quote.validate(now, &config))
+        {
+            Ok(()) => return Ok(true),
+            Err(error @ Error::MarketDataError { .. }) => error,
+            Err(error) => return Err(error),
+        };
+
+        if config.invalid_quote_action == InvalidQuoteAction::Error {
+            return Err(error);
+        }
+
+        if order.order_type == OrderType::Market {
+            self.reject_open_order(&order.id, &error.to_string());
+        }
+
+        Ok(false)
     }
 
-    /// Validate an order before submission
-    fn validate_order(&self, order: &Order) -> Result<()> {
+    /// Check that the order's market is open
+    ///
+    /// Returns `false` if the order should not be evaluated. Extended-hours orders
+    /// may also execute pre- and post-market. Market orders are rejected if the
+    /// configuration says so; otherwise orders wait for the open.
+    fn check_session(&mut self, order: &Order) -> bool {
+        let config = self.get_config();
+        let now = self.now();
+        let calendar = match config.calendar(&order.symbol) {
+            Some(calendar) => calendar,
+            None => return true,
+        };
+        if calendar.is_open(now, order.extended_hours) {
+            return true;
+        }
+
+        if order.order_type == OrderType::Market && config.closed_market_action == ClosedMarketAction::Reject {
+            let reason = format!("Market for {} is closed ({})", order.symbol, calendar.name);
+            self.reject_open_order(&order.id, &reason);
+        }
+
+        false
+    }
+
+    /// Check that the order's symbol is trading
+    ///
+    /// Returns `false` if the order should not be evaluated. Orders wait while the
+    /// symbol is halted. Resting limit orders priced outside the limit-up/limit-down
+    /// bands stay open, as fills are only taken at prices inside the bands.
+    fn check_trading_status<M: MarketDataProvider>(&self, order: &Order, market_data: &M) -> bool {
+        !market_data.trading_status(&order.symbol).is_halted()
+    }
+
+    /// Reject an open order and move it to history
+    fn reject_open_order(&mut self, order_id: &OrderId, reason: &str) {
+        let now = self.now();
+        let order_id_str = order_id.0.to_string();
+        if let Some(order) = self.open_orders.get_mut(&order_id_str) {
+            order.reject_at(reason, now);
+            if order.is_rejected() {
+                let order = self.remove_open_order(&order_id_str);
+                if let Some(order) = order {
+                    self.order_history.push(order);
+                }
+                self.updated_at = now;
+            }
+        }
+    }
+
+    /// Get the prices an order is evaluated against
+    ///
+    /// Uses the provider's intrabar path if the order was resting before the path
+    /// began, otherwise the current ask (buys) or bid (sells). The flag is `true`
+    /// when the prices come from the path.
+    fn evaluation_prices<M: MarketDataProvider>(
+        &self,
+        order: &Order,
+        market_data: &M,
+    ) -> Result<(Vec<Price>, bool)> {
+        // Nothing trades outside the price bands
+        let bands = market_data.price_bands(&order.symbol);
+        let within_bands = |price: &Price| bands.is_none_or(|bands| bands.contains(*price));
+
+        if let Some(path) = market_data.price_path(&order.symbol) {
+            if order.created_at <= path.timestamp && !path.prices.is_empty() {
+                let prices = path.prices.into_iter().filter(within_bands).collect();
+                return Ok((prices, true));
+            }
+        }
+
+        let quote = market_data.get_quote(&order.symbol)?;
+        let price = match order.side {
+            OrderSide::Buy => quote.ask,
+            OrderSide::Sell => quote.bid,
+        };
+        Ok((Some(price).into_iter().filter(within_bands).collect(), false))
+    }
+
+    /// Apply the configured slippage against the side of an order
+    fn apply_slippage(&self, side: OrderSide, price: Price) -> Price {
+        let config = self.get_config();
+        let slippage_adjustment = price.0 * config.default_slippage;
+        match side {
+            OrderSide::Buy => Price(price.0 + slippage_adjustment),
+            OrderSide::Sell => Price(price.0 - slippage_adjustment),
+        }
+    }
+
+    /// Fill an order by walking the provider's order book
+    ///
+    /// Returns `None` if the provider has no book for the symbol, otherwise whether
+    /// anything was filled. Each level taken produces its own trade, and any
+    /// quantity the book cannot fill (or only outside the price bands) stays open.
+    fn fill_from_book<M: MarketDataProvider>(
+        &mut self,
+        order: &Order,
+        limit: Option<Price>,
+        market_data: &M,
+    ) -> Result<Option<bool>> {
+        let limit = match (limit, market_data.price_bands(&order.symbol)) {
+            (limit, None) => limit,
+            (None, Some(bands)) => Some(bands.limit_for(order.side)),
+            (Some(limit), Some(bands)) => Some(match order.side {
+                OrderSide::Buy => Price(limit.0.min(bands.upper.0)),
+                OrderSide::Sell => Price(limit.0.max(bands.lower.0)),
+            }),
+        };
+        let fills = match market_data.book_fills(&order.symbol, order.side, order.remaining_quantity(), limit) {
+            Some(fills) => fills,
+            None => return Ok(None),
+        };
+        if fills.is_empty() {
+            return Ok(Some(false));
+        }
+
+        // Check the whole sweep is affordable before taking any liquidity
+        let config = self.get_config();
+        let quantity: Decimal = fills.iter().map(|fill| fill.quantity.0).sum();
+        match order.side {
+            OrderSide::Buy => {
+                let value: Decimal = fills.iter().map(|fill| fill.price.0 * fill.quantity.0).sum();
+                let total_cost = value + value * config.commission_rate;
+                if self.cash_balance < total_cost {
+                    return Err(Error::InsufficientFunds {
+                        required: total_cost,
+                        available: self.cash_balance,
+                    });
+                }
+            }
+            OrderSide::Sell => {
+                let available = self
+                    .get_position(&order.symbol)
+                    .map(|position| position.quantity.0)
+                    .unwrap_or(Decimal::ZERO);
+                if available < quantity {
+                    return Err(Error::InsufficientPosition {
+                        symbol: order.symbol.clone(),
+                        required: quantity,
+                        available,
+                    });
+                }
+            }
+        }
+
+        for fill in &fills {
+            self.execute_fill(&order.id, fill.quantity, fill.price)?;
+        }
+        market_data.consume_liquidity(&order.symbol, order.side, &fills);
+
+        Ok(Some(true))
+    }
+
+    /// Execute the remaining quantity of an order at a specific price
+    fn execute_order_at_price(&mut self, order_id: &OrderId, price: Price) -> Result<()> {
+        let quantity = match self.get_order(order_id) {
+            Some(order) => order.remaining_quantity(),
+            None => return Err(Error::OrderNotFound { order_id: *order_id }),
+        };
+        self.execute_fill(order_id, quantity, price)
+    }
+
+    /// Execute part or all of an order at a specific price
+    fn execute_fill(&mut self, order_id: &OrderId, quantity: Quantity, price: Price) -> Result<()> {
+        // First, clone the order to avoid borrowing issues
+        let order = match self.get_order(order_id) {
+            Some(order) => order.clone(),
+            None => return Err(Error::OrderNotFound { order_id: *order_id }),
+        };
+
+        // Check if order is active
+        if !order.is_active() {
+            return Ok(());
+        }
+
+        // Get configuration
+        let config = self.get_config();
+
+        // Calculate the trade value and commission
+        let value = price.0 * quantity.0;
+        let commission = value * config.commission_rate;
+
+        // Process order based on side
+        match order.side {
+            OrderSide::Buy => {
+                // Check if we have enough cash
+                let total_cost = value + commission;
+                if self.cash_balance < total_cost {
+                    return Err(Error::InsufficientFunds {
+                        required: total_cost,
+                        available: self.cash_balance,
+                    });
+                }
+
+                // Update cash balance
+                self.cash_balance -= total_cost;
+
+                // Update position
+                let symbol = order.symbol.clone();
+                self.get_or_create_position(symbol).add(quantity, price);
+            }
+            OrderSide::Sell => {
+                // Check if we have enough of the asset
+                let symbol = order.symbol.clone();
+                let position_quantity = match self.get_position(&symbol) {
+                    Some(position) => position.quantity,
+                    None => {
+                        return Err(Error::InsufficientPosition {
+                            symbol,
+                            required: quantity.0,
+                            available: Decimal::ZERO,
+                        });
+                    }
+                };
+
+                if position_quantity < quantity {
+                    return Err(Error::InsufficientPosition {
+                        symbol,
+                        required: quantity.0,
+                        available: position_quantity.0,
+                    });
+                }
+
+                // Update position
+                self.get_position_mut(&symbol).unwrap().remove(quantity, price);
+
+                // Update cash balance
+                self.cash_balance += value - commission;
+            }
+        }
+
+        // Create a trade record
+        let now = self.now();
+        let trade = Trade::new(
+            *order_id,
+            order.symbol.clone(),
+            order.side,
+            quantity,
+            price,
+            commission,
+        )
+        .with_timestamp(now)
+        .with_order_tags(&order);
+        let trade = match &self.id_generator {
+            Some(id_generator) => Trade {
+                id: TradeId::generate(id_generator.as_ref()),
+                ..trade
+            },
+            None => trade,
+        };
+
+        // Update the order
+        if let Some(order) = self.open_orders.get_mut(&order_id.0.to_string()) {
+            order.execute(trade);
+            
+            // If the order is complete, move it to history
+            if order.is_complete() {
+                let order_id_str = order_id.0.to_string();
+                let order = self.remove_open_order(&order_id_str);
+                if let Some(order) = order {
+                    self.order_history.push(order);
+                }
+            }
+        }
+
+        self.filled_since_snapshot = true;
+        self.updated_at = now;
+
+        Ok(())
+    }
+
+    /// Validate an order before submission
+    fn validate_order(&self, order: &Order) -> Result<()> {
         // Check if we have enough cash for buy orders
         if order.side == OrderSide::Buy {
             let estimated_cost = match order.order_type {
-                OrderType::Market => {
+                OrderType::Market | OrderType::MarketOnOpen | OrderType::MarketOnClose => {
                     // For market orders, we can't know the exact price,
                     // so we should have enough cash for the full quantity
                     // This is a simplification - in practice, you might want to
                     // estimate based on the last price plus some margin
                     return Ok(());
                 }
-                OrderType::Limit => {
+                OrderType::Limit | OrderType::LimitOnOpen | OrderType::LimitOnClose => {
                     let limit_price = order.limit_price.ok_or_else(|| Error::InvalidOrder {
                         reason: "Limit order without limit price".to_string(),
                     })?;
@@ -409,44 +1121,288 @@ impl Account {
         Ok(())
     }
 
+    /// Expire open orders whose time in force has elapsed on the account clock
+    ///
+    /// DAY orders for symbols with a trading calendar expire at the session close.
+    /// Auction orders are left open, as the auction they target may not have been
+    /// reported yet; `process_auction_order` expires them once it has.
+    pub fn expire_orders(&mut self) -> Vec<OrderId> {
+        let config = self.get_config();
+        let now = config.now();
+        let expired: Vec<OrderId> = self
+            .open_order_ids()
+            .into_iter()
+            .filter(|order_id| {
+                let order = &self.open_orders.orders[&order_id.0.to_string()];
+                order.order_type.auction().is_none() && order.is_expired_in(now, config.calendar(&order.symbol))
+            })
+            .collect();
+
+        for order_id in &expired {
+            if let Some(mut order) = self.remove_open_order(&order_id.0.to_string()) {
+                order.expire_at(now);
+                self.order_history.push(order);
+            }
+        }
+
+        if !expired.is_empty() {
+            self.updated_at = now;
+        }
+
+        expired
+    }
+
     /// Process all open orders against current market data
+    ///
+    /// Orders are evaluated in the order they were created, so runs are repeatable.
     pub fn process_open_orders<M: MarketDataProvider>(&mut self, market_data: &M) -> Result<()> {
-        // Collect all order IDs to avoid borrowing issues
-        let order_ids: Vec<OrderId> = self
-            .open_orders
-            .values()
-            .map(|order| order.id)
-            .collect();
+        self.accrue_to_now();
+
+        // Expire orders before evaluating them against the market
+        self.expire_orders();
+
+        for order_id in self.open_order_ids() {
+            self.process_order(&order_id, market_data)?;
+        }
+
+        self.snapshot_if_due(market_data)?;
+
+        Ok(())
+    }
+
+    /// Process only the open orders for one symbol, e.g. after a quote update
+    ///
+    /// Orders are found through the symbol index, so accounts with many open
+    /// orders across many symbols only evaluate the orders the update affects.
+    pub fn process_open_orders_for_symbol<M: MarketDataProvider>(
+        &mut self,
+        symbol: &Symbol,
+        market_data: &M,
+    ) -> Result<()> {
+        self.accrue_to_now();
+
+        let order_ids = creation_order(self.open_orders.for_symbol(symbol));
+        if order_ids.is_empty() {
+            return Ok(());
+        }
+
+        // Expire this symbol's orders before evaluating them against the market
+        // (auction orders expire once their auction has been processed)
+        for order_id in &order_ids {
+            let is_auction = self
+                .get_order(order_id)
+                .is_some_and(|order| order.order_type.auction().is_some());
+            if !is_auction {
+                self.expire_elapsed_order(order_id);
+            }
+        }
 
         for order_id in order_ids {
-            // Get the order type
-            let order_type = {
-                let order = self.get_order(&order_id).ok_or_else(|| Error::OrderNotFound {
-                    order_id,
-                })?;
-                order.order_type
-            };
+            if self.get_order(&order_id).is_some() {
+                self.process_order(&order_id, market_data)?;
+            }
+        }
 
-            // Process based on order type
-            match order_type {
-                OrderType::Market => {
-                    self.execute_market_order(&order_id, market_data)?;
-                }
-                OrderType::Limit => {
-                    self.process_limit_order(&order_id, market_data)?;
-                }
-                OrderType::Stop => {
-                    // TODO: Implement stop order processing
-                }
-                OrderType::StopLimit => {
-                    // TODO: Implement stop-limit order processing
-                }
+        self.snapshot_if_due(market_data)?;
+
+        Ok(())
+    }
+
+    /// Process a single open order according to its type
+    fn process_order<M: MarketDataProvider>(&mut self, order_id: &OrderId, market_data: &M) -> Result<()> {
+        // Get the order type
+        let order_type = {
+            let order = self.get_order(order_id).ok_or(Error::OrderNotFound {
+                order_id: *order_id,
+            })?;
+            order.order_type
+        };
+
+        // Process based on order type
+        match order_type {
+            OrderType::Market => {
+                self.execute_market_order(order_id, market_data)?;
+            }
+            OrderType::Limit => {
+                self.process_limit_order(order_id, market_data)?;
+            }
+            OrderType::Stop | OrderType::StopLimit => {
+                self.process_stop_order(order_id, market_data)?;
+            }
+            OrderType::MarketOnOpen
+            | OrderType::MarketOnClose
+            | OrderType::LimitOnOpen
+            | OrderType::LimitOnClose => {
+                self.process_auction_order(order_id, market_data)?;
             }
         }
 
         Ok(())
     }
 
+    /// Record a fill snapshot if an order filled since the last one and fill snapshots are enabled
+    ///
+    /// If the snapshot fails (e.g., a position has no quote), the error is logged,
+    /// the fill itself stands and the snapshot is retried after the next order is processed.
+    fn record_fill_snapshot<M: MarketDataProvider>(&mut self, market_data: &M) {
+        if !self.filled_since_snapshot {
+            return;
+        }
+        if self.get_config().snapshot_on_fill {
+            if let Err(error) = self.record_snapshot(SnapshotKind::Fill, market_data) {
+                warn!("Fill snapshot for account {} failed: {}", self.id, error);
+                return;
+            }
+        }
+        self.filled_since_snapshot = false;
+    }
+
+    /// Value the account's cash and positions at the current time
+    pub fn snapshot<M: MarketDataProvider>(&self, kind: SnapshotKind, market_data: &M) -> Result<EquitySnapshot> {
+        let mut positions = BTreeMap::new();
+        for position in self.positions.values() {
+            if !position.is_flat() {
+                let quote = market_data.get_quote(&position.symbol)?;
+                positions.insert(position.symbol.0.clone(), position.market_value(quote.mid()));
+            }
+        }
+        Ok(EquitySnapshot::new(self.now(), kind, self.cash_balance, positions)
+            .with_external_flows(self.net_external_flows()))
+    }
+
+    /// Take a snapshot and add it to the equity history
+    ///
+    /// If `max_snapshots` is set, the oldest snapshots are dropped to stay within it.
+    pub fn record_snapshot<M: MarketDataProvider>(
+        &mut self,
+        kind: SnapshotKind,
+        market_data: &M,
+    ) -> Result<EquitySnapshot> {
+        self.accrue_to_now();
+        let snapshot = self.snapshot(kind, market_data)?;
+        self.equity_history.push(snapshot.clone());
+        if let Some(max_snapshots) = self.get_config().max_snapshots {
+            self.equity_history.retain_latest(max_snapshots);
+        }
+        Ok(snapshot)
+    }
+
+    /// Record an interval snapshot if the configured interval has passed since the last one
+    ///
+    /// Intervals are measured on the account clock from the last interval snapshot
+    /// (or account creation). If the clock jumps several intervals, one snapshot is
+    /// taken at the current time. Returns `true` if a snapshot was recorded.
+    pub fn snapshot_if_due<M: MarketDataProvider>(&mut self, market_data: &M) -> Result<bool> {
+        let interval = match self.get_config().snapshot_interval {
+            Some(interval) if interval > Duration::zero() => interval,
+            _ => return Ok(false),
+        };
+        let last = self
+            .equity_history
+            .latest_of(SnapshotKind::Interval)
+            .map_or(self.created_at, |snapshot| snapshot.timestamp);
+        if self.now() < last + interval {
+            return Ok(false);
+        }
+
+        self.record_snapshot(SnapshotKind::Interval, market_data)?;
+        Ok(true)
+    }
+
+    /// Accrue daily interest on positive cash up to `as_of`
+    ///
+    /// Accrual runs in whole days from the last accrual (or account creation),
+    /// compounding daily, and posts an interest entry per day. It runs
+    /// automatically when open orders are processed and when snapshots are
+    /// recorded. Returns the entries posted by this call.
+    pub fn accrue(&mut self, as_of: DateTime<Utc>) -> Vec<CashEntry> {
+        let start = self.last_accrual.unwrap_or(self.created_at);
+        let days = (as_of - start).num_days();
+        if days <= 0 {
+            return Vec::new();
+        }
+
+        let config = self.get_config();
+        let day_count = Decimal::from(config.interest_day_count.max(1));
+
+        let mut entries = Vec::new();
+        for day in 1..=days {
+            if self.cash_balance > Decimal::ZERO && config.cash_interest_rate > Decimal::ZERO {
+                let amount = self.cash_balance * config.cash_interest_rate / day_count;
+                entries.push(self.post_cash_entry(CashEntry::new(
+                    CashEntryKind::InterestCredit,
+                    amount,
+                    None,
+                    start + Duration::days(day),
+                )));
+            }
+        }
+
+        self.last_accrual = Some(start + Duration::days(days));
+        self.updated_at = self.now();
+
+        entries
+    }
+
+    /// Accrue daily interest up to the current time of the account clock
+    pub fn accrue_to_now(&mut self) -> Vec<CashEntry> {
+        let now = self.now();
+        self.accrue(now)
+    }
+
+    /// Deposit cash into the account
+    pub fn deposit(&mut self, amount: Decimal) -> Result<CashEntry> {
+        self.post_external_flow(CashEntryKind::Deposit, amount)
+    }
+
+    /// Withdraw cash from the account
+    pub fn withdraw(&mut self, amount: Decimal) -> Result<CashEntry> {
+        self.post_external_flow(CashEntryKind::Withdrawal, amount)
+    }
+
+    /// Post a deposit, withdrawal or transfer of a positive amount
+    ///
+    /// Outgoing flows must be covered by the cash balance.
+    pub(crate) fn post_external_flow(&mut self, kind: CashEntryKind, amount: Decimal) -> Result<CashEntry> {
+        if amount <= Decimal::ZERO {
+            return Err(Error::Custom(format!("{:?} amount must be positive, got {}", kind, amount)));
+        }
+
+        let amount = match kind {
+            CashEntryKind::Withdrawal | CashEntryKind::TransferOut => {
+                if self.cash_balance < amount {
+                    return Err(Error::InsufficientFunds {
+                        required: amount,
+                        available: self.cash_balance,
+                    });
+                }
+                -amount
+            }
+            _ => amount,
+        };
+
+        let now = self.now();
+        let entry = self.post_cash_entry(CashEntry::new(kind, amount, None, now));
+        self.updated_at = now;
+        Ok(entry)
+    }
+
+    /// Get the net deposits, withdrawals and transfers posted to the account
+    pub fn net_external_flows(&self) -> Decimal {
+        self.cash_ledger
+            .iter()
+            .filter(|entry| entry.is_external_flow())
+            .map(|entry| entry.amount)
+            .sum()
+    }
+
+    /// Apply a cash entry to the cash balance and record it in the ledger
+    fn post_cash_entry(&mut self, entry: CashEntry) -> CashEntry {
+        self.cash_balance += entry.amount;
+        self.cash_ledger.push(entry.clone());
+        entry
+    }
+
     /// Get the total realized profit/loss
     pub fn total_realized_pnl(&self) -> Decimal {
         self.positions
@@ -459,7 +1415,7 @@ impl Account {
     pub fn total_unrealized_pnl<M: MarketDataProvider>(&self, market_data: &M) -> Result<Decimal> {
         let mut total = Decimal::ZERO;
 
-        for (_, position) in &self.positions {
+        for position in self.positions.values() {
             if !position.is_flat() {
                 let quote = market_data.get_quote(&position.symbol)?;
                 let unrealized_pnl = position.unrealized_pnl(quote.mid());
@@ -470,6 +1426,60 @@ impl Account {
         Ok(total)
     }
 
+    /// Iterate over every trade executed by the account, order by order
+    ///
+    /// Trades are not sorted across orders; use `query_trades` for trades in time order.
+    pub fn trades(&self) -> impl Iterator<Item = &Trade> {
+        self.all_orders().flat_map(|order| order.trades.iter())
+    }
+
+    /// Reconstruct round trips from the account's trades, with win/loss statistics
+    pub fn round_trips(&self) -> RoundTripReport {
+        RoundTripReport::new(roundtrip::reconstruct(self.trades()))
+    }
+
+    /// Reconstruct round trips, with excursions measured against a provider's bars
+    pub fn round_trips_with_excursions<H: HistoricalDataProvider>(
+        &self,
+        history: &H,
+        interval: BarInterval,
+    ) -> Result<RoundTripReport> {
+        let mut round_trips = roundtrip::reconstruct(self.trades());
+        roundtrip::apply_excursions(&mut round_trips, history, interval)?;
+        Ok(RoundTripReport::new(round_trips))
+    }
+
+    /// Attribute P&L and commission by symbol, side, UTC day and strategy tag
+    pub fn pnl_attribution<M: MarketDataProvider>(&self, market_data: &M) -> Result<PnlAttribution> {
+        self.pnl_attribution_in(market_data, chrono_tz::UTC)
+    }
+
+    /// Attribute P&L and commission by symbol, side, day in a time zone and strategy tag
+    ///
+    /// Open positions are marked at the quote midpoint, like `total_unrealized_pnl`,
+    /// and a missing quote for one of the account's positions is an error.
+    pub fn pnl_attribution_in<M: MarketDataProvider>(
+        &self,
+        market_data: &M,
+        time_zone: chrono_tz::Tz,
+    ) -> Result<PnlAttribution> {
+        let mut marks = HashMap::new();
+        for position in self.positions.values() {
+            if !position.is_flat() {
+                let quote = market_data.get_quote(&position.symbol)?;
+                marks.insert(position.symbol.0.clone(), quote.mid());
+            }
+        }
+
+        // Strategies can hold offsetting positions in a symbol the account is flat in
+        Ok(PnlAttribution::from_trades(self.trades(), time_zone, |symbol| {
+            marks
+                .get(&symbol.0)
+                .copied()
+                .or_else(|| market_data.get_quote(symbol).ok().map(|quote| quote.mid()))
+        }))
+    }
+
     /// Get account performance metrics
     pub fn performance<M: MarketDataProvider>(&self, market_data: &M) -> Result<AccountPerformance> {
         let current_equity = self.equity(market_data)?;
@@ -477,14 +1487,18 @@ impl Account {
         let realized_pnl = self.total_realized_pnl();
         let total_pnl = realized_pnl + unrealized_pnl;
         
-        let roi = if self.initial_deposit > Decimal::ZERO {
-            (total_pnl / self.initial_deposit) * Decimal::from(100)
+        // Deposits and transfers are capital, not profit
+        let net_external_flows = self.net_external_flows();
+        let capital = self.initial_deposit + net_external_flows;
+        let roi = if capital > Decimal::ZERO {
+            (total_pnl / capital) * Decimal::from(100)
         } else {
             Decimal::ZERO
         };
 
         Ok(AccountPerformance {
             initial_deposit: self.initial_deposit,
+            net_external_flows,
             cash_balance: self.cash_balance,
             equity: current_equity,
             realized_pnl,
@@ -495,11 +1509,61 @@ impl Account {
     }
 }
 
+/// Find the first point where a price path reaches a level
+///
+/// Returns the index of the point and the price reached: the first price itself
+/// if the path starts beyond the level (a gap), otherwise the level.
+fn first_crossing(prices: &[Price], level: Price, rising: bool) -> Option<(usize, Price)> {
+    let reached = |price: &Price| {
+        if rising {
+            price.0 >= level.0
+        } else {
+            price.0 <= level.0
+        }
+    };
+
+    let index = prices.iter().position(reached)?;
+    if index == 0 {
+        Some((0, prices[0]))
+    } else {
+        Some((index, level))
+    }
+}
+
+/// Keep an execution price within the price bands, if any
+fn clamp_to_bands(price: Price, bands: Option<PriceBands>) -> Price {
+    match bands {
+        Some(bands) => Price(price.0.clamp(bands.lower.0, bands.upper.0)),
+        None => price,
+    }
+}
+
+/// Get the fill price of a limit order along a price path, if it is reached
+///
+/// Fills against an intrabar path take the price reached (gaps fill at the open);
+/// fills against a quote take the limit price.
+fn limit_fill_price(prices: &[Price], side: OrderSide, limit_price: Price, from_path: bool) -> Option<Price> {
+    // Buy limits fill on falling prices, sell limits on rising prices
+    let rising = side == OrderSide::Sell;
+    first_crossing(prices, limit_price, rising)
+        .map(|(_, price)| if from_path { price } else { limit_price })
+}
+
+/// Get the IDs of orders in the order they were created (then by ID), so processing is repeatable
+fn creation_order<'a>(orders: impl Iterator<Item = &'a Order>) -> Vec<OrderId> {
+    let mut orders: Vec<&Order> = orders.collect();
+    orders.sort_by_key(|order| (order.created_at, order.id.0));
+    orders.into_iter().map(|order| order.id).collect()
+}
+
 /// Account performance metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountPerformance {
     /// Initial deposit
     pub initial_deposit: Decimal,
+    /// Net deposits, withdrawals and transfers since the initial deposit
+    #[serde(default)]
+    pub net_external_flows: Decimal,
     /// Current cash balance
     pub cash_balance: Decimal,
     /// Total equity (cash + positions)
@@ -510,6 +1574,581 @@ pub struct AccountPerformance {
     pub unrealized_pnl: Decimal,
     /// Total profit/loss (realized + unrealized)
     pub total_pnl: Decimal,
-    /// Return on investment (%)
+    /// Return on investment (%) against the initial deposit plus net external flows
     pub roi: Decimal,
 }
+
+/// Open orders by order ID, with an index of order IDs by symbol
+///
+/// Orders only change through `insert`, `remove` and the `OrderMut` guard, so the
+/// index always matches the orders. Only the orders are serialized.
+#[derive(Debug, Clone, Default)]
+struct OpenOrders {
+    orders: HashMap<String, Order>,
+    by_symbol: HashMap<String, HashSet<String>>,
+}
+
+impl OpenOrders {
+    /// Get an open order by ID
+    fn get(&self, order_id: &str) -> Option<&Order> {
+        self.orders.get(order_id)
+    }
+
+    /// Get a mutable open order by ID (the ID and symbol must not be changed)
+    fn get_mut(&mut self, order_id: &str) -> Option<&mut Order> {
+        self.orders.get_mut(order_id)
+    }
+
+    /// Get a guard for an open order that re-indexes it when dropped
+    fn guard(&mut self, order_id: &str) -> Option<OrderMut<'_>> {
+        let symbol = self.orders.get(order_id)?.symbol.clone();
+        Some(OrderMut {
+            open_orders: self,
+            indexed_id: order_id.to_string(),
+            indexed_symbol: symbol,
+        })
+    }
+
+    /// Iterate over the open orders
+    fn values(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values()
+    }
+
+    /// Iterate over the open orders for a symbol
+    fn for_symbol<'a>(&'a self, symbol: &Symbol) -> impl Iterator<Item = &'a Order> {
+        self.by_symbol
+            .get(&symbol.0)
+            .into_iter()
+            .flatten()
+            .filter_map(|order_id| self.orders.get(order_id))
+    }
+
+    /// Add an order, keyed by its ID
+    fn insert(&mut self, order: Order) {
+        let order_id = order.id.0.to_string();
+        self.by_symbol
+            .entry(order.symbol.0.clone())
+            .or_default()
+            .insert(order_id.clone());
+        self.orders.insert(order_id, order);
+    }
+
+    /// Remove an order by ID
+    fn remove(&mut self, order_id: &str) -> Option<Order> {
+        let order = self.orders.remove(order_id)?;
+        if let Some(order_ids) = self.by_symbol.get_mut(&order.symbol.0) {
+            order_ids.remove(order_id);
+            if order_ids.is_empty() {
+                self.by_symbol.remove(&order.symbol.0);
+            }
+        }
+        Some(order)
+    }
+}
+
+impl Serialize for OpenOrders {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serialize_sorted(&self.orders, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OpenOrders {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let orders = HashMap::<String, Order>::deserialize(deserializer)?;
+        let mut open_orders = OpenOrders::default();
+        for order in orders.into_values() {
+            open_orders.insert(order);
+        }
+        Ok(open_orders)
+    }
+}
+
+/// Mutable access to an open order
+///
+/// Changes to the order's ID or symbol are applied to the account's open order
+/// index when the guard is dropped.
+#[derive(Debug)]
+pub struct OrderMut<'a> {
+    open_orders: &'a mut OpenOrders,
+    indexed_id: String,
+    indexed_symbol: Symbol,
+}
+
+impl Deref for OrderMut<'_> {
+    type Target = Order;
+
+    fn deref(&self) -> &Order {
+        &self.open_orders.orders[&self.indexed_id]
+    }
+}
+
+impl DerefMut for OrderMut<'_> {
+    fn deref_mut(&mut self) -> &mut Order {
+        self.open_orders.orders.get_mut(&self.indexed_id).unwrap()
+    }
+}
+
+impl Drop for OrderMut<'_> {
+    fn drop(&mut self) {
+        let order = &self.open_orders.orders[&self.indexed_id];
+        if order.id.0.to_string() != self.indexed_id || order.symbol != self.indexed_symbol {
+            // Index the order under its new ID and symbol
+            let order = self.open_orders.orders.remove(&self.indexed_id).unwrap();
+            if let Some(order_ids) = self.open_orders.by_symbol.get_mut(&self.indexed_symbol.0) {
+                order_ids.remove(&self.indexed_id);
+                if order_ids.is_empty() {
+                    self.open_orders.by_symbol.remove(&self.indexed_symbol.0);
+                }
+            }
+            self.open_orders.insert(order);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::calendar::TradingCalendar;
+    use crate::halt::PriceBands;
+    use crate::instrument::InstrumentRegistry;
+    use crate::market::{HistoricalDataPoint, SimpleMarketDataProvider};
+    use crate::order::TimeInForce;
+    use crate::replay::BarReplayProvider;
+    use crate::id::SequentialIdGenerator;
+    use std::sync::Arc;
+    use uuid::Uuid;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap()
+    }
+
+    fn setup(config: Config) -> (SimulatedClock, Account, SimpleMarketDataProvider) {
+        let clock = SimulatedClock::new(start());
+        let config = Config {
+            clock: Some(clock.shared()),
+            ..config
+        };
+        let account = Account::new_with_config("Test", "USD", dec!(100_000), config);
+        let market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+        (clock, account, market_data)
+    }
+
+    fn buy(symbol: &str, quantity: Decimal) -> Order {
+        Order::market(Symbol::new(symbol), OrderSide::Buy, Quantity(quantity))
+    }
+
+    #[test]
+    fn orders_and_trades_are_stamped_with_the_account_clock() {
+        let (clock, mut account, mut market_data) = setup(Config::default());
+        let order_id = account.submit_order(buy("AAA", dec!(1))).unwrap();
+
+        clock.advance(Duration::minutes(30));
+        market_data.set_price(Symbol::new("AAA"), Price(dec!(100)));
+        account.process_open_orders(&market_data).unwrap();
+
+        let order = account.order_history.iter().find(|order| order.id == order_id).unwrap();
+        assert_eq!(order.created_at, start());
+        assert_eq!(order.trades[0].timestamp, start() + Duration::minutes(30));
+        assert_eq!(order.updated_at, start() + Duration::minutes(30));
+        assert_eq!(account.updated_at, start() + Duration::minutes(30));
+        let quote = market_data.get_quote(&Symbol::new("AAA")).unwrap();
+        assert_eq!(quote.timestamp, start() + Duration::minutes(30));
+    }
+
+    #[test]
+    fn invalid_quotes_error_or_reject_market_orders_as_configured() {
+        let stale = |action| Config {
+            max_quote_age: Some(Duration::seconds(30)),
+            invalid_quote_action: action,
+            ..Config::default()
+        };
+
+        let (clock, mut account, mut market_data) = setup(stale(InvalidQuoteAction::Error));
+        market_data.set_price(Symbol::new("AAA"), Price(dec!(100)));
+        let order_id = account.submit_order(buy("AAA", dec!(1))).unwrap();
+        clock.advance(Duration::minutes(1));
+        assert!(matches!(
+            account.process_open_orders(&market_data),
+            Err(Error::MarketDataError { .. })
+        ));
+        assert!(account.get_order(&order_id).unwrap().is_active());
+
+        let (clock, mut account, mut market_data) = setup(stale(InvalidQuoteAction::Reject));
+        market_data.set_price(Symbol::new("AAA"), Price(dec!(100)));
+        let market_id = account.submit_order(buy("AAA", dec!(1))).unwrap();
+        let limit = Order::limit(Symbol::new("AAA"), OrderSide::Buy, Quantity(dec!(1)), Price(dec!(100)));
+        let limit_id = account.submit_order(limit).unwrap();
+        clock.advance(Duration::minutes(1));
+        account.process_open_orders(&market_data).unwrap();
+        let market = account.order_history.iter().find(|order| order.id == market_id).unwrap();
+        assert!(market.is_rejected());
+        assert!(account.get_order(&limit_id).unwrap().is_active());
+    }
+
+    #[test]
+    fn submitted_orders_and_trades_take_ids_from_the_generator() {
+        let (_, account, mut market_data) = setup(Config::default());
+        let mut account = account.with_id_generator(Arc::new(SequentialIdGenerator::default()));
+        let symbols = ["EEE", "DDD", "CCC", "BBB", "AAA"];
+
+        let order_ids: Vec<OrderId> = symbols
+            .iter()
+            .map(|symbol| account.submit_order(buy(symbol, dec!(1))).unwrap())
+            .collect();
+        let expected: Vec<OrderId> = (1..=5).map(|id| OrderId(Uuid::from_u128(id))).collect();
+        assert_eq!(order_ids, expected);
+
+        for symbol in symbols {
+            market_data.set_price(Symbol::new(symbol), Price(dec!(100)));
+        }
+        account.process_open_orders(&market_data).unwrap();
+
+        // Orders created at the same time are processed by ID, so trade IDs follow the order IDs
+        let mut trades: Vec<&Trade> = account.trades().collect();
+        trades.sort_by_key(|trade| trade.id.0);
+        let traded: Vec<OrderId> = trades.iter().map(|trade| trade.order_id).collect();
+        assert_eq!(traded, expected);
+        assert_eq!(trades[0].id.0, Uuid::from_u128(6));
+    }
+
+    #[test]
+    fn new_order_ids_are_kept_on_submission() {
+        let (_, account, _) = setup(Config::default());
+        let mut account = account.with_id_generator(Arc::new(SequentialIdGenerator::new(10)));
+
+        let order = account.new_order(buy("AAA", dec!(1)));
+        assert_eq!(order.id, OrderId(Uuid::from_u128(10)));
+        assert_eq!(account.submit_order(order).unwrap(), OrderId(Uuid::from_u128(10)));
+        assert_eq!(account.submit_order(buy("AAA", dec!(1))).unwrap(), OrderId(Uuid::from_u128(11)));
+    }
+
+    #[test]
+    fn open_orders_are_indexed_by_symbol() {
+        let (_, mut account, _) = setup(Config::default());
+        let aaa = account.submit_order(buy("AAA", dec!(1))).unwrap();
+        account.submit_order(buy("BBB", dec!(1))).unwrap();
+
+        assert_eq!(account.open_orders_for_symbol(&Symbol::new("AAA"))[0].id, aaa);
+        assert!(account.open_orders_for_symbol(&Symbol::new("CCC")).is_empty());
+
+        account.cancel_order(&aaa).unwrap();
+        assert!(!account.has_open_orders_for(&Symbol::new("AAA")));
+        assert!(account.has_open_orders_for(&Symbol::new("BBB")));
+    }
+
+    #[test]
+    fn changing_an_order_symbol_updates_the_index() {
+        let (_, mut account, _) = setup(Config::default());
+        let order_id = account.submit_order(buy("AAA", dec!(1))).unwrap();
+
+        account.get_order_mut(&order_id).unwrap().symbol = Symbol::new("BBB");
+
+        assert!(account.open_orders_for_symbol(&Symbol::new("AAA")).is_empty());
+        assert_eq!(account.open_orders_for_symbol(&Symbol::new("BBB"))[0].id, order_id);
+    }
+
+    #[test]
+    fn order_index_is_rebuilt_when_loading() {
+        let (_, mut account, _) = setup(Config::default());
+        let order_id = account.submit_order(buy("AAA", dec!(1))).unwrap();
+
+        let json = serde_json::to_string(&account).unwrap();
+        let loaded: Account = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.open_orders().len(), 1);
+        assert_eq!(loaded.open_orders_for_symbol(&Symbol::new("AAA"))[0].id, order_id);
+    }
+
+    #[test]
+    fn interest_accrues_daily_as_orders_are_processed() {
+        let (clock, mut account, market_data) = setup(Config {
+            cash_interest_rate: dec!(0.0365),
+            ..Config::default()
+        });
+
+        clock.advance(Duration::hours(47));
+        account.process_open_orders(&market_data).unwrap();
+        assert_eq!(account.cash_ledger.len(), 1);
+        assert_eq!(account.cash_ledger[0].kind, CashEntryKind::InterestCredit);
+        assert_eq!(account.cash_ledger[0].amount, dec!(10));
+
+        // Processing again within the day posts nothing; the next day compounds
+        account.process_open_orders(&market_data).unwrap();
+        clock.advance(Duration::hours(1));
+        account.process_open_orders(&market_data).unwrap();
+        assert_eq!(account.cash_ledger.len(), 2);
+        assert_eq!(account.cash_ledger[1].amount, dec!(10.001));
+        assert_eq!(account.cash_balance, dec!(100_020.001));
+    }
+
+    #[test]
+    fn snapshots_include_accrued_interest() {
+        let (clock, mut account, market_data) = setup(Config {
+            cash_interest_rate: dec!(0.0365),
+            ..Config::default()
+        });
+
+        clock.advance(Duration::days(1));
+        let snapshot = account.record_snapshot(SnapshotKind::Manual, &market_data).unwrap();
+        assert_eq!(snapshot.cash, dec!(100_010));
+        assert_eq!(account.last_accrual, Some(start() + Duration::days(1)));
+    }
+
+    fn with_us_calendar() -> Config {
+        let calendar = Arc::new(TradingCalendar::us_equities());
+        Config {
+            instruments: Some(Arc::new(InstrumentRegistry::new().with_default_calendar(calendar))),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn day_market_on_close_fills_at_the_same_days_close() {
+        let (clock, mut account, mut market_data) = setup(with_us_calendar());
+        let order = Order::market_on_close(Symbol::new("AAA"), OrderSide::Buy, Quantity(dec!(10)))
+            .with_time_in_force(TimeInForce::Day);
+        let order_id = account.submit_order(order).unwrap();
+
+        // The close print arrives at the session close, when DAY orders expire
+        clock.set(Utc.with_ymd_and_hms(2024, 1, 2, 21, 0, 0).unwrap());
+        market_data.set_auction_price(Symbol::new("AAA"), Auction::Close, Price(dec!(101)));
+        account.process_open_orders(&market_data).unwrap();
+
+        let order = account.order_history.iter().find(|order| order.id == order_id).unwrap();
+        assert!(order.is_filled());
+        assert_eq!(order.average_fill_price(), Some(Price(dec!(101))));
+    }
+
+    #[test]
+    fn day_auction_orders_expire_once_their_auction_has_passed() {
+        let (clock, mut account, mut market_data) = setup(with_us_calendar());
+        market_data.set_auction_price(Symbol::new("AAA"), Auction::Close, Price(dec!(99)));
+        let order = Order::market_on_close(Symbol::new("AAA"), OrderSide::Buy, Quantity(dec!(10)))
+            .with_time_in_force(TimeInForce::Day);
+        account.submit_order(order).unwrap();
+
+        clock.set(Utc.with_ymd_and_hms(2024, 1, 2, 22, 0, 0).unwrap());
+        account.process_open_orders(&market_data).unwrap();
+
+        assert!(account.open_orders().is_empty());
+        assert!(account.order_history[0].is_expired());
+    }
+
+    #[test]
+    fn replayed_auction_orders_fill_at_the_auction_after_submission() {
+        let clock = SimulatedClock::new(start());
+        let config = Config {
+            clock: Some(clock.shared()),
+            ..Config::default()
+        };
+        let mut account = Account::new_with_config("Test", "USD", dec!(100_000), config);
+        let day = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        let bars = [(dec!(100), dec!(102)), (dec!(103), dec!(104))]
+            .iter()
+            .enumerate()
+            .map(|(i, (open, close))| HistoricalDataPoint {
+                symbol: Symbol::new("AAA"),
+                open: Price(*open),
+                high: Price(*close),
+                low: Price(*open),
+                close: Price(*close),
+                volume: 1000.0,
+                timestamp: day + Duration::days(i as i64),
+            })
+            .collect();
+        let mut market_data = BarReplayProvider::new()
+            .with_clock(clock.shared())
+            .with_auction_prints(true);
+        market_data.add_series(&Symbol::new("AAA"), BarInterval::Days(1), bars);
+
+        // Submitted during the first session, the order fills at that session's close
+        let on_close = account
+            .submit_order(Order::market_on_close(Symbol::new("AAA"), OrderSide::Buy, Quantity(dec!(1))))
+            .unwrap();
+        market_data.advance(&clock);
+        account.process_open_orders(&market_data).unwrap();
+        assert_eq!(account.order_history[0].id, on_close);
+        assert_eq!(account.order_history[0].average_fill_price(), Some(Price(dec!(102))));
+
+        // Submitted as the next session starts, the order fills at its open
+        account
+            .submit_order(Order::market_on_open(Symbol::new("AAA"), OrderSide::Buy, Quantity(dec!(1))))
+            .unwrap();
+        account.process_open_orders(&market_data).unwrap();
+        assert_eq!(account.order_history.len(), 1);
+        market_data.advance(&clock);
+        account.process_open_orders(&market_data).unwrap();
+        assert_eq!(account.order_history[1].average_fill_price(), Some(Price(dec!(103))));
+    }
+
+    #[test]
+    fn fill_snapshot_is_taken_from_execute_market_order() {
+        let (_, mut account, mut market_data) = setup(Config {
+            snapshot_on_fill: true,
+            ..Config::default()
+        });
+        market_data.set_price(Symbol::new("AAA"), Price(dec!(100)));
+
+        let order_id = account.submit_order(buy("AAA", dec!(10))).unwrap();
+        account.execute_market_order(&order_id, &market_data).unwrap();
+
+        let snapshot = account.equity_history.latest().unwrap();
+        assert_eq!(snapshot.kind, SnapshotKind::Fill);
+        assert_eq!(snapshot.cash, dec!(99_000));
+        assert_eq!(snapshot.equity, dec!(100_000));
+    }
+
+    #[test]
+    fn failed_fill_snapshot_keeps_the_fill_and_retries() {
+        let (_, mut account, mut market_data) = setup(Config {
+            snapshot_on_fill: true,
+            ..Config::default()
+        });
+        market_data.set_price(Symbol::new("AAA"), Price(dec!(100)));
+        let order_id = account.submit_order(buy("AAA", dec!(10))).unwrap();
+        account.execute_market_order(&order_id, &market_data).unwrap();
+
+        // A provider without a quote for AAA can fill BBB but can't value the account
+        let mut partial = SimpleMarketDataProvider::new();
+        partial.set_price(Symbol::new("BBB"), Price(dec!(50)));
+        let order_id = account.submit_order(buy("BBB", dec!(10))).unwrap();
+        account.execute_market_order(&order_id, &partial).unwrap();
+        assert!(account.order_history.iter().any(|order| order.id == order_id && order.is_filled()));
+        assert_eq!(account.equity_history.len(), 1);
+
+        // Once both symbols are quoted, processing the next order records the pending snapshot
+        market_data.set_price(Symbol::new("BBB"), Price(dec!(50)));
+        let limit = Order::limit(Symbol::new("BBB"), OrderSide::Buy, Quantity(dec!(1)), Price(dec!(1)));
+        account.submit_order(limit).unwrap();
+        account.process_open_orders(&market_data).unwrap();
+        assert_eq!(account.equity_history.len(), 2);
+        assert_eq!(account.equity_history.latest().unwrap().equity, dec!(100_000));
+    }
+
+    #[test]
+    fn halted_orders_wait_for_trading_to_resume() {
+        let (_, mut account, mut market_data) = setup(Config::default());
+        let symbol = Symbol::new("AAA");
+        market_data.set_price(symbol.clone(), Price(dec!(100)));
+        market_data.halt(&symbol, "news pending");
+
+        let order_id = account.submit_order(buy("AAA", dec!(10))).unwrap();
+        account.process_open_orders(&market_data).unwrap();
+        assert!(account.get_order(&order_id).is_some());
+
+        market_data.resume(&symbol);
+        account.process_open_orders(&market_data).unwrap();
+        assert!(account.get_order(&order_id).is_none());
+        assert_eq!(account.get_position(&symbol).unwrap().quantity, Quantity(dec!(10)));
+    }
+
+    #[test]
+    fn limit_orders_outside_the_bands_are_rejected_on_submission() {
+        let (_, mut account, mut market_data) = setup(Config::default());
+        let symbol = Symbol::new("AAA");
+        market_data.set_price(symbol.clone(), Price(dec!(100)));
+        market_data.set_price_bands(&symbol, PriceBands::new(Price(dec!(90)), Price(dec!(110))));
+
+        let order = Order::limit(symbol.clone(), OrderSide::Buy, Quantity(dec!(10)), Price(dec!(120)));
+        let order_id = account.submit_order_to(order, &market_data).unwrap();
+        assert!(account.get_order(&order_id).is_none());
+        let rejected = account.order_history.last().unwrap();
+        assert!(rejected.is_rejected());
+        assert!(rejected.reject_reason.as_deref().unwrap().contains("outside the price bands"));
+    }
+
+    #[test]
+    fn resting_limit_orders_survive_moving_bands() {
+        let (_, mut account, mut market_data) = setup(Config::default());
+        let symbol = Symbol::new("AAA");
+        market_data.set_price(symbol.clone(), Price(dec!(100)));
+        market_data.set_price_bands(&symbol, PriceBands::new(Price(dec!(90)), Price(dec!(110))));
+        let order = Order::limit(symbol.clone(), OrderSide::Buy, Quantity(dec!(10)), Price(dec!(95)));
+        let order_id = account.submit_order_to(order, &market_data).unwrap();
+
+        // The bands move above the limit price: the order rests instead of being rejected
+        market_data.set_price(symbol.clone(), Price(dec!(120)));
+        market_data.set_price_bands(&symbol, PriceBands::new(Price(dec!(108)), Price(dec!(132))));
+        account.process_open_orders(&market_data).unwrap();
+        assert!(account.get_order(&order_id).is_some());
+
+        market_data.set_price(symbol.clone(), Price(dec!(94)));
+        market_data.set_price_bands(&symbol, PriceBands::new(Price(dec!(85)), Price(dec!(105))));
+        account.process_open_orders(&market_data).unwrap();
+        let filled = account.order_history.last().unwrap();
+        assert_eq!(filled.id, order_id);
+        assert_eq!(filled.average_fill_price(), Some(Price(dec!(95))));
+    }
+
+    #[test]
+    fn market_orders_wait_for_the_price_to_return_inside_the_bands() {
+        let (_, mut account, mut market_data) = setup(Config::default());
+        let symbol = Symbol::new("AAA");
+        market_data.set_price(symbol.clone(), Price(dec!(120)));
+        market_data.set_price_bands(&symbol, PriceBands::new(Price(dec!(90)), Price(dec!(110))));
+
+        let order_id = account.submit_order(buy("AAA", dec!(10))).unwrap();
+        account.process_open_orders(&market_data).unwrap();
+        assert!(account.get_order(&order_id).is_some());
+
+        market_data.set_price(symbol.clone(), Price(dec!(105)));
+        account.process_open_orders(&market_data).unwrap();
+        assert_eq!(account.order_history.last().unwrap().average_fill_price(), Some(Price(dec!(105))));
+    }
+
+    #[test]
+    fn interval_snapshots_follow_the_clock_and_respect_the_cap() {
+        let (clock, mut account, mut market_data) = setup(Config {
+            snapshot_interval: Some(Duration::hours(1)),
+            max_snapshots: Some(2),
+            ..Config::default()
+        });
+        market_data.set_price(Symbol::new("AAA"), Price(dec!(100)));
+
+        assert!(!account.snapshot_if_due(&market_data).unwrap());
+        for _ in 0..3 {
+            clock.advance(Duration::hours(1));
+            account.process_open_orders(&market_data).unwrap();
+        }
+        assert!(!account.snapshot_if_due(&market_data).unwrap());
+
+        let timestamps: Vec<DateTime<Utc>> = account.equity_history.snapshots().iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, vec![start() + Duration::hours(2), start() + Duration::hours(3)]);
+    }
+
+    #[test]
+    fn client_order_ids_are_unique_and_survive_fills_and_reloads() {
+        let (_, mut account, mut market_data) = setup(Config::default());
+        market_data.set_price(Symbol::new("AAA"), Price(dec!(100)));
+        let order = buy("AAA", dec!(1))
+            .with_client_order_id("entry-1")
+            .with_strategy("momentum")
+            .with_metadata("signal", "breakout");
+        let order_id = account.submit_order(order).unwrap();
+        account.process_open_orders(&market_data).unwrap();
+
+        assert!(matches!(
+            account.submit_order(buy("AAA", dec!(1)).with_client_order_id("entry-1")),
+            Err(Error::DuplicateClientOrderId { .. })
+        ));
+        assert_eq!(account.get_order_by_client_id("entry-1").unwrap().id, order_id);
+        assert!(account.get_order_by_client_id("entry-2").is_none());
+
+        let trades = account.trades_for_client_order_id("entry-1");
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].strategy.as_deref(), Some("momentum"));
+        assert_eq!(trades[0].metadata["signal"], "breakout");
+        assert_eq!(account.orders_for_strategy("momentum").len(), 1);
+        assert_eq!(account.orders_with_metadata("signal", "breakout").len(), 1);
+        assert!(account.orders_with_metadata("signal", "reversal").is_empty());
+
+        let json = serde_json::to_string(&account).unwrap();
+        let mut loaded: Account = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_order_by_client_id("entry-1").unwrap().id, order_id);
+        assert!(loaded.submit_order(buy("AAA", dec!(1)).with_client_order_id("entry-1")).is_err());
+        assert!(loaded.submit_order(buy("AAA", dec!(1)).with_client_order_id("entry-2")).is_ok());
+    }
+}
@@ -0,0 +1,875 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::bars::BarInterval;
+use crate::error::{Error, Result};
+use crate::ledger::CashEntry;
+use crate::market::HistoricalDataProvider;
+use crate::snapshot::{EquityHistory, EquitySnapshot, SnapshotKind};
+use crate::types::{duration_seconds, Price, Symbol};
+
+/// Seconds in an average calendar year, used to annualize
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Largest ratio of the longest to the shortest gap between points for spacing to count as regular
+const MAX_SPACING_RATIO: f64 = 1.5;
+
+/// Largest peak-to-trough decline of an equity curve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Drawdown {
+    /// Decline from the peak as a fraction of the peak (e.g., 0.2 for 20%)
+    pub depth: f64,
+    /// Equity at the peak
+    pub peak_equity: Decimal,
+    /// Equity at the trough
+    pub trough_equity: Decimal,
+    /// Time of the peak
+    pub peak_at: DateTime<Utc>,
+    /// Time of the trough
+    pub trough_at: DateTime<Utc>,
+    /// Time equity first regained the peak (none if it hasn't)
+    pub recovered_at: Option<DateTime<Utc>>,
+    /// Time from the peak to recovery, or to the end of the curve if not recovered (seconds when serialized)
+    #[serde(with = "duration_seconds")]
+    pub duration: Duration,
+    /// Time from the trough to recovery, none if not recovered (seconds when serialized)
+    #[serde(with = "duration_seconds::option")]
+    pub recovery_time: Option<Duration>,
+}
+
+/// Risk-adjusted performance of an equity curve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskReport {
+    /// Time of the first point
+    pub start: DateTime<Utc>,
+    /// Time of the last point
+    pub end: DateTime<Utc>,
+    /// Number of returns the statistics are computed from
+    pub periods: usize,
+    /// Periods per year used to annualize
+    pub periods_per_year: f64,
+    /// Annual risk-free rate used for the ratios
+    pub risk_free_rate: f64,
+    /// Return over the whole curve
+    pub total_return: f64,
+    /// Compound annual return
+    pub annualized_return: f64,
+    /// Annualized standard deviation of returns
+    pub annualized_volatility: f64,
+    /// Annualized excess return over volatility (none without volatility)
+    pub sharpe_ratio: Option<f64>,
+    /// Annualized excess return over downside deviation (none without losing periods)
+    pub sortino_ratio: Option<f64>,
+    /// Annualized return over the maximum drawdown (none without a drawdown)
+    pub calmar_ratio: Option<f64>,
+    /// Largest drawdown (none if equity never declined)
+    pub max_drawdown: Option<Drawdown>,
+}
+
+/// Computes risk-adjusted performance from equity history
+#[derive(Debug, Clone, Default)]
+pub struct RiskAnalytics {
+    risk_free_rate: f64,
+    periods_per_year: Option<f64>,
+}
+
+impl RiskAnalytics {
+    /// Create analytics with a zero risk-free rate
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the annual risk-free rate (as a decimal, e.g., 0.04 for 4%)
+    pub fn with_risk_free_rate(mut self, rate: f64) -> Self {
+        self.risk_free_rate = rate;
+        self
+    }
+
+    /// Set the number of periods per year (e.g., 252 for trading days)
+    ///
+    /// If not set, it is inferred from the spacing of the points, which must then
+    /// be regular (e.g., snapshots on every calendar day, not only trading days).
+    pub fn with_periods_per_year(mut self, periods: f64) -> Self {
+        self.periods_per_year = Some(periods);
+        self
+    }
+
+    /// Analyze an account's equity history
+    ///
+    /// Only interval snapshots are used, so fill and manual snapshots at irregular
+    /// times don't skew the statistics. Period returns exclude deposits,
+    /// withdrawals and transfers recorded on the snapshots, so moving cash in or
+    /// out doesn't count as performance.
+    pub fn analyze(&self, history: &EquityHistory) -> Result<Option<RiskReport>> {
+        let intervals = history
+            .snapshots()
+            .iter()
+            .filter(|snapshot| snapshot.kind == SnapshotKind::Interval);
+        self.analyze_points(&collapse(intervals))
+    }
+
+    /// Analyze an equity curve of (timestamp, equity) points in time order
+    ///
+    /// Points sharing a timestamp are collapsed to the last one. Returns `Ok(None)`
+    /// with fewer than two distinct points or non-positive equity, and
+    /// `Error::AnalyticsError` if the points are irregularly spaced and periods
+    /// per year weren't set.
+    pub fn analyze_curve(&self, curve: &[(DateTime<Utc>, Decimal)]) -> Result<Option<RiskReport>> {
+        self.analyze_points(&curve_points(curve))
+    }
+
+    fn analyze_points(&self, points: &[Point]) -> Result<Option<RiskReport>> {
+        let returns = match period_returns(points, &[]) {
+            Some(returns) => returns,
+            None => return Ok(None),
+        };
+        let start = points[0].timestamp;
+        let end = points[points.len() - 1].timestamp;
+        let years = (end - start).num_seconds() as f64 / SECONDS_PER_YEAR;
+        let periods_per_year = match self.periods_per_year {
+            Some(periods) => periods,
+            None if is_regular(points) => returns.len() as f64 / years,
+            None => {
+                return Err(Error::AnalyticsError {
+                    reason: "points are irregularly spaced; set periods per year".to_string(),
+                })
+            }
+        };
+
+        let growth = growth_index(&returns);
+        let total_return = growth[growth.len() - 1] - 1.0;
+        let annualized_return = (1.0 + total_return).powf(1.0 / years) - 1.0;
+
+        // Excess returns over the risk-free rate for the period length
+        let risk_free = (1.0 + self.risk_free_rate).powf(1.0 / periods_per_year) - 1.0;
+        let excess: Vec<f64> = returns.iter().map(|r| r - risk_free).collect();
+        let mean_excess = mean(&excess);
+
+        let volatility = std_dev(&returns);
+        let downside = (excess.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / excess.len() as f64).sqrt();
+        let annualize = periods_per_year.sqrt();
+
+        let max_drawdown = drawdown(points, &growth);
+        let calmar_ratio = max_drawdown
+            .as_ref()
+            .filter(|drawdown| drawdown.depth > 0.0)
+            .map(|drawdown| annualized_return / drawdown.depth);
+
+        Ok(Some(RiskReport {
+            start,
+            end,
+            periods: returns.len(),
+            periods_per_year,
+            risk_free_rate: self.risk_free_rate,
+            total_return,
+            annualized_return,
+            annualized_volatility: volatility * annualize,
+            sharpe_ratio: (volatility > 0.0).then(|| mean_excess / volatility * annualize),
+            sortino_ratio: (downside > 0.0).then(|| mean_excess / downside * annualize),
+            calmar_ratio,
+            max_drawdown,
+        }))
+    }
+
+    /// Compare an account's equity history against a benchmark's closing prices from a provider
+    ///
+    /// Bars are stamped with their start, so each close is taken as known at the
+    /// end of its bar. Returns `Ok(None)` if fewer than two bars end within the
+    /// account's history.
+    pub fn compare<H: HistoricalDataProvider>(
+        &self,
+        history: &EquityHistory,
+        market_history: &H,
+        benchmark: &Symbol,
+        interval: BarInterval,
+    ) -> Result<Option<BenchmarkReport>> {
+        let (start, end) = match (history.snapshots().first(), history.latest()) {
+            (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+            _ => return Ok(None),
+        };
+        let prices: Vec<(DateTime<Utc>, Price)> = market_history
+            .get_historical_data(benchmark, start - interval.duration(), end, interval)?
+            .into_iter()
+            .map(|bar| (bar.timestamp + interval.duration(), bar.close))
+            .collect();
+        Ok(self.compare_prices(history, benchmark, &prices))
+    }
+
+    /// Compare an account's equity history against benchmark prices in time order
+    ///
+    /// The account is sampled at each price's timestamp from the latest snapshot
+    /// at or before it, so no snapshot is paired with a price from its future,
+    /// and returns are compared period by period, net of external flows. Prices
+    /// after the last snapshot are skipped, and when several prices fall on the
+    /// same snapshot only the first is used, so snapshots should be at least as
+    /// frequent as the prices.
+    pub fn compare_prices(
+        &self,
+        history: &EquityHistory,
+        benchmark: &Symbol,
+        prices: &[(DateTime<Utc>, Price)],
+    ) -> Option<BenchmarkReport> {
+        let snapshots = collapse(history.snapshots().iter());
+        let last = snapshots.last()?.timestamp;
+        let mut points: Vec<Point> = Vec::new();
+        let mut benchmark_prices = Vec::new();
+        let mut sampled = None;
+        for (timestamp, price) in prices {
+            if *timestamp > last || price.0 <= Decimal::ZERO {
+                continue;
+            }
+            let index = snapshots.partition_point(|point| point.timestamp <= *timestamp);
+            if index == 0 || sampled == Some(index) {
+                continue;
+            }
+            sampled = Some(index);
+            points.push(Point {
+                timestamp: *timestamp,
+                ..snapshots[index - 1]
+            });
+            benchmark_prices.push(to_f64(price.0));
+        }
+
+        let returns = period_returns(&points, &[])?;
+        let benchmark_returns: Vec<f64> = benchmark_prices.windows(2).map(|pair| pair[1] / pair[0] - 1.0).collect();
+        let start = points[0].timestamp;
+        let end = points[points.len() - 1].timestamp;
+        let years = (end - start).num_seconds() as f64 / SECONDS_PER_YEAR;
+        let periods_per_year = self.periods_per_year.unwrap_or(returns.len() as f64 / years);
+
+        let risk_free = (1.0 + self.risk_free_rate).powf(1.0 / periods_per_year) - 1.0;
+        let benchmark_variance = covariance(&benchmark_returns, &benchmark_returns);
+        let beta = (benchmark_variance > 0.0).then(|| covariance(&returns, &benchmark_returns) / benchmark_variance);
+        let alpha = beta.map(|beta| {
+            (mean(&returns) - risk_free - beta * (mean(&benchmark_returns) - risk_free)) * periods_per_year
+        });
+        let deviation = std_dev(&returns) * std_dev(&benchmark_returns);
+        let correlation = (deviation > 0.0).then(|| covariance(&returns, &benchmark_returns) / deviation);
+
+        let active: Vec<f64> = returns.iter().zip(&benchmark_returns).map(|(r, b)| r - b).collect();
+        let tracking_error = std_dev(&active) * periods_per_year.sqrt();
+        let information_ratio = (tracking_error > 0.0).then(|| mean(&active) * periods_per_year / tracking_error);
+
+        let growth = growth_index(&returns);
+        let benchmark_growth = growth_index(&benchmark_returns);
+        let series = points
+            .iter()
+            .zip(growth.iter().zip(&benchmark_growth))
+            .map(|(point, (portfolio, benchmark))| RelativePoint {
+                timestamp: point.timestamp,
+                portfolio: *portfolio,
+                benchmark: *benchmark,
+                relative: portfolio / benchmark,
+            })
+            .collect();
+
+        Some(BenchmarkReport {
+            benchmark: benchmark.clone(),
+            start,
+            end,
+            periods: returns.len(),
+            periods_per_year,
+            total_return: growth[growth.len() - 1] - 1.0,
+            benchmark_return: benchmark_growth[benchmark_growth.len() - 1] - 1.0,
+            alpha,
+            beta,
+            correlation,
+            tracking_error,
+            information_ratio,
+            up_capture: capture(&returns, &benchmark_returns, |benchmark| benchmark > 0.0),
+            down_capture: capture(&returns, &benchmark_returns, |benchmark| benchmark < 0.0),
+            series,
+        })
+    }
+}
+
+/// Growth of the account and a benchmark from a common starting point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelativePoint {
+    /// Time of the point
+    pub timestamp: DateTime<Utc>,
+    /// Growth of 1 invested in the account (time-weighted)
+    pub portfolio: f64,
+    /// Growth of 1 invested in the benchmark
+    pub benchmark: f64,
+    /// Account growth relative to the benchmark (above 1 when outperforming)
+    pub relative: f64,
+}
+
+/// Performance of an account relative to a benchmark
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// Benchmark symbol
+    pub benchmark: Symbol,
+    /// Time of the first benchmark price compared
+    pub start: DateTime<Utc>,
+    /// Time of the last benchmark price compared
+    pub end: DateTime<Utc>,
+    /// Number of returns compared
+    pub periods: usize,
+    /// Periods per year used to annualize
+    pub periods_per_year: f64,
+    /// Time-weighted return of the account
+    pub total_return: f64,
+    /// Return of the benchmark
+    pub benchmark_return: f64,
+    /// Annualized Jensen's alpha over the risk-free rate (none without benchmark volatility)
+    pub alpha: Option<f64>,
+    /// Sensitivity of account returns to benchmark returns (none without benchmark volatility)
+    pub beta: Option<f64>,
+    /// Correlation of account and benchmark returns (none without volatility)
+    pub correlation: Option<f64>,
+    /// Annualized standard deviation of the return differences
+    pub tracking_error: f64,
+    /// Annualized active return over tracking error (none without tracking error)
+    pub information_ratio: Option<f64>,
+    /// Average account return over average benchmark return in rising periods (none without any)
+    pub up_capture: Option<f64>,
+    /// Average account return over average benchmark return in falling periods (none without any)
+    pub down_capture: Option<f64>,
+    /// Growth of the account and benchmark at each benchmark price compared, for charting
+    pub series: Vec<RelativePoint>,
+}
+
+impl BenchmarkReport {
+    /// Write the relative performance series as CSV
+    pub fn write_series_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(["timestamp", "portfolio", "benchmark", "relative"])?;
+        for point in &self.series {
+            writer.write_record([
+                point.timestamp.to_rfc3339(),
+                point.portfolio.to_string(),
+                point.benchmark.to_string(),
+                point.relative.to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Time- and money-weighted returns over a date range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReturnReport {
+    /// Time of the first snapshot in the range
+    pub start: DateTime<Utc>,
+    /// Time of the last snapshot in the range
+    pub end: DateTime<Utc>,
+    /// Equity at the start
+    pub starting_equity: Decimal,
+    /// Equity at the end
+    pub ending_equity: Decimal,
+    /// Net deposits, withdrawals and transfers within the range
+    pub net_external_flows: Decimal,
+    /// Time-weighted return, chaining the returns between snapshots
+    pub time_weighted_return: f64,
+    /// Time-weighted return, annualized
+    pub annualized_time_weighted_return: f64,
+    /// Money-weighted return (annualized internal rate of return), none if it can't be solved
+    pub money_weighted_return: Option<f64>,
+}
+
+/// Compute time- and money-weighted returns from the snapshots taken from `start` to `end` (inclusive)
+///
+/// External flows are dated from the account's cash ledger. The money-weighted
+/// return discounts each flow from its own date, and the time-weighted return
+/// weights flows by the time they were invested within each period between
+/// snapshots (Modified Dietz). Returns `None` with fewer than two distinct
+/// snapshots in the range or non-positive equity.
+pub fn returns_between(
+    history: &EquityHistory,
+    ledger: &[CashEntry],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<ReturnReport> {
+    let points = collapse(
+        history
+            .snapshots()
+            .iter()
+            .filter(|snapshot| snapshot.timestamp >= start && snapshot.timestamp <= end),
+    );
+    let flows: Vec<(DateTime<Utc>, Decimal)> = ledger
+        .iter()
+        .filter(|entry| entry.is_external_flow())
+        .map(|entry| (entry.timestamp, entry.amount))
+        .collect();
+    let returns = period_returns(&points, &flows)?;
+
+    let first = points[0];
+    let last = points[points.len() - 1];
+    let years = (last.timestamp - first.timestamp).num_seconds() as f64 / SECONDS_PER_YEAR;
+    let time_weighted_return = growth_index(&returns)[returns.len()] - 1.0;
+
+    // Cash flows from the investor's side: money in is negative, money out positive
+    let counts = flow_counts(&points, &flows);
+    let mut cash_flows = vec![(first.timestamp, -to_f64(first.equity))];
+    for (timestamp, amount) in &flows[counts[0]..counts[counts.len() - 1]] {
+        cash_flows.push((*timestamp, -to_f64(*amount)));
+    }
+    cash_flows.push((last.timestamp, to_f64(last.equity)));
+
+    Some(ReturnReport {
+        start: first.timestamp,
+        end: last.timestamp,
+        starting_equity: first.equity,
+        ending_equity: last.equity,
+        net_external_flows: last.flows - first.flows,
+        time_weighted_return,
+        annualized_time_weighted_return: (1.0 + time_weighted_return).powf(1.0 / years) - 1.0,
+        money_weighted_return: internal_rate_of_return(&cash_flows),
+    })
+}
+
+/// Find the largest peak-to-trough decline of an equity curve
+pub fn max_drawdown(curve: &[(DateTime<Utc>, Decimal)]) -> Option<Drawdown> {
+    let points = curve_points(curve);
+    let equity: Vec<f64> = points.iter().map(|point| to_f64(point.equity)).collect();
+    drawdown(&points, &equity)
+}
+
+/// Equity and cumulative external flows at a point in time
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    timestamp: DateTime<Utc>,
+    equity: Decimal,
+    flows: Decimal,
+}
+
+/// Keep the last snapshot for each timestamp
+fn collapse<'a, I: Iterator<Item = &'a EquitySnapshot>>(snapshots: I) -> Vec<Point> {
+    let mut points: Vec<Point> = Vec::new();
+    for snapshot in snapshots {
+        let point = Point {
+            timestamp: snapshot.timestamp,
+            equity: snapshot.equity,
+            flows: snapshot.external_flows,
+        };
+        match points.last_mut() {
+            Some(last) if last.timestamp == point.timestamp => *last = point,
+            _ => points.push(point),
+        }
+    }
+    points
+}
+
+/// Keep the last point of an equity curve for each timestamp
+fn curve_points(curve: &[(DateTime<Utc>, Decimal)]) -> Vec<Point> {
+    let mut points: Vec<Point> = Vec::with_capacity(curve.len());
+    for &(timestamp, equity) in curve {
+        let point = Point {
+            timestamp,
+            equity,
+            flows: Decimal::ZERO,
+        };
+        match points.last_mut() {
+            Some(last) if last.timestamp == timestamp => *last = point,
+            _ => points.push(point),
+        }
+    }
+    points
+}
+
+/// Get the return of each period between points, net of external flows
+///
+/// Dated flows (in ledger order) are weighted by the share of the period they
+/// were invested for; flows on the points without a dated entry count at the
+/// end of their period. Returns `None` with fewer than two points or
+/// non-positive equity or capital.
+fn period_returns(points: &[Point], flows: &[(DateTime<Utc>, Decimal)]) -> Option<Vec<f64>> {
+    if points.len() < 2 || points.iter().any(|point| point.equity <= Decimal::ZERO) {
+        return None;
+    }
+    let counts = flow_counts(points, flows);
+    points
+        .windows(2)
+        .zip(counts.windows(2))
+        .map(|(pair, count)| {
+            let span = (pair[1].timestamp - pair[0].timestamp).num_seconds() as f64;
+            let weighted: f64 = flows[count[0]..count[1]]
+                .iter()
+                .map(|(timestamp, amount)| {
+                    to_f64(*amount) * (pair[1].timestamp - *timestamp).num_seconds() as f64 / span
+                })
+                .sum();
+            let capital = to_f64(pair[0].equity) + weighted;
+            let gain = to_f64(pair[1].equity - pair[0].equity - (pair[1].flows - pair[0].flows));
+            (capital > 0.0).then(|| gain / capital)
+        })
+        .collect()
+}
+
+/// Count the dated flows posted before each point was taken
+///
+/// Flows sharing a point's timestamp are split where their running total
+/// matches the cumulative flows recorded on the point.
+fn flow_counts(points: &[Point], flows: &[(DateTime<Utc>, Decimal)]) -> Vec<usize> {
+    let mut totals = Vec::with_capacity(flows.len() + 1);
+    totals.push(Decimal::ZERO);
+    for (_, amount) in flows {
+        totals.push(totals[totals.len() - 1] + amount);
+    }
+    points
+        .iter()
+        .map(|point| {
+            let before = flows.partition_point(|(timestamp, _)| *timestamp < point.timestamp);
+            let through = flows.partition_point(|(timestamp, _)| *timestamp <= point.timestamp);
+            (before..=through)
+                .find(|count| totals[*count] == point.flows)
+                .unwrap_or(through)
+        })
+        .collect()
+}
+
+/// Check whether the gaps between points are roughly equal
+fn is_regular(points: &[Point]) -> bool {
+    let gaps: Vec<i64> = points
+        .windows(2)
+        .map(|pair| (pair[1].timestamp - pair[0].timestamp).num_seconds())
+        .collect();
+    match (gaps.iter().min(), gaps.iter().max()) {
+        (Some(&shortest), Some(&longest)) => longest as f64 <= shortest as f64 * MAX_SPACING_RATIO,
+        _ => false,
+    }
+}
+
+/// Compound period returns into a growth index starting at 1
+fn growth_index(returns: &[f64]) -> Vec<f64> {
+    let mut index = Vec::with_capacity(returns.len() + 1);
+    index.push(1.0);
+    for r in returns {
+        index.push(index[index.len() - 1] * (1.0 + r));
+    }
+    index
+}
+
+/// Find the largest decline of a value series, reporting the equity at each point
+fn drawdown(points: &[Point], values: &[f64]) -> Option<Drawdown> {
+    // Track the worst decline as (depth, peak index, trough index)
+    let mut peak = 0;
+    let mut worst: Option<(f64, usize, usize)> = None;
+
+    for (index, value) in values.iter().enumerate() {
+        if *value > values[peak] {
+            peak = index;
+        } else if values[peak] > 0.0 {
+            let depth = 1.0 - value / values[peak];
+            if depth > worst.map_or(0.0, |(depth, ..)| depth) {
+                worst = Some((depth, peak, index));
+            }
+        }
+    }
+
+    let (depth, peak, trough) = worst?;
+    let end = points[points.len() - 1].timestamp;
+    let recovered_at = (trough..values.len())
+        .find(|index| values[*index] >= values[peak])
+        .map(|index| points[index].timestamp);
+    let peak_at = points[peak].timestamp;
+    let trough_at = points[trough].timestamp;
+
+    Some(Drawdown {
+        depth,
+        peak_equity: points[peak].equity,
+        trough_equity: points[trough].equity,
+        peak_at,
+        trough_at,
+        recovered_at,
+        duration: recovered_at.unwrap_or(end) - peak_at,
+        recovery_time: recovered_at.map(|recovered_at| recovered_at - trough_at),
+    })
+}
+
+/// Solve for the annual rate at which dated cash flows have zero net present value
+fn internal_rate_of_return(cash_flows: &[(DateTime<Utc>, f64)]) -> Option<f64> {
+    let start = cash_flows.first()?.0;
+    let present_value = |rate: f64| {
+        cash_flows
+            .iter()
+            .map(|(timestamp, amount)| {
+                let years = (*timestamp - start).num_seconds() as f64 / SECONDS_PER_YEAR;
+                amount / (1.0 + rate).powf(years)
+            })
+            .sum::<f64>()
+    };
+
+    // Bisect between a near-total loss and a rate high enough to change sign
+    let mut low = -0.999_999;
+    let mut high = 1.0;
+    let low_value = present_value(low);
+    while present_value(high).signum() == low_value.signum() {
+        high *= 2.0;
+        if high > 1e9 {
+            return None;
+        }
+    }
+
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if present_value(mid).signum() == low_value.signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+/// Convert a decimal to a float
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+/// Get the ratio of average returns in the periods the benchmark return matches
+fn capture(returns: &[f64], benchmark_returns: &[f64], matches: impl Fn(f64) -> bool) -> Option<f64> {
+    let (portfolio, benchmark): (Vec<f64>, Vec<f64>) = returns
+        .iter()
+        .zip(benchmark_returns)
+        .filter(|(_, benchmark)| matches(**benchmark))
+        .unzip();
+    if benchmark.is_empty() {
+        return None;
+    }
+    Some(mean(&portfolio) / mean(&benchmark))
+}
+
+/// Get the sample covariance of two series
+fn covariance(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 {
+        return 0.0;
+    }
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    a.iter().zip(b).map(|(a, b)| (a - mean_a) * (b - mean_b)).sum::<f64>() / (a.len() - 1) as f64
+}
+
+/// Get the mean of a series
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Get the sample standard deviation of a series
+fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(values);
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    variance.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::CashEntryKind;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    const YEAR: i64 = SECONDS_PER_YEAR as i64;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn curve(step: Duration, equity: &[i64]) -> Vec<(DateTime<Utc>, Decimal)> {
+        equity
+            .iter()
+            .enumerate()
+            .map(|(index, equity)| (start() + step * index as i32, Decimal::from(*equity)))
+            .collect()
+    }
+
+    fn snapshot(timestamp: DateTime<Utc>, kind: SnapshotKind, equity: i64) -> EquitySnapshot {
+        EquitySnapshot::new(timestamp, kind, Decimal::from(equity), BTreeMap::new())
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn max_drawdown_reports_peak_trough_and_recovery() {
+        let drawdown = max_drawdown(&curve(Duration::days(1), &[100, 120, 90, 130])).unwrap();
+        assert_close(drawdown.depth, 0.25);
+        assert_eq!(drawdown.peak_equity, Decimal::from(120));
+        assert_eq!(drawdown.trough_equity, Decimal::from(90));
+        assert_eq!(drawdown.peak_at, start() + Duration::days(1));
+        assert_eq!(drawdown.trough_at, start() + Duration::days(2));
+        assert_eq!(drawdown.recovered_at, Some(start() + Duration::days(3)));
+        assert_eq!(drawdown.duration, Duration::days(2));
+        assert_eq!(drawdown.recovery_time, Some(Duration::days(1)));
+    }
+
+    #[test]
+    fn max_drawdown_is_none_for_rising_equity() {
+        assert!(max_drawdown(&curve(Duration::days(1), &[100, 110, 120])).is_none());
+    }
+
+    #[test]
+    fn unrecovered_drawdown_lasts_until_the_end() {
+        let drawdown = max_drawdown(&curve(Duration::days(1), &[100, 80, 90])).unwrap();
+        assert_close(drawdown.depth, 0.2);
+        assert_eq!(drawdown.recovered_at, None);
+        assert_eq!(drawdown.duration, Duration::days(2));
+        assert_eq!(drawdown.recovery_time, None);
+    }
+
+    #[test]
+    fn analyze_curve_computes_ratios() {
+        // Returns of +20% and -10% a year apart: mean 5%, sample deviation sqrt(0.045)
+        let report = RiskAnalytics::new()
+            .analyze_curve(&curve(Duration::seconds(YEAR), &[100, 120, 108]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.periods, 2);
+        assert_close(report.periods_per_year, 1.0);
+        assert_close(report.total_return, 0.08);
+        assert_close(report.annualized_return, 1.08_f64.sqrt() - 1.0);
+        assert_close(report.annualized_volatility, 0.045_f64.sqrt());
+        assert_close(report.sharpe_ratio.unwrap(), 0.05 / 0.045_f64.sqrt());
+        // Downside deviation is sqrt((0 + 0.1^2) / 2)
+        assert_close(report.sortino_ratio.unwrap(), 0.05 / 0.005_f64.sqrt());
+        assert_close(report.calmar_ratio.unwrap(), (1.08_f64.sqrt() - 1.0) / 0.1);
+        assert_close(report.max_drawdown.unwrap().depth, 0.1);
+    }
+
+    #[test]
+    fn analyze_curve_subtracts_the_risk_free_rate() {
+        // At a 5% risk-free rate the excess returns are +15% and -15%
+        let report = RiskAnalytics::new()
+            .with_risk_free_rate(0.05)
+            .analyze_curve(&curve(Duration::seconds(YEAR), &[100, 120, 108]))
+            .unwrap()
+            .unwrap();
+        assert_close(report.sharpe_ratio.unwrap(), 0.0);
+        assert_close(report.sortino_ratio.unwrap(), 0.0);
+    }
+
+    #[test]
+    fn irregular_spacing_requires_periods_per_year() {
+        let mut irregular = curve(Duration::days(1), &[100, 101, 102]);
+        irregular.push((start() + Duration::days(5), Decimal::from(103)));
+        assert!(matches!(
+            RiskAnalytics::new().analyze_curve(&irregular),
+            Err(Error::AnalyticsError { .. })
+        ));
+
+        let report = RiskAnalytics::new()
+            .with_periods_per_year(252.0)
+            .analyze_curve(&irregular)
+            .unwrap()
+            .unwrap();
+        assert_close(report.periods_per_year, 252.0);
+    }
+
+    #[test]
+    fn analyze_uses_only_interval_snapshots() {
+        let mut history = EquityHistory::new();
+        history.push(snapshot(start(), SnapshotKind::Interval, 100));
+        history.push(snapshot(start() + Duration::hours(3), SnapshotKind::Fill, 50));
+        history.push(snapshot(start() + Duration::hours(4), SnapshotKind::Manual, 60));
+        history.push(snapshot(start() + Duration::days(1), SnapshotKind::Interval, 110));
+        history.push(snapshot(start() + Duration::days(2), SnapshotKind::Interval, 121));
+
+        let report = RiskAnalytics::new().analyze(&history).unwrap().unwrap();
+        assert_eq!(report.periods, 2);
+        assert_close(report.periods_per_year, 365.25);
+        assert_close(report.total_return, 0.21);
+        assert!(report.max_drawdown.is_none());
+    }
+
+    fn flow_history(points: &[(i64, i64, i64)]) -> EquityHistory {
+        let mut history = EquityHistory::new();
+        for (seconds, equity, flows) in points {
+            history.push(
+                snapshot(start() + Duration::seconds(*seconds), SnapshotKind::Interval, *equity)
+                    .with_external_flows(Decimal::from(*flows)),
+            );
+        }
+        history
+    }
+
+    fn deposit(seconds: i64, amount: i64) -> CashEntry {
+        CashEntry::new(CashEntryKind::Deposit, Decimal::from(amount), None, start() + Duration::seconds(seconds))
+    }
+
+    #[test]
+    fn returns_between_weights_flows_by_date() {
+        // 100 invested for the year and 100 deposited halfway: 50 / (100 + 100 * 0.5)
+        let history = flow_history(&[(0, 100, 0), (YEAR, 250, 100)]);
+        let ledger = [deposit(YEAR / 2, 100)];
+        let report = returns_between(&history, &ledger, start(), start() + Duration::seconds(YEAR)).unwrap();
+        assert_eq!(report.net_external_flows, Decimal::from(100));
+        assert_close(report.time_weighted_return, 1.0 / 3.0);
+        let growth = (11.0_f64.sqrt() - 1.0) / 2.0;
+        assert_close(report.money_weighted_return.unwrap(), growth * growth - 1.0);
+    }
+
+    #[test]
+    fn returns_between_splits_flows_at_snapshot_time() {
+        let end = start() + Duration::seconds(YEAR);
+        let ledger = [deposit(0, 100)];
+
+        // Deposited just after the first snapshot: invested for the whole period
+        let after = flow_history(&[(0, 100, 0), (YEAR, 220, 100)]);
+        let report = returns_between(&after, &ledger, start(), end).unwrap();
+        assert_close(report.time_weighted_return, 0.1);
+        assert_close(report.money_weighted_return.unwrap(), 0.1);
+
+        // Deposited just before it: already part of the starting equity
+        let before = flow_history(&[(0, 200, 100), (YEAR, 220, 100)]);
+        let report = returns_between(&before, &ledger, start(), end).unwrap();
+        assert_eq!(report.net_external_flows, Decimal::ZERO);
+        assert_close(report.time_weighted_return, 0.1);
+        assert_close(report.money_weighted_return.unwrap(), 0.1);
+    }
+
+    #[test]
+    fn compare_prices_samples_at_benchmark_times() {
+        // End-of-day snapshots, with an intraday fill snapshot that must not see the day's close
+        let mut history = EquityHistory::new();
+        for (day, equity) in [100, 104, 102, 999].iter().enumerate() {
+            let close = start() + Duration::days(day as i64) + Duration::hours(20);
+            history.push(snapshot(close - Duration::hours(4), SnapshotKind::Fill, 1));
+            history.push(snapshot(close, SnapshotKind::Interval, *equity));
+        }
+        // Benchmark returns +1% then -2%, known at each midnight after the close; the last
+        // price comes after the last snapshot
+        let prices: Vec<(DateTime<Utc>, Price)> = [200.0, 202.0, 197.96, 250.0]
+            .iter()
+            .enumerate()
+            .map(|(day, price)| (start() + Duration::days(day as i64 + 1), Price::from_f64(*price)))
+            .collect();
+
+        let report = RiskAnalytics::new()
+            .compare_prices(&history, &Symbol::new("SPY"), &prices)
+            .unwrap();
+        assert_eq!(report.periods, 2);
+        assert_eq!(report.start, start() + Duration::days(1));
+        assert_close(report.total_return, 0.02);
+        assert_close(report.benchmark_return, 0.0102 - 0.0204);
+        // Account returns +4% and -1.923%: beta is their spread over the benchmark's
+        assert_close(report.beta.unwrap(), (0.04 + 2.0 / 104.0) / 0.03);
+        assert_close(report.correlation.unwrap(), 1.0);
+    }
+
+    #[test]
+    fn internal_rate_of_return_solves_single_period() {
+        let cash_flows = [(start(), -100.0), (start() + Duration::seconds(YEAR), 110.0)];
+        assert_close(internal_rate_of_return(&cash_flows).unwrap(), 0.1);
+    }
+
+    #[test]
+    fn internal_rate_of_return_discounts_by_date() {
+        // 100(1 + r) + 100 sqrt(1 + r) = 250, so sqrt(1 + r) = (sqrt(11) - 1) / 2
+        let cash_flows = [
+            (start(), -100.0),
+            (start() + Duration::seconds(YEAR / 2), -100.0),
+            (start() + Duration::seconds(YEAR), 250.0),
+        ];
+        let growth = (11.0_f64.sqrt() - 1.0) / 2.0;
+        assert_close(internal_rate_of_return(&cash_flows).unwrap(), growth * growth - 1.0);
+    }
+}
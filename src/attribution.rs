@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::order::{OrderSide, Trade};
+use crate::types::{Price, Symbol};
+
+/// Realized and unrealized P&L and commission attributed to one bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlBreakdown {
+    /// Profit/loss realized on exits, before commission
+    pub realized_pnl: Decimal,
+    /// Profit/loss on quantity still held, marked at the current price
+    pub unrealized_pnl: Decimal,
+    /// Commission paid
+    pub commission: Decimal,
+}
+
+impl PnlBreakdown {
+    /// Get the realized plus unrealized profit/loss, net of commission
+    pub fn net_pnl(&self) -> Decimal {
+        self.realized_pnl + self.unrealized_pnl - self.commission
+    }
+
+    fn add(&mut self, other: &PnlBreakdown) {
+        self.realized_pnl += other.realized_pnl;
+        self.unrealized_pnl += other.unrealized_pnl;
+        self.commission += other.commission;
+    }
+}
+
+/// P&L broken down by symbol, position side, day and strategy
+///
+/// Each strategy (and untagged trades) keeps its own average-cost position per
+/// symbol, so a strategy's realized P&L is measured against its own entries.
+/// Days only hold realized P&L and commission, as unrealized P&L belongs to no day.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlAttribution {
+    /// Totals across all buckets
+    pub total: PnlBreakdown,
+    /// Breakdown by symbol
+    pub by_symbol: BTreeMap<String, PnlBreakdown>,
+    /// Breakdown for long positions
+    pub long: PnlBreakdown,
+    /// Breakdown for short positions
+    pub short: PnlBreakdown,
+    /// Breakdown by calendar day of the trade
+    pub by_day: BTreeMap<NaiveDate, PnlBreakdown>,
+    /// Breakdown by trade strategy tag
+    pub by_strategy: BTreeMap<String, PnlBreakdown>,
+    /// Breakdown for trades without a strategy tag
+    pub untagged: PnlBreakdown,
+}
+
+/// Position held by one strategy in one symbol
+#[derive(Debug, Clone, Copy, Default)]
+struct Book {
+    /// Signed quantity (negative when short)
+    quantity: Decimal,
+    average_price: Decimal,
+}
+
+impl PnlAttribution {
+    /// Attribute P&L from trades by the strategy tag copied onto each trade
+    ///
+    /// Trades are replayed in time order, with days taken in `time_zone`. Open
+    /// positions are marked with `mark`; positions it can't price get no unrealized P&L.
+    pub fn from_trades<'a, I, F>(trades: I, time_zone: Tz, mark: F) -> Self
+    where
+        I: IntoIterator<Item = &'a Trade>,
+        F: Fn(&Symbol) -> Option<Price>,
+    {
+        let mut trades: Vec<&Trade> = trades.into_iter().collect();
+        trades.sort_by_key(|trade| trade.timestamp);
+
+        let mut attribution = Self::default();
+        let mut books: HashMap<(String, Option<&str>), Book> = HashMap::new();
+
+        for trade in trades {
+            let strategy = trade.strategy.as_deref();
+            let book = books.entry((trade.symbol.0.clone(), strategy)).or_default();
+            let signed = match trade.side {
+                OrderSide::Buy => trade.quantity.0,
+                OrderSide::Sell => -trade.quantity.0,
+            };
+            let day = trade.timestamp.with_timezone(&time_zone).date_naive();
+
+            // Commission goes to the side the trade closes, or opens if flat
+            let was_long = book.quantity > Decimal::ZERO || (book.quantity.is_zero() && signed > Decimal::ZERO);
+            let mut commission = PnlBreakdown {
+                commission: trade.commission,
+                ..PnlBreakdown::default()
+            };
+
+            // Close against the existing position first
+            let closing = if book.quantity.is_sign_positive() != signed.is_sign_positive() {
+                signed.abs().min(book.quantity.abs())
+            } else {
+                Decimal::ZERO
+            };
+            if closing > Decimal::ZERO {
+                let per_unit = if book.quantity > Decimal::ZERO {
+                    trade.price.0 - book.average_price
+                } else {
+                    book.average_price - trade.price.0
+                };
+                commission.realized_pnl = per_unit * closing;
+                book.quantity += if book.quantity > Decimal::ZERO { -closing } else { closing };
+            }
+
+            // Any remainder opens or adds to a position
+            let opening = signed.abs() - closing;
+            if opening > Decimal::ZERO {
+                let held = book.quantity.abs();
+                book.average_price = (book.average_price * held + trade.price.0 * opening) / (held + opening);
+                book.quantity += if signed > Decimal::ZERO { opening } else { -opening };
+            }
+
+            attribution.record(&trade.symbol, was_long, Some(day), strategy, &commission);
+        }
+
+        for ((symbol, strategy), book) in books {
+            if book.quantity.is_zero() {
+                continue;
+            }
+            let symbol = Symbol(symbol);
+            if let Some(price) = mark(&symbol) {
+                let unrealized = PnlBreakdown {
+                    unrealized_pnl: (price.0 - book.average_price) * book.quantity,
+                    ..PnlBreakdown::default()
+                };
+                attribution.record(&symbol, book.quantity > Decimal::ZERO, None, strategy, &unrealized);
+            }
+        }
+
+        attribution
+    }
+
+    /// Add an amount to every bucket it belongs to
+    fn record(
+        &mut self,
+        symbol: &Symbol,
+        long: bool,
+        day: Option<NaiveDate>,
+        strategy: Option<&str>,
+        amount: &PnlBreakdown,
+    ) {
+        self.total.add(amount);
+        self.by_symbol.entry(symbol.0.clone()).or_default().add(amount);
+        if long {
+            self.long.add(amount);
+        } else {
+            self.short.add(amount);
+        }
+        if let Some(day) = day {
+            self.by_day.entry(day).or_default().add(amount);
+        }
+        match strategy {
+            Some(strategy) => self.by_strategy.entry(strategy.to_string()).or_default().add(amount),
+            None => self.untagged.add(amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OrderId, Quantity};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap()
+    }
+
+    fn trade(hours: i64, side: OrderSide, quantity: Decimal, price: Decimal, strategy: Option<&str>) -> Trade {
+        Trade {
+            timestamp: start() + Duration::hours(hours),
+            strategy: strategy.map(str::to_string),
+            ..Trade::new(OrderId::new(), Symbol::new("AAA"), side, Quantity(quantity), Price(price), dec!(1))
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    #[test]
+    fn strategies_are_measured_against_their_own_entries() {
+        let trades = vec![
+            trade(0, OrderSide::Buy, dec!(10), dec!(100), Some("a")),
+            trade(1, OrderSide::Buy, dec!(10), dec!(120), Some("b")),
+            trade(24, OrderSide::Sell, dec!(10), dec!(130), Some("a")),
+        ];
+
+        let attribution = PnlAttribution::from_trades(&trades, Tz::UTC, |_| Some(Price(dec!(125))));
+
+        let a = attribution.by_strategy["a"];
+        assert_eq!((a.realized_pnl, a.unrealized_pnl, a.commission), (dec!(300), dec!(0), dec!(2)));
+        let b = attribution.by_strategy["b"];
+        assert_eq!((b.realized_pnl, b.unrealized_pnl, b.commission), (dec!(0), dec!(50), dec!(1)));
+        assert_eq!(attribution.untagged, PnlBreakdown::default());
+
+        assert_eq!(attribution.total.net_pnl(), dec!(347));
+        assert_eq!(attribution.by_symbol["AAA"], attribution.total);
+        assert_eq!(attribution.long, attribution.total);
+        assert_eq!(attribution.by_day[&date(2)].commission, dec!(2));
+        assert_eq!(attribution.by_day[&date(3)].realized_pnl, dec!(300));
+    }
+
+    #[test]
+    fn short_positions_and_days_use_the_time_zone() {
+        // 03:00 UTC on January 3rd is still January 2nd in New York
+        let trades = vec![
+            trade(12, OrderSide::Sell, dec!(5), dec!(100), None),
+            trade(13, OrderSide::Buy, dec!(5), dec!(90), None),
+        ];
+
+        let attribution = PnlAttribution::from_trades(&trades, chrono_tz::America::New_York, |_| None);
+
+        assert_eq!(attribution.short.realized_pnl, dec!(50));
+        assert_eq!(attribution.short.commission, dec!(2));
+        assert_eq!(attribution.long, PnlBreakdown::default());
+        assert_eq!(attribution.untagged, attribution.total);
+        assert_eq!(attribution.by_day.keys().copied().collect::<Vec<_>>(), vec![date(2)]);
+    }
+
+    #[test]
+    fn a_fill_that_flips_the_position_realizes_then_opens() {
+        let trades = vec![
+            trade(0, OrderSide::Buy, dec!(10), dec!(100), None),
+            trade(1, OrderSide::Sell, dec!(15), dec!(110), None),
+        ];
+
+        let attribution = PnlAttribution::from_trades(&trades, Tz::UTC, |_| Some(Price(dec!(100))));
+
+        assert_eq!(attribution.long.realized_pnl, dec!(100));
+        assert_eq!(attribution.short.unrealized_pnl, dec!(50));
+        assert_eq!(attribution.total.net_pnl(), dec!(148));
+    }
+}
@@ -0,0 +1,303 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::market::HistoricalDataPoint;
+
+/// Represents the length of a bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BarInterval {
+    /// Bars spanning a number of minutes (must divide a day evenly)
+    Minutes(u32),
+    /// Bars spanning a number of hours (must divide a day evenly)
+    Hours(u32),
+    /// Daily bars (only one day is supported)
+    Days(u32),
+}
+
+impl BarInterval {
+    /// One minute bars
+    pub const ONE_MINUTE: BarInterval = BarInterval::Minutes(1);
+    /// Five minute bars
+    pub const FIVE_MINUTES: BarInterval = BarInterval::Minutes(5);
+    /// Fifteen minute bars
+    pub const FIFTEEN_MINUTES: BarInterval = BarInterval::Minutes(15);
+    /// One hour bars
+    pub const ONE_HOUR: BarInterval = BarInterval::Hours(1);
+    /// Daily bars
+    pub const ONE_DAY: BarInterval = BarInterval::Days(1);
+
+    /// Get the length of the interval
+    pub fn duration(&self) -> Duration {
+        match *self {
+            BarInterval::Minutes(minutes) => Duration::minutes(minutes as i64),
+            BarInterval::Hours(hours) => Duration::hours(hours as i64),
+            BarInterval::Days(days) => Duration::days(days as i64),
+        }
+    }
+
+    /// Check if the interval is daily
+    pub fn is_daily(&self) -> bool {
+        matches!(self, BarInterval::Days(_))
+    }
+
+    /// Check that the interval can be used for resampling
+    pub fn validate(&self) -> Result<()> {
+        let supported = match *self {
+            BarInterval::Minutes(minutes) => minutes > 0 && 1440 % minutes == 0,
+            BarInterval::Hours(hours) => hours > 0 && 24 % hours == 0,
+            BarInterval::Days(days) => days == 1,
+        };
+
+        if supported {
+            Ok(())
+        } else {
+            Err(Error::MarketDataError {
+                reason: format!("Unsupported bar interval: {}", self),
+            })
+        }
+    }
+}
+
+impl fmt::Display for BarInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BarInterval::Minutes(minutes) => write!(f, "{}m", minutes),
+            BarInterval::Hours(hours) => write!(f, "{}h", hours),
+            BarInterval::Days(days) => write!(f, "{}d", days),
+        }
+    }
+}
+
+impl FromStr for BarInterval {
+    type Err = Error;
+
+    /// Parse an interval such as `1m`, `5m`, `1h` or `1d`
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let unsupported = || Error::MarketDataError {
+            reason: format!("Unsupported bar interval: '{}'", value),
+        };
+
+        let split = value.find(|c: char| !c.is_ascii_digit()).ok_or_else(unsupported)?;
+        let (count, unit) = value.split_at(split);
+        let count: u32 = count.parse().map_err(|_| unsupported())?;
+
+        let interval = match unit {
+            "m" | "min" => BarInterval::Minutes(count),
+            "h" | "H" => BarInterval::Hours(count),
+            "d" | "D" => BarInterval::Days(count),
+            _ => return Err(unsupported()),
+        };
+
+        interval.validate()?;
+        Ok(interval)
+    }
+}
+
+/// Defines where trading days begin, so daily bars follow the session rather than UTC midnight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionBoundary {
+    /// Time zone of the session
+    pub time_zone: Tz,
+    /// Local time at which a trading day begins (e.g., 18:00 for futures trading the next day)
+    pub day_start: NaiveTime,
+}
+
+impl Default for SessionBoundary {
+    fn default() -> Self {
+        Self::new(Tz::UTC)
+    }
+}
+
+impl SessionBoundary {
+    /// Create a session boundary with days starting at local midnight
+    pub fn new(time_zone: Tz) -> Self {
+        Self {
+            time_zone,
+            day_start: NaiveTime::MIN,
+        }
+    }
+
+    /// Set the local time at which a trading day begins
+    pub fn with_day_start(mut self, day_start: NaiveTime) -> Self {
+        self.day_start = day_start;
+        self
+    }
+
+    /// Get the start of the trading day containing `timestamp`
+    pub fn day_start_of(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let local = timestamp.with_timezone(&self.time_zone).naive_local();
+        let mut date = local.date();
+        if local.time() < self.day_start {
+            date = date.pred_opt().unwrap_or(date);
+        }
+        self.localize(date.and_time(self.day_start))
+    }
+
+    /// Get the start of the bar containing `timestamp`
+    ///
+    /// Intraday bars are aligned to the start of the trading day.
+    pub fn bucket_start(&self, timestamp: DateTime<Utc>, interval: BarInterval) -> DateTime<Utc> {
+        let day_start = self.day_start_of(timestamp);
+        if interval.is_daily() {
+            return day_start;
+        }
+
+        let length = interval.duration().num_seconds();
+        let elapsed = (timestamp - day_start).num_seconds();
+        day_start + Duration::seconds(elapsed - elapsed.rem_euclid(length))
+    }
+
+    /// Convert a local time to UTC, skipping forward over DST gaps
+    fn localize(&self, naive: chrono::NaiveDateTime) -> DateTime<Utc> {
+        let mut naive = naive;
+        loop {
+            if let Some(local) = self.time_zone.from_local_datetime(&naive).earliest() {
+                return local.with_timezone(&Utc);
+            }
+            naive += Duration::minutes(30);
+        }
+    }
+}
+
+/// Aggregate bars into coarser bars
+///
+/// Each output bar takes the open of its first bar, the highest high, the lowest low,
+/// the close of its last bar and the summed volume, and is stamped with the start of
+/// its interval. Input bars must be sorted by timestamp. The input spacing is taken
+/// as the smallest gap between consecutive bars of a symbol, and an interval finer
+/// than it is an error, as bars can't be split.
+pub fn resample(
+    bars: &[HistoricalDataPoint],
+    interval: BarInterval,
+    session: &SessionBoundary,
+) -> Result<Vec<HistoricalDataPoint>> {
+    interval.validate()?;
+    if let Some(spacing) = bar_spacing(bars) {
+        if interval.duration() < spacing {
+            return Err(Error::MarketDataError {
+                reason: format!(
+                    "Can't resample bars spaced {} seconds apart to the finer interval {}",
+                    spacing.num_seconds(),
+                    interval
+                ),
+            });
+        }
+    }
+
+    let mut resampled: Vec<HistoricalDataPoint> = Vec::new();
+    for bar in bars {
+        let start = session.bucket_start(bar.timestamp, interval);
+        match resampled.last_mut() {
+            Some(current) if current.timestamp == start && current.symbol == bar.symbol => {
+                if bar.high.0 > current.high.0 {
+                    current.high = bar.high;
+                }
+                if bar.low.0 < current.low.0 {
+                    current.low = bar.low;
+                }
+                current.close = bar.close;
+                current.volume += bar.volume;
+            }
+            _ => resampled.push(HistoricalDataPoint {
+                timestamp: start,
+                ..bar.clone()
+            }),
+        }
+    }
+
+    Ok(resampled)
+}
+
+/// Get the smallest gap between consecutive bars of the same symbol
+fn bar_spacing(bars: &[HistoricalDataPoint]) -> Option<Duration> {
+    bars.windows(2)
+        .filter(|pair| pair[0].symbol == pair[1].symbol)
+        .map(|pair| pair[1].timestamp - pair[0].timestamp)
+        .filter(|gap| *gap > Duration::zero())
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Price, Symbol};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn bar(minute: i64, open: Decimal, close: Decimal) -> HistoricalDataPoint {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+        HistoricalDataPoint {
+            symbol: Symbol::new("AAA"),
+            timestamp: start + Duration::minutes(minute),
+            open: Price(open),
+            high: Price(open.max(close) + dec!(1)),
+            low: Price(open.min(close) - dec!(1)),
+            close: Price(close),
+            volume: 100.0,
+        }
+    }
+
+    #[test]
+    fn intervals_parse_and_reject_uneven_lengths() {
+        assert_eq!("5m".parse::<BarInterval>().unwrap(), BarInterval::FIVE_MINUTES);
+        assert_eq!("1d".parse::<BarInterval>().unwrap().duration(), Duration::days(1));
+        assert_eq!(BarInterval::Hours(4).to_string(), "4h");
+
+        assert!("7m".parse::<BarInterval>().is_err());
+        assert!("2d".parse::<BarInterval>().is_err());
+        assert!("0h".parse::<BarInterval>().is_err());
+        assert!("m".parse::<BarInterval>().is_err());
+    }
+
+    #[test]
+    fn resampling_aggregates_bars_into_intervals() {
+        let bars = vec![
+            bar(0, dec!(10), dec!(11)),
+            bar(1, dec!(11), dec!(15)),
+            bar(2, dec!(15), dec!(12)),
+            bar(5, dec!(12), dec!(13)),
+        ];
+
+        let resampled = resample(&bars, BarInterval::FIVE_MINUTES, &SessionBoundary::default()).unwrap();
+
+        assert_eq!(resampled.len(), 2);
+        assert_eq!(resampled[0].timestamp, bars[0].timestamp);
+        assert_eq!(resampled[0].open, Price(dec!(10)));
+        assert_eq!(resampled[0].high, Price(dec!(16)));
+        assert_eq!(resampled[0].low, Price(dec!(9)));
+        assert_eq!(resampled[0].close, Price(dec!(12)));
+        assert_eq!(resampled[0].volume, 300.0);
+        assert_eq!(resampled[1].timestamp, bars[3].timestamp);
+    }
+
+    #[test]
+    fn resampling_to_a_finer_interval_is_an_error() {
+        let bars = vec![bar(0, dec!(10), dec!(11)), bar(15, dec!(11), dec!(12))];
+
+        assert!(resample(&bars, BarInterval::FIVE_MINUTES, &SessionBoundary::default()).is_err());
+        assert!(resample(&bars, BarInterval::ONE_HOUR, &SessionBoundary::default()).is_ok());
+    }
+
+    #[test]
+    fn daily_bars_follow_the_session_boundary() {
+        let session = SessionBoundary::new(Tz::America__New_York)
+            .with_day_start(NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+
+        // 2024-01-02 19:00 New York belongs to the session starting at 18:00 that evening
+        let evening = Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap();
+        assert_eq!(session.day_start_of(evening), Utc.with_ymd_and_hms(2024, 1, 2, 23, 0, 0).unwrap());
+
+        // 2024-01-02 09:30 New York belongs to the session that began the evening before
+        let morning = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+        assert_eq!(session.day_start_of(morning), Utc.with_ymd_and_hms(2024, 1, 1, 23, 0, 0).unwrap());
+        assert_eq!(
+            session.bucket_start(morning, BarInterval::ONE_HOUR),
+            Utc.with_ymd_and_hms(2024, 1, 2, 14, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,531 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{self, SharedClock};
+use crate::error::{Error, Result};
+use crate::market::{MarketDataProvider, Quote};
+use crate::order::OrderSide;
+use crate::types::{Price, Quantity, Symbol};
+
+/// Represents a side of an order book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BookSide {
+    /// Resting buy orders
+    Bid,
+    /// Resting sell orders
+    Ask,
+}
+
+impl BookSide {
+    /// Get the side of the book an order takes liquidity from
+    pub fn taken_by(side: OrderSide) -> Self {
+        match side {
+            OrderSide::Buy => BookSide::Ask,
+            OrderSide::Sell => BookSide::Bid,
+        }
+    }
+}
+
+/// A price level of an order book
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookLevel {
+    /// Price of the level
+    pub price: Price,
+    /// Total size resting at the level
+    pub size: Quantity,
+}
+
+impl BookLevel {
+    /// Create a new book level
+    pub fn new(price: Price, size: Quantity) -> Self {
+        Self { price, size }
+    }
+}
+
+/// A change to a single price level (a size of zero removes the level)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookDelta {
+    /// Side of the level
+    pub side: BookSide,
+    /// Price of the level
+    pub price: Price,
+    /// New size of the level
+    pub size: Quantity,
+}
+
+/// A fill produced by walking an order book
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookFill {
+    /// Price of the fill
+    pub price: Price,
+    /// Quantity filled at the price
+    pub quantity: Quantity,
+}
+
+/// Parameters for generating a synthetic order book around a mid price
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticBookConfig {
+    /// Spread between best bid and ask (as a decimal, e.g., 0.0005 for 0.05%)
+    pub spread: Decimal,
+    /// Price distance between levels
+    pub tick_size: Decimal,
+    /// Number of levels per side
+    pub levels: usize,
+    /// Size at the best level
+    pub level_size: Decimal,
+    /// Size added per level away from the best price
+    pub size_increment: Decimal,
+}
+
+impl Default for SyntheticBookConfig {
+    fn default() -> Self {
+        Self {
+            spread: Decimal::new(5, 4),
+            tick_size: Decimal::new(1, 2),
+            levels: 10,
+            level_size: Decimal::from(100),
+            size_increment: Decimal::from(50),
+        }
+    }
+}
+
+/// Level-2 order book for a symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderBook {
+    /// Symbol of the book
+    pub symbol: Symbol,
+    /// Bid sizes by price
+    bids: BTreeMap<Decimal, Decimal>,
+    /// Ask sizes by price
+    asks: BTreeMap<Decimal, Decimal>,
+    /// Timestamp of the last update
+    pub timestamp: DateTime<Utc>,
+}
+
+impl OrderBook {
+    /// Create an empty order book
+    pub fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            timestamp: clock::now(),
+        }
+    }
+
+    /// Create an order book from a snapshot of bid and ask levels
+    pub fn from_snapshot(symbol: Symbol, bids: &[BookLevel], asks: &[BookLevel]) -> Self {
+        let mut book = Self::new(symbol);
+        for level in bids {
+            book.set_level(BookSide::Bid, level.price, level.size);
+        }
+        for level in asks {
+            book.set_level(BookSide::Ask, level.price, level.size);
+        }
+        book
+    }
+
+    /// Generate a synthetic order book around a mid price
+    pub fn synthetic(symbol: Symbol, mid: Price, config: &SyntheticBookConfig) -> Self {
+        let mut book = Self::new(symbol);
+        let half_spread = mid.0 * config.spread / Decimal::from(2);
+
+        for level in 0..config.levels {
+            let offset = config.tick_size * Decimal::from(level as u64);
+            let size = Quantity(config.level_size + config.size_increment * Decimal::from(level as u64));
+            book.set_level(BookSide::Bid, Price(mid.0 - half_spread - offset), size);
+            book.set_level(BookSide::Ask, Price(mid.0 + half_spread + offset), size);
+        }
+        book
+    }
+
+    /// Set the timestamp of the book
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set the size of a level (a size of zero or less removes it)
+    pub fn set_level(&mut self, side: BookSide, price: Price, size: Quantity) {
+        let levels = self.levels_mut(side);
+        if size.0 > Decimal::ZERO {
+            levels.insert(price.0, size.0);
+        } else {
+            levels.remove(&price.0);
+        }
+    }
+
+    /// Apply a delta to the book
+    pub fn apply_delta(&mut self, delta: &BookDelta) {
+        self.set_level(delta.side, delta.price, delta.size);
+    }
+
+    /// Get the best bid level
+    pub fn best_bid(&self) -> Option<BookLevel> {
+        self.bids
+            .iter()
+            .next_back()
+            .map(|(price, size)| BookLevel::new(Price(*price), Quantity(*size)))
+    }
+
+    /// Get the best ask level
+    pub fn best_ask(&self) -> Option<BookLevel> {
+        self.asks
+            .iter()
+            .next()
+            .map(|(price, size)| BookLevel::new(Price(*price), Quantity(*size)))
+    }
+
+    /// Get the levels of a side, best price first
+    pub fn levels(&self, side: BookSide) -> Vec<BookLevel> {
+        let to_level = |(price, size): (&Decimal, &Decimal)| BookLevel::new(Price(*price), Quantity(*size));
+        match side {
+            BookSide::Bid => self.bids.iter().rev().map(to_level).collect(),
+            BookSide::Ask => self.asks.iter().map(to_level).collect(),
+        }
+    }
+
+    /// Get a top-of-book quote, with the mid price as the last price
+    pub fn quote(&self) -> Result<Quote> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => {
+                let mid = Price((bid.price.0 + ask.price.0) / Decimal::from(2));
+                Ok(Quote::new(self.symbol.clone(), bid.price, ask.price, mid).with_timestamp(self.timestamp))
+            }
+            _ => Err(Error::MarketDataError {
+                reason: format!("Order book for {} is one-sided or empty", self.symbol),
+            }),
+        }
+    }
+
+    /// Walk the book for an order without consuming it
+    ///
+    /// Returns fills at successive prices until the quantity is filled, the book
+    /// is exhausted, or the next level is beyond the limit price.
+    pub fn fills_for(&self, side: OrderSide, quantity: Quantity, limit: Option<Price>) -> Vec<BookFill> {
+        let mut fills = Vec::new();
+        let mut remaining = quantity.0;
+
+        for level in self.levels(BookSide::taken_by(side)) {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let within_limit = match (side, limit) {
+                (_, None) => true,
+                (OrderSide::Buy, Some(limit)) => level.price.0 <= limit.0,
+                (OrderSide::Sell, Some(limit)) => level.price.0 >= limit.0,
+            };
+            if !within_limit {
+                break;
+            }
+
+            let fill_quantity = remaining.min(level.size.0);
+            fills.push(BookFill {
+                price: level.price,
+                quantity: Quantity(fill_quantity),
+            });
+            remaining -= fill_quantity;
+        }
+
+        fills
+    }
+
+    /// Remove filled liquidity from the book
+    pub fn consume(&mut self, side: OrderSide, fills: &[BookFill]) {
+        let book_side = BookSide::taken_by(side);
+        for fill in fills {
+            let levels = self.levels_mut(book_side);
+            if let Some(size) = levels.get_mut(&fill.price.0) {
+                *size -= fill.quantity.0;
+                if *size <= Decimal::ZERO {
+                    levels.remove(&fill.price.0);
+                }
+            }
+        }
+    }
+
+    fn levels_mut(&mut self, side: BookSide) -> &mut BTreeMap<Decimal, Decimal> {
+        match side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        }
+    }
+}
+
+/// How liquidity consumed by simulated fills comes back
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum Replenishment {
+    /// Consumed liquidity never comes back until the next snapshot or delta for the level
+    #[default]
+    Never,
+    /// Consumed liquidity comes back before the next order
+    Immediate,
+    /// Consumed liquidity comes back linearly, as a fraction of the amount consumed per second
+    Rate {
+        /// Fraction restored per second (e.g., 0.1 restores everything after 10 seconds)
+        fraction_per_second: Decimal,
+    },
+}
+
+/// Liquidity consumed at one price level
+#[derive(Debug, Clone, Copy)]
+struct Consumption {
+    /// Quantity consumed, as of `at`
+    quantity: Decimal,
+    /// Time of the last fill at the level
+    at: DateTime<Utc>,
+}
+
+impl Consumption {
+    /// Get the quantity still missing from the level at `now`
+    fn outstanding(&self, now: DateTime<Utc>, replenishment: Replenishment) -> Decimal {
+        match replenishment {
+            Replenishment::Never => self.quantity,
+            Replenishment::Immediate => Decimal::ZERO,
+            Replenishment::Rate { fraction_per_second } => {
+                let elapsed = Decimal::from((now - self.at).num_milliseconds().max(0)) / Decimal::from(1000);
+                self.quantity * (Decimal::ONE - fraction_per_second * elapsed).max(Decimal::ZERO)
+            }
+        }
+    }
+}
+
+/// State of one symbol's book in the provider
+#[derive(Debug, Clone)]
+struct BookState {
+    /// Book as published by the market
+    market: OrderBook,
+    /// Liquidity consumed by simulated fills, by side and price
+    consumed: HashMap<(BookSide, Decimal), Consumption>,
+}
+
+impl BookState {
+    /// Get the market book minus the liquidity still consumed at `now`
+    fn visible(&self, now: DateTime<Utc>, replenishment: Replenishment) -> OrderBook {
+        let mut book = self.market.clone();
+        for ((side, price), consumption) in &self.consumed {
+            let levels = book.levels_mut(*side);
+            if let Some(size) = levels.get_mut(price) {
+                *size -= consumption.outstanding(now, replenishment);
+                if *size <= Decimal::ZERO {
+                    levels.remove(price);
+                }
+            }
+        }
+        book
+    }
+}
+
+/// Market data provider backed by level-2 order books
+///
+/// Market and marketable limit orders walk the book and consume its liquidity,
+/// which is restored according to the replenishment policy.
+#[derive(Debug)]
+pub struct OrderBookProvider {
+    books: Mutex<HashMap<String, BookState>>,
+    replenishment: Replenishment,
+    clock: Option<SharedClock>,
+}
+
+impl Default for OrderBookProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderBookProvider {
+    /// Create a new order book provider
+    pub fn new() -> Self {
+        Self {
+            books: Mutex::new(HashMap::new()),
+            replenishment: Replenishment::default(),
+            clock: None,
+        }
+    }
+
+    /// Set how consumed liquidity is replenished
+    pub fn with_replenishment(mut self, replenishment: Replenishment) -> Self {
+        self.replenishment = replenishment;
+        self
+    }
+
+    /// Use a specific clock for replenishment
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Get the current time from the provider's clock, or the global clock if none is set
+    fn now(&self) -> DateTime<Utc> {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => clock::now(),
+        }
+    }
+
+    /// Replace the book for a symbol (e.g., from a snapshot), clearing consumed liquidity
+    pub fn set_book(&self, book: OrderBook) {
+        let state = BookState {
+            market: book,
+            consumed: HashMap::new(),
+        };
+        self.books.lock().unwrap().insert(state.market.symbol.0.clone(), state);
+    }
+
+    /// Apply a delta to a symbol's book
+    ///
+    /// The level's size is taken as published, so consumption at that level is cleared.
+    pub fn apply_delta(&self, symbol: &Symbol, delta: &BookDelta) -> Result<()> {
+        let mut books = self.books.lock().unwrap();
+        let state = books.get_mut(&symbol.0).ok_or_else(|| Error::SymbolNotFound {
+            symbol: symbol.clone(),
+        })?;
+        state.market.apply_delta(delta);
+        state.market.timestamp = self.now();
+        state.consumed.remove(&(delta.side, delta.price.0));
+        Ok(())
+    }
+
+    /// Get the current visible book for a symbol
+    pub fn book(&self, symbol: &Symbol) -> Result<OrderBook> {
+        let mut books = self.books.lock().unwrap();
+        let state = books.get_mut(&symbol.0).ok_or_else(|| Error::SymbolNotFound {
+            symbol: symbol.clone(),
+        })?;
+        let now = self.now();
+        state
+            .consumed
+            .retain(|_, consumption| consumption.outstanding(now, self.replenishment) > Decimal::ZERO);
+        Ok(state.visible(now, self.replenishment))
+    }
+}
+
+impl MarketDataProvider for OrderBookProvider {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        self.book(symbol)?.quote()
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.books.lock().unwrap().contains_key(&symbol.0)
+    }
+
+    fn book_fills(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        quantity: Quantity,
+        limit: Option<Price>,
+    ) -> Option<Vec<BookFill>> {
+        self.book(symbol)
+            .ok()
+            .map(|book| book.fills_for(side, quantity, limit))
+    }
+
+    fn consume_liquidity(&self, symbol: &Symbol, side: OrderSide, fills: &[BookFill]) {
+        let mut books = self.books.lock().unwrap();
+        if let Some(state) = books.get_mut(&symbol.0) {
+            let now = self.now();
+            let book_side = BookSide::taken_by(side);
+            for fill in fills {
+                // Carry over what is still missing and restart the replenishment from now
+                let outstanding = state
+                    .consumed
+                    .get(&(book_side, fill.price.0))
+                    .map_or(Decimal::ZERO, |consumption| consumption.outstanding(now, self.replenishment));
+                let consumption = Consumption {
+                    quantity: outstanding + fill.quantity.0,
+                    at: now,
+                };
+                state.consumed.insert((book_side, fill.price.0), consumption);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    fn book() -> OrderBook {
+        OrderBook::from_snapshot(
+            Symbol::new("AAA"),
+            &[BookLevel::new(Price(dec!(99)), Quantity(dec!(100)))],
+            &[
+                BookLevel::new(Price(dec!(101)), Quantity(dec!(100))),
+                BookLevel::new(Price(dec!(102)), Quantity(dec!(200))),
+            ],
+        )
+    }
+
+    fn best_ask_size(provider: &OrderBookProvider) -> Decimal {
+        provider.book(&Symbol::new("AAA")).unwrap().best_ask().unwrap().size.0
+    }
+
+    #[test]
+    fn fills_walk_the_book_up_to_the_limit() {
+        let fills = book().fills_for(OrderSide::Buy, Quantity(dec!(250)), None);
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[1].price, Price(dec!(102)));
+        assert_eq!(fills[1].quantity, Quantity(dec!(150)));
+
+        let limited = book().fills_for(OrderSide::Buy, Quantity(dec!(250)), Some(Price(dec!(101))));
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].quantity, Quantity(dec!(100)));
+    }
+
+    #[test]
+    fn rate_replenishment_is_linear_and_independent_of_polling() {
+        let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap());
+        let provider = OrderBookProvider::new()
+            .with_clock(clock.shared())
+            .with_replenishment(Replenishment::Rate {
+                fraction_per_second: dec!(0.1),
+            });
+        provider.set_book(book());
+        let fills = [BookFill {
+            price: Price(dec!(101)),
+            quantity: Quantity(dec!(80)),
+        }];
+        provider.consume_liquidity(&Symbol::new("AAA"), OrderSide::Buy, &fills);
+        assert_eq!(best_ask_size(&provider), dec!(20));
+
+        // Polling every second restores the same amount as not polling at all
+        for _ in 0..5 {
+            clock.advance(Duration::seconds(1));
+            provider.book(&Symbol::new("AAA")).unwrap();
+        }
+        assert_eq!(best_ask_size(&provider), dec!(60));
+
+        clock.advance(Duration::seconds(5));
+        assert_eq!(best_ask_size(&provider), dec!(100));
+    }
+
+    #[test]
+    fn consumption_without_replenishment_stays_until_the_level_updates() {
+        let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap());
+        let provider = OrderBookProvider::new().with_clock(clock.shared());
+        provider.set_book(book());
+        let fills = [BookFill {
+            price: Price(dec!(101)),
+            quantity: Quantity(dec!(100)),
+        }];
+        provider.consume_liquidity(&Symbol::new("AAA"), OrderSide::Buy, &fills);
+
+        clock.advance(Duration::hours(1));
+        assert_eq!(provider.get_quote(&Symbol::new("AAA")).unwrap().ask, Price(dec!(102)));
+
+        let delta = BookDelta {
+            side: BookSide::Ask,
+            price: Price(dec!(101)),
+            size: Quantity(dec!(50)),
+        };
+        provider.apply_delta(&Symbol::new("AAA"), &delta).unwrap();
+        assert_eq!(best_ask_size(&provider), dec!(50));
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Number of days searched for the next session before giving up
+const MAX_SESSION_SEARCH_DAYS: i64 = 366;
+
+/// Represents the part of the trading day a time falls in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionPhase {
+    /// No trading
+    Closed,
+    /// Extended hours before the regular open
+    PreMarket,
+    /// Regular trading hours
+    Regular,
+    /// Extended hours after the regular close
+    PostMarket,
+}
+
+/// Trading hours of a single day, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Local date of the session
+    pub date: NaiveDate,
+    /// Start of pre-market trading (the regular open if there is none)
+    pub pre_market_open: DateTime<Utc>,
+    /// Regular open
+    pub open: DateTime<Utc>,
+    /// Regular close (earlier on early-close days)
+    pub close: DateTime<Utc>,
+    /// End of post-market trading (the regular close if there is none)
+    pub post_market_close: DateTime<Utc>,
+}
+
+impl Session {
+    /// Get the phase of the session at a time
+    pub fn phase_at(&self, timestamp: DateTime<Utc>) -> SessionPhase {
+        if timestamp >= self.open && timestamp < self.close {
+            SessionPhase::Regular
+        } else if timestamp >= self.pre_market_open && timestamp < self.open {
+            SessionPhase::PreMarket
+        } else if timestamp >= self.close && timestamp < self.post_market_close {
+            SessionPhase::PostMarket
+        } else {
+            SessionPhase::Closed
+        }
+    }
+
+    /// Get the end of the session, including post-market trading if `extended_hours` is set
+    pub fn end(&self, extended_hours: bool) -> DateTime<Utc> {
+        if extended_hours {
+            self.post_market_close
+        } else {
+            self.close
+        }
+    }
+}
+
+/// Exchange calendar defining when an instrument trades
+///
+/// Sessions run within a single local day: regular hours, optional pre- and
+/// post-market hours, early closes and holidays. On early-close days the
+/// post-market session keeps its usual length after the early close.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradingCalendar {
+    /// Name of the calendar (e.g., "XNYS")
+    pub name: String,
+    /// Time zone the hours are given in
+    pub time_zone: Tz,
+    /// Local time of the regular open
+    pub open: NaiveTime,
+    /// Local time of the regular close
+    pub close: NaiveTime,
+    /// Local time pre-market trading begins
+    pub pre_market_open: Option<NaiveTime>,
+    /// Local time post-market trading ends
+    pub post_market_close: Option<NaiveTime>,
+    /// Days of the week with sessions
+    pub trading_days: BTreeSet<u32>,
+    /// Dates without a session
+    pub holidays: BTreeSet<NaiveDate>,
+    /// Dates with an early regular close
+    pub early_closes: BTreeMap<NaiveDate, NaiveTime>,
+}
+
+impl TradingCalendar {
+    /// Create a calendar with regular hours on weekdays
+    pub fn new<S: Into<String>>(name: S, time_zone: Tz, open: NaiveTime, close: NaiveTime) -> Self {
+        Self {
+            name: name.into(),
+            time_zone,
+            open,
+            close,
+            pre_market_open: None,
+            post_market_close: None,
+            trading_days: [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]
+                .iter()
+                .map(Weekday::num_days_from_monday)
+                .collect(),
+            holidays: BTreeSet::new(),
+            early_closes: BTreeMap::new(),
+        }
+    }
+
+    /// Create a calendar that trades around the clock every day (e.g., crypto)
+    pub fn always_open() -> Self {
+        let mut calendar = Self::new("24/7", Tz::UTC, NaiveTime::MIN, NaiveTime::MIN);
+        calendar.trading_days = (0..7).collect();
+        calendar
+    }
+
+    /// Create a calendar with US equity hours: 9:30-16:00 New York time, with
+    /// pre-market from 4:00 and post-market until 20:00
+    ///
+    /// Holidays and early closes are not included; load them with `with_holidays_file`.
+    pub fn us_equities() -> Self {
+        Self::new(
+            "XNYS",
+            chrono_tz::America::New_York,
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+        )
+        .with_pre_market_open(NaiveTime::from_hms_opt(4, 0, 0).unwrap())
+        .with_post_market_close(NaiveTime::from_hms_opt(20, 0, 0).unwrap())
+    }
+
+    /// Set the local time pre-market trading begins
+    pub fn with_pre_market_open(mut self, time: NaiveTime) -> Self {
+        self.pre_market_open = Some(time);
+        self
+    }
+
+    /// Set the local time post-market trading ends
+    pub fn with_post_market_close(mut self, time: NaiveTime) -> Self {
+        self.post_market_close = Some(time);
+        self
+    }
+
+    /// Set the days of the week with sessions
+    pub fn with_trading_days(mut self, days: &[Weekday]) -> Self {
+        self.trading_days = days.iter().map(Weekday::num_days_from_monday).collect();
+        self
+    }
+
+    /// Add a holiday
+    pub fn with_holiday(mut self, date: NaiveDate) -> Self {
+        self.holidays.insert(date);
+        self
+    }
+
+    /// Add an early close
+    pub fn with_early_close(mut self, date: NaiveDate, close: NaiveTime) -> Self {
+        self.early_closes.insert(date, close);
+        self
+    }
+
+    /// Add holidays and early closes from a file
+    ///
+    /// Each line holds a date (`2024-12-25`) for a holiday, or a date and local
+    /// close time (`2024-11-29,13:00`) for an early close. Blank lines and lines
+    /// starting with `#` are ignored.
+    pub fn with_holidays_file<P: AsRef<Path>>(self, path: P) -> Result<Self> {
+        self.with_holidays_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Add holidays and early closes from a reader (see `with_holidays_file`)
+    pub fn with_holidays_reader<R: BufRead>(mut self, reader: R) -> Result<Self> {
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || Error::MarketDataError {
+                reason: format!("Invalid calendar entry '{}'", line),
+            };
+            let mut fields = line.split(',').map(str::trim);
+            let date = fields
+                .next()
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                .ok_or_else(invalid)?;
+            match fields.next().filter(|time| !time.is_empty()) {
+                Some(time) => {
+                    let close = NaiveTime::parse_from_str(time, "%H:%M")
+                        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+                        .map_err(|_| invalid())?;
+                    self.early_closes.insert(date, close);
+                }
+                None => {
+                    self.holidays.insert(date);
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Check if a local date has a session
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        self.trading_days.contains(&date.weekday().num_days_from_monday()) && !self.holidays.contains(&date)
+    }
+
+    /// Get the session on a local date, if there is one
+    pub fn session(&self, date: NaiveDate) -> Option<Session> {
+        if !self.is_trading_day(date) {
+            return None;
+        }
+
+        let open = self.localize(date.and_time(self.open));
+
+        // A close at or before the open (e.g., 00:00 around the clock) ends the session at midnight
+        let regular_close = if self.close > self.open {
+            self.localize(date.and_time(self.close))
+        } else {
+            self.localize(date.succ_opt()?.and_time(NaiveTime::MIN))
+        };
+        let close = match self.early_closes.get(&date) {
+            Some(early) => self.localize(date.and_time(*early)).min(regular_close),
+            None => regular_close,
+        };
+        let pre_market_open = match self.pre_market_open {
+            Some(time) if time < self.open => self.localize(date.and_time(time)),
+            _ => open,
+        };
+        let post_market_close = match self.post_market_close {
+            Some(time) if self.close > self.open && time > self.close => {
+                close + (self.localize(date.and_time(time)) - regular_close)
+            }
+            _ => close,
+        };
+
+        Some(Session {
+            date,
+            pre_market_open,
+            open,
+            close,
+            post_market_close,
+        })
+    }
+
+    /// Get the local date of a time
+    pub fn local_date(&self, timestamp: DateTime<Utc>) -> NaiveDate {
+        timestamp.with_timezone(&self.time_zone).date_naive()
+    }
+
+    /// Get the session phase at a time
+    pub fn phase_at(&self, timestamp: DateTime<Utc>) -> SessionPhase {
+        self.session(self.local_date(timestamp))
+            .map_or(SessionPhase::Closed, |session| session.phase_at(timestamp))
+    }
+
+    /// Check if the market is open at a time, including extended hours if `extended_hours` is set
+    pub fn is_open(&self, timestamp: DateTime<Utc>, extended_hours: bool) -> bool {
+        match self.phase_at(timestamp) {
+            SessionPhase::Regular => true,
+            SessionPhase::PreMarket | SessionPhase::PostMarket => extended_hours,
+            SessionPhase::Closed => false,
+        }
+    }
+
+    /// Get the first session that hasn't ended by a time
+    pub fn current_or_next_session(&self, timestamp: DateTime<Utc>, extended_hours: bool) -> Option<Session> {
+        let date = self.local_date(timestamp);
+        (0..MAX_SESSION_SEARCH_DAYS)
+            .filter_map(|offset| self.session(date + Duration::days(offset)))
+            .find(|session| session.end(extended_hours) > timestamp)
+    }
+
+    /// Get the time the market next opens after a time
+    pub fn next_open(&self, timestamp: DateTime<Utc>, extended_hours: bool) -> Option<DateTime<Utc>> {
+        let date = self.local_date(timestamp);
+        (0..MAX_SESSION_SEARCH_DAYS)
+            .filter_map(|offset| self.session(date + Duration::days(offset)))
+            .map(|session| {
+                if extended_hours {
+                    session.pre_market_open
+                } else {
+                    session.open
+                }
+            })
+            .find(|open| *open > timestamp)
+    }
+
+    /// Get the time a DAY order created at a time expires
+    ///
+    /// This is the close of the session the order was created in, or of the
+    /// next session if it was created after the close.
+    pub fn day_order_expiry(&self, created_at: DateTime<Utc>, extended_hours: bool) -> Option<DateTime<Utc>> {
+        self.current_or_next_session(created_at, extended_hours)
+            .map(|session| session.end(extended_hours))
+    }
+
+    /// Convert a local time to UTC, skipping forward over DST gaps
+    fn localize(&self, naive: NaiveDateTime) -> DateTime<Utc> {
+        let mut naive = naive;
+        loop {
+            if let Some(local) = self.time_zone.from_local_datetime(&naive).earliest() {
+                return local.with_timezone(&Utc);
+            }
+            naive += Duration::minutes(30);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn calendar() -> TradingCalendar {
+        TradingCalendar::us_equities()
+            .with_holidays_reader("# 2024\n2024-12-25\n\n2024-11-29, 13:00\n".as_bytes())
+            .unwrap()
+    }
+
+    #[test]
+    fn phases_follow_the_local_session_hours() {
+        let calendar = calendar();
+
+        // 2024-01-02 is a Tuesday in New York winter time (UTC-5)
+        assert_eq!(calendar.phase_at(utc(1, 2, 8, 59)), SessionPhase::Closed);
+        assert_eq!(calendar.phase_at(utc(1, 2, 9, 0)), SessionPhase::PreMarket);
+        assert_eq!(calendar.phase_at(utc(1, 2, 14, 30)), SessionPhase::Regular);
+        assert_eq!(calendar.phase_at(utc(1, 2, 21, 0)), SessionPhase::PostMarket);
+        assert_eq!(calendar.phase_at(utc(1, 3, 1, 0)), SessionPhase::Closed);
+
+        assert!(!calendar.is_open(utc(1, 2, 21, 0), false));
+        assert!(calendar.is_open(utc(1, 2, 21, 0), true));
+        assert!(!calendar.is_open(utc(1, 6, 15, 0), true));
+    }
+
+    #[test]
+    fn holidays_and_early_closes_are_loaded_from_files() {
+        let calendar = calendar();
+
+        assert!(!calendar.is_trading_day(date(12, 25)));
+        assert!(calendar.session(date(12, 25)).is_none());
+
+        let session = calendar.session(date(11, 29)).unwrap();
+        assert_eq!(session.close, utc(11, 29, 18, 0));
+        assert_eq!(session.post_market_close, utc(11, 29, 22, 0));
+
+        assert!(calendar.clone().with_holidays_reader("2024-13-01\n".as_bytes()).is_err());
+        assert!(calendar.with_holidays_reader("2024-11-29,1pm\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn day_orders_expire_at_the_close_of_the_current_or_next_session() {
+        let calendar = calendar();
+
+        assert_eq!(calendar.day_order_expiry(utc(1, 2, 15, 0), false), Some(utc(1, 2, 21, 0)));
+        assert_eq!(calendar.day_order_expiry(utc(1, 2, 15, 0), true), Some(utc(1, 3, 1, 0)));
+        // Created after Friday's close, the order lasts through Monday's session
+        assert_eq!(calendar.day_order_expiry(utc(1, 5, 22, 0), false), Some(utc(1, 8, 21, 0)));
+        // Created before an early close on the same day
+        assert_eq!(calendar.day_order_expiry(utc(11, 29, 15, 0), false), Some(utc(11, 29, 18, 0)));
+
+        assert_eq!(calendar.next_open(utc(12, 24, 22, 0), false), Some(utc(12, 26, 14, 30)));
+        assert_eq!(calendar.next_open(utc(12, 24, 22, 0), true), Some(utc(12, 26, 9, 0)));
+    }
+
+    #[test]
+    fn always_open_calendars_trade_every_day() {
+        let calendar = TradingCalendar::always_open();
+
+        assert!(calendar.is_open(utc(1, 6, 0, 0), false));
+        assert!(calendar.is_open(utc(1, 6, 23, 59), false));
+        let session = calendar.session(date(1, 6)).unwrap();
+        assert_eq!(session.close, utc(1, 7, 0, 0));
+    }
+}
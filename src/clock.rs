@@ -0,0 +1,86 @@
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+use chrono::{DateTime, Duration, Utc};
+
+/// Source of the current time for accounts, orders, trades and quotes
+pub trait Clock: Debug + Send + Sync {
+    /// Get the current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Shared handle to a clock
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Clock backed by the system wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Manually advanced clock for simulations and backtests
+///
+/// Clones share the same underlying time, so advancing one handle
+/// advances every account, provider and order that uses it.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    time: Arc<RwLock<DateTime<Utc>>>,
+}
+
+impl SimulatedClock {
+    /// Create a new simulated clock starting at the given time
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            time: Arc::new(RwLock::new(start)),
+        }
+    }
+
+    /// Set the clock to a specific time
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.time.write().unwrap() = time;
+    }
+
+    /// Advance the clock by a duration
+    pub fn advance(&self, duration: Duration) {
+        let mut time = self.time.write().unwrap();
+        *time += duration;
+    }
+
+    /// Get a shared handle to this clock
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.time.read().unwrap()
+    }
+}
+
+/// Get the current time from the global configuration's clock
+pub fn now() -> DateTime<Utc> {
+    crate::config::get().now()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn clones_share_the_simulated_time() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap();
+        let clock = SimulatedClock::new(start);
+        let shared = clock.shared();
+
+        clock.advance(Duration::minutes(5));
+        assert_eq!(shared.now(), start + Duration::minutes(5));
+
+        clock.clone().set(start);
+        assert_eq!(shared.now(), start);
+    }
+}
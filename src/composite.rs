@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::book::BookFill;
+use crate::clock::{self, SharedClock};
+use crate::error::{Error, Result};
+use crate::halt::{PriceBands, TradingStatus};
+use crate::market::{AuctionPrint, MarketDataProvider, PricePath, Quote};
+use crate::order::{Auction, OrderSide};
+use crate::types::{Price, Quantity, Symbol};
+
+/// Market data provider that tries a chain of providers in order
+///
+/// Quotes come from the first provider that supports the symbol and returns
+/// one, so a secondary feed can stand in when the primary has no data. Other
+/// requests for the symbol (paths, book fills, liquidity, auctions, status) go
+/// to the provider that answered the latest quote, so an order is filled and
+/// its liquidity consumed against the same feed.
+#[derive(Default)]
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn MarketDataProvider>>,
+    /// Index of the provider that answered the latest quote, by symbol
+    sources: Mutex<HashMap<String, usize>>,
+}
+
+impl FallbackProvider {
+    /// Create an empty fallback chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a provider to the end of the chain
+    pub fn with_provider<P: MarketDataProvider + 'static>(mut self, provider: P) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    /// Get the provider that answered the latest quote for a symbol
+    ///
+    /// Before the symbol has been quoted, this is the first provider that supports it.
+    fn source(&self, symbol: &Symbol) -> Option<&dyn MarketDataProvider> {
+        let index = self.sources.lock().unwrap().get(&symbol.0).copied();
+        match index {
+            Some(index) => self.providers.get(index),
+            None => self.providers.iter().find(|provider| provider.is_symbol_supported(symbol)),
+        }
+        .map(|provider| provider.as_ref())
+    }
+}
+
+impl MarketDataProvider for FallbackProvider {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        let mut last_error = None;
+        for (index, provider) in self.providers.iter().enumerate() {
+            if !provider.is_symbol_supported(symbol) {
+                continue;
+            }
+            match provider.get_quote(symbol) {
+                Ok(quote) => {
+                    self.sources.lock().unwrap().insert(symbol.0.clone(), index);
+                    return Ok(quote);
+                }
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::SymbolNotFound {
+            symbol: symbol.clone(),
+        }))
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.providers
+            .iter()
+            .any(|provider| provider.is_symbol_supported(symbol))
+    }
+
+    fn price_path(&self, symbol: &Symbol) -> Option<PricePath> {
+        self.source(symbol)?.price_path(symbol)
+    }
+
+    fn book_fills(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        quantity: Quantity,
+        limit: Option<Price>,
+    ) -> Option<Vec<BookFill>> {
+        self.source(symbol)?.book_fills(symbol, side, quantity, limit)
+    }
+
+    fn consume_liquidity(&self, symbol: &Symbol, side: OrderSide, fills: &[BookFill]) {
+        if let Some(provider) = self.source(symbol) {
+            provider.consume_liquidity(symbol, side, fills);
+        }
+    }
+
+    fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+        self.source(symbol)?.auction_print(symbol, auction)
+    }
+
+    fn trading_status(&self, symbol: &Symbol) -> TradingStatus {
+        self.source(symbol)
+            .map_or(TradingStatus::Trading, |provider| provider.trading_status(symbol))
+    }
+
+    fn price_bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+        self.source(symbol)?.price_bands(symbol)
+    }
+}
+
+/// A routing rule for a symbol-routed provider
+type Route = Box<dyn Fn(&Symbol) -> bool>;
+
+/// Market data provider that sends each symbol to a specific provider
+///
+/// Routes are checked in the order they were added, and symbols that match no
+/// route go to the default provider if one is set.
+#[derive(Default)]
+pub struct SymbolRouter {
+    routes: Vec<(Route, Box<dyn MarketDataProvider>)>,
+    default: Option<Box<dyn MarketDataProvider>>,
+}
+
+impl SymbolRouter {
+    /// Create a router with no routes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route symbols matching a predicate to a provider
+    pub fn with_route<F, P>(mut self, predicate: F, provider: P) -> Self
+    where
+        F: Fn(&Symbol) -> bool + 'static,
+        P: MarketDataProvider + 'static,
+    {
+        self.routes.push((Box::new(predicate), Box::new(provider)));
+        self
+    }
+
+    /// Route a fixed list of symbols to a provider
+    pub fn with_symbols<P: MarketDataProvider + 'static>(self, symbols: &[Symbol], provider: P) -> Self {
+        let symbols: Vec<Symbol> = symbols.to_vec();
+        self.with_route(move |symbol| symbols.contains(symbol), provider)
+    }
+
+    /// Send symbols that match no route to a provider
+    pub fn with_default<P: MarketDataProvider + 'static>(mut self, provider: P) -> Self {
+        self.default = Some(Box::new(provider));
+        self
+    }
+
+    /// Get the provider a symbol is routed to
+    pub fn route(&self, symbol: &Symbol) -> Option<&dyn MarketDataProvider> {
+        self.routes
+            .iter()
+            .find(|(predicate, _)| predicate(symbol))
+            .map(|(_, provider)| provider.as_ref())
+            .or(self.default.as_deref())
+    }
+}
+
+impl MarketDataProvider for SymbolRouter {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        match self.route(symbol) {
+            Some(provider) => provider.get_quote(symbol),
+            None => Err(Error::SymbolNotFound {
+                symbol: symbol.clone(),
+            }),
+        }
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.route(symbol)
+            .is_some_and(|provider| provider.is_symbol_supported(symbol))
+    }
+
+    fn price_path(&self, symbol: &Symbol) -> Option<PricePath> {
+        self.route(symbol)?.price_path(symbol)
+    }
+
+    fn book_fills(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        quantity: Quantity,
+        limit: Option<Price>,
+    ) -> Option<Vec<BookFill>> {
+        self.route(symbol)?.book_fills(symbol, side, quantity, limit)
+    }
+
+    fn consume_liquidity(&self, symbol: &Symbol, side: OrderSide, fills: &[BookFill]) {
+        if let Some(provider) = self.route(symbol) {
+            provider.consume_liquidity(symbol, side, fills);
+        }
+    }
+
+    fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+        self.route(symbol)?.auction_print(symbol, auction)
+    }
+
+    fn trading_status(&self, symbol: &Symbol) -> TradingStatus {
+        self.route(symbol)
+            .map_or(TradingStatus::Trading, |provider| provider.trading_status(symbol))
+    }
+
+    fn price_bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+        self.route(symbol)?.price_bands(symbol)
+    }
+}
+
+/// Market data provider that layers manual quotes over another provider
+///
+/// Overridden symbols are quoted from the override only, with no price path or
+/// order book, so tests can pin prices without touching the underlying feed.
+#[derive(Debug, Clone)]
+pub struct OverrideProvider<P> {
+    inner: P,
+    overrides: HashMap<String, Quote>,
+}
+
+impl<P: MarketDataProvider> OverrideProvider<P> {
+    /// Wrap a provider with no overrides
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Override the quote for a symbol
+    pub fn set_quote(&mut self, quote: Quote) {
+        self.overrides.insert(quote.symbol.0.clone(), quote);
+    }
+
+    /// Override a symbol with a single price for bid, ask and last
+    pub fn set_price(&mut self, symbol: &Symbol, price: Price) {
+        self.set_quote(Quote::new(symbol.clone(), price, price, price));
+    }
+
+    /// Remove the override for a symbol
+    pub fn clear_override(&mut self, symbol: &Symbol) -> Option<Quote> {
+        self.overrides.remove(&symbol.0)
+    }
+
+    /// Remove all overrides
+    pub fn clear_overrides(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// Check if a symbol is overridden
+    pub fn is_overridden(&self, symbol: &Symbol) -> bool {
+        self.overrides.contains_key(&symbol.0)
+    }
+
+    /// Get the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get the wrapped provider mutably
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+impl<P: MarketDataProvider> MarketDataProvider for OverrideProvider<P> {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        match self.overrides.get(&symbol.0) {
+            Some(quote) => Ok(quote.clone()),
+            None => self.inner.get_quote(symbol),
+        }
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.is_overridden(symbol) || self.inner.is_symbol_supported(symbol)
+    }
+
+    fn price_path(&self, symbol: &Symbol) -> Option<PricePath> {
+        if self.is_overridden(symbol) {
+            return None;
+        }
+        self.inner.price_path(symbol)
+    }
+
+    fn book_fills(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        quantity: Quantity,
+        limit: Option<Price>,
+    ) -> Option<Vec<BookFill>> {
+        if self.is_overridden(symbol) {
+            return None;
+        }
+        self.inner.book_fills(symbol, side, quantity, limit)
+    }
+
+    fn consume_liquidity(&self, symbol: &Symbol, side: OrderSide, fills: &[BookFill]) {
+        if !self.is_overridden(symbol) {
+            self.inner.consume_liquidity(symbol, side, fills);
+        }
+    }
+
+    fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+        self.inner.auction_print(symbol, auction)
+    }
+
+    fn trading_status(&self, symbol: &Symbol) -> TradingStatus {
+        self.inner.trading_status(symbol)
+    }
+
+    fn price_bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+        self.inner.price_bands(symbol)
+    }
+}
+
+/// Market data provider that caches another provider's quotes for a fixed time
+///
+/// Only quotes are cached; price paths and order books always come from the
+/// wrapped provider, and taking liquidity invalidates the symbol's cached quote.
+#[derive(Debug)]
+pub struct CachedProvider<P> {
+    inner: P,
+    ttl: Duration,
+    clock: Option<SharedClock>,
+    cache: Mutex<HashMap<String, (Quote, DateTime<Utc>)>>,
+}
+
+impl<P: MarketDataProvider> CachedProvider<P> {
+    /// Wrap a provider, keeping each quote for `ttl`
+    pub fn new(inner: P, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            clock: None,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use a specific clock to expire cached quotes
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Get the current time from the provider's clock, or the global clock if none is set
+    fn now(&self) -> DateTime<Utc> {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => clock::now(),
+        }
+    }
+
+    /// Drop the cached quote for a symbol
+    pub fn invalidate(&self, symbol: &Symbol) {
+        self.cache.lock().unwrap().remove(&symbol.0);
+    }
+
+    /// Drop all cached quotes
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Get the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get the wrapped provider mutably, dropping all cached quotes
+    pub fn inner_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.inner
+    }
+}
+
+impl<P: MarketDataProvider> MarketDataProvider for CachedProvider<P> {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        let now = self.now();
+        if let Some((quote, fetched_at)) = self.cache.lock().unwrap().get(&symbol.0) {
+            if now - *fetched_at < self.ttl {
+                return Ok(quote.clone());
+            }
+        }
+
+        let quote = self.inner.get_quote(symbol)?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(symbol.0.clone(), (quote.clone(), now));
+        Ok(quote)
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.inner.is_symbol_supported(symbol)
+    }
+
+    fn price_path(&self, symbol: &Symbol) -> Option<PricePath> {
+        self.inner.price_path(symbol)
+    }
+
+    fn book_fills(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        quantity: Quantity,
+        limit: Option<Price>,
+    ) -> Option<Vec<BookFill>> {
+        self.inner.book_fills(symbol, side, quantity, limit)
+    }
+
+    fn consume_liquidity(&self, symbol: &Symbol, side: OrderSide, fills: &[BookFill]) {
+        self.inner.consume_liquidity(symbol, side, fills);
+        self.invalidate(symbol);
+    }
+
+    fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+        self.inner.auction_print(symbol, auction)
+    }
+
+    fn trading_status(&self, symbol: &Symbol) -> TradingStatus {
+        self.inner.trading_status(symbol)
+    }
+
+    fn price_bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+        self.inner.price_bands(symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::market::SimpleMarketDataProvider;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Feed that counts the quotes and liquidity it serves
+    #[derive(Debug, Default, Clone)]
+    struct Feed {
+        price: Option<Decimal>,
+        quotes: Rc<Cell<usize>>,
+        consumed: Rc<Cell<usize>>,
+    }
+
+    impl Feed {
+        fn quoting(price: Decimal) -> Self {
+            Self {
+                price: Some(price),
+                ..Self::default()
+            }
+        }
+    }
+
+    impl MarketDataProvider for Feed {
+        fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+            self.quotes.set(self.quotes.get() + 1);
+            let price = self.price.ok_or_else(|| Error::MarketDataError {
+                reason: format!("No data for {}", symbol),
+            })?;
+            Ok(Quote::new(symbol.clone(), Price(price), Price(price), Price(price)))
+        }
+
+        fn is_symbol_supported(&self, _symbol: &Symbol) -> bool {
+            true
+        }
+
+        fn consume_liquidity(&self, _symbol: &Symbol, _side: OrderSide, _fills: &[BookFill]) {
+            self.consumed.set(self.consumed.get() + 1);
+        }
+    }
+
+    fn aaa() -> Symbol {
+        Symbol::new("AAA")
+    }
+
+    #[test]
+    fn fallback_quotes_each_provider_once() {
+        let primary = Feed::default();
+        let secondary = Feed::quoting(dec!(100));
+        let fallback = FallbackProvider::new()
+            .with_provider(primary.clone())
+            .with_provider(secondary.clone());
+
+        assert_eq!(fallback.get_quote(&aaa()).unwrap().last, Price(dec!(100)));
+        assert_eq!(primary.quotes.get(), 1);
+        assert_eq!(secondary.quotes.get(), 1);
+
+        // Other requests go to the provider that answered, without quoting again
+        fallback.trading_status(&aaa());
+        fallback.consume_liquidity(&aaa(), OrderSide::Buy, &[]);
+        assert_eq!(primary.quotes.get() + secondary.quotes.get(), 2);
+        assert_eq!(primary.consumed.get(), 0);
+        assert_eq!(secondary.consumed.get(), 1);
+    }
+
+    #[test]
+    fn fallback_reports_the_last_error() {
+        let fallback = FallbackProvider::new().with_provider(Feed::default());
+        assert!(matches!(fallback.get_quote(&aaa()), Err(Error::MarketDataError { .. })));
+        assert!(matches!(FallbackProvider::new().get_quote(&aaa()), Err(Error::SymbolNotFound { .. })));
+    }
+
+    #[test]
+    fn router_sends_symbols_to_their_routes() {
+        let router = SymbolRouter::new()
+            .with_symbols(&[aaa()], Feed::quoting(dec!(100)))
+            .with_default(Feed::quoting(dec!(200)));
+
+        assert_eq!(router.get_quote(&aaa()).unwrap().last, Price(dec!(100)));
+        assert_eq!(router.get_quote(&Symbol::new("BBB")).unwrap().last, Price(dec!(200)));
+        assert!(SymbolRouter::new().get_quote(&aaa()).is_err());
+    }
+
+    #[test]
+    fn overrides_replace_the_inner_quote_until_cleared() {
+        let mut provider = OverrideProvider::new(Feed::quoting(dec!(100)));
+        provider.set_price(&aaa(), Price(dec!(90)));
+        assert_eq!(provider.get_quote(&aaa()).unwrap().last, Price(dec!(90)));
+
+        provider.clear_override(&aaa());
+        assert_eq!(provider.get_quote(&aaa()).unwrap().last, Price(dec!(100)));
+    }
+
+    #[test]
+    fn cached_quotes_expire_after_the_ttl() {
+        let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap());
+        let feed = Feed::quoting(dec!(100));
+        let cached = CachedProvider::new(feed.clone(), Duration::seconds(5)).with_clock(clock.shared());
+
+        cached.get_quote(&aaa()).unwrap();
+        cached.get_quote(&aaa()).unwrap();
+        assert_eq!(feed.quotes.get(), 1);
+
+        clock.advance(Duration::seconds(5));
+        cached.get_quote(&aaa()).unwrap();
+        assert_eq!(feed.quotes.get(), 2);
+
+        // Taking liquidity drops the cached quote
+        cached.consume_liquidity(&aaa(), OrderSide::Buy, &[]);
+        cached.get_quote(&aaa()).unwrap();
+        assert_eq!(feed.quotes.get(), 3);
+    }
+
+    #[test]
+    fn changing_the_cached_provider_drops_its_quotes() {
+        let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap());
+        let mut inner = SimpleMarketDataProvider::new().with_clock(clock.shared());
+        inner.set_price(aaa(), Price(dec!(100)));
+        let mut cached = CachedProvider::new(inner, Duration::seconds(5)).with_clock(clock.shared());
+        assert_eq!(cached.get_quote(&aaa()).unwrap().last, Price(dec!(100)));
+
+        cached.inner_mut().set_price(aaa(), Price(dec!(102)));
+        assert_eq!(cached.get_quote(&aaa()).unwrap().last, Price(dec!(102)));
+    }
+}
@@ -1,6 +1,31 @@
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::Decimal;
 use log::{debug, info};
-use std::sync::Once;
+use std::sync::{Arc, Once, RwLock};
+use crate::calendar::TradingCalendar;
+use crate::clock::SharedClock;
+use crate::instrument::InstrumentRegistry;
+use crate::types::Symbol;
+
+/// How market orders respond to a quote that fails validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidQuoteAction {
+    /// Return `Error::MarketDataError` and leave the order open
+    #[default]
+    Error,
+    /// Reject the order with the validation failure as the reason
+    Reject,
+}
+
+/// How market orders respond to the market being closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClosedMarketAction {
+    /// Leave the order open until the market opens
+    #[default]
+    Queue,
+    /// Reject the order
+    Reject,
+}
 
 /// Configuration for the paper trading account
 #[derive(Debug, Clone)]
@@ -11,10 +36,52 @@ pub struct Config {
     pub default_spread: Decimal,
     /// Commission rate for trades (as a decimal, e.g., 0.0025 for 0.25%)
     pub commission_rate: Decimal,
+    /// Annual interest rate credited on positive cash balances (as a decimal, e.g., 0.02 for 2%)
+    pub cash_interest_rate: Decimal,
+    /// Number of days in the interest year used for daily accrual (e.g., 360 or 365)
+    pub interest_day_count: u32,
+    /// Maximum age of a quote relative to the account clock (no limit if none is set)
+    pub max_quote_age: Option<Duration>,
+    /// Treat quotes with the bid above the ask as invalid
+    pub reject_crossed_quotes: bool,
+    /// Treat quotes with the bid equal to the ask as invalid
+    pub reject_locked_quotes: bool,
+    /// How market orders respond to an invalid quote (resting orders are left open)
+    pub invalid_quote_action: InvalidQuoteAction,
+    /// Instruments with trading calendars (all symbols trade around the clock if none are set)
+    pub instruments: Option<Arc<InstrumentRegistry>>,
+    /// How market orders respond to the market being closed (resting orders wait for the open)
+    pub closed_market_action: ClosedMarketAction,
+    /// Record an equity snapshot after each order fill (off by default, as the history is persisted)
+    pub snapshot_on_fill: bool,
+    /// Record an equity snapshot at this interval of the account clock (none if not set)
+    pub snapshot_interval: Option<Duration>,
+    /// Maximum number of snapshots kept in the equity history, dropping the oldest (unlimited if not set)
+    pub max_snapshots: Option<usize>,
     /// Log level for the library
     pub log_level: String,
     /// Path for data persistence (if enabled)
     pub storage_path: Option<String>,
+    /// Clock used for timestamps (the system clock if none is set)
+    pub clock: Option<SharedClock>,
+}
+
+impl Config {
+    /// Get the current time from the configured clock
+    pub fn now(&self) -> DateTime<Utc> {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => Utc::now(),
+        }
+    }
+
+    /// Get the trading calendar for a symbol, if the instruments define one
+    pub fn calendar(&self, symbol: &Symbol) -> Option<&TradingCalendar> {
+        self.instruments
+            .as_ref()
+            .and_then(|instruments| instruments.calendar(symbol))
+            .map(|calendar| calendar.as_ref())
+    }
 }
 
 impl Default for Config {
@@ -23,14 +90,26 @@ impl Default for Config {
             default_slippage: Decimal::ZERO,
             default_spread: Decimal::ZERO,
             commission_rate: Decimal::ZERO,
+            cash_interest_rate: Decimal::ZERO,
+            interest_day_count: 365,
+            max_quote_age: None,
+            reject_crossed_quotes: true,
+            reject_locked_quotes: false,
+            invalid_quote_action: InvalidQuoteAction::default(),
+            instruments: None,
+            closed_market_action: ClosedMarketAction::default(),
+            snapshot_on_fill: false,
+            snapshot_interval: None,
+            max_snapshots: None,
             log_level: "info".to_string(),
             storage_path: None,
+            clock: None,
         }
     }
 }
 
 /// Global configuration instance
-static mut CONFIG: Option<Config> = None;
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
 static CONFIG_INIT: Once = Once::new();
 
 /// Initialize the global configuration with default values
@@ -39,9 +118,7 @@ pub fn init() {
     CONFIG_INIT.call_once(|| {
         let default_config = Config::default();
         debug!("Config::init() - Default config initialized: {:?}", default_config);
-        unsafe {
-            CONFIG = Some(default_config);
-        }
+        *CONFIG.write().unwrap() = Some(default_config);
     });
 }
 
@@ -50,27 +127,27 @@ pub fn init_with_config(config: Config) {
     info!("Config::init_with_config() - Initializing with custom config");
     debug!("Config::init_with_config() - Custom config: {:?}", config);
     CONFIG_INIT.call_once(|| {
-        unsafe {
-            CONFIG = Some(config);
-        }
+        *CONFIG.write().unwrap() = Some(config);
     });
 }
 
 /// Get the global configuration
+///
+/// Takes a read lock, so concurrent callers don't block each other; the write
+/// lock is only taken once, to store the default configuration.
 pub fn get() -> Config {
-    unsafe {
-        // If CONFIG is not initialized, initialize it with default values
-        if CONFIG.is_none() {
-            debug!("Config::get() - No config found, creating default");
-            let default_config = Config::default();
-            CONFIG = Some(default_config.clone());
-            debug!("Config::get() - Created default config: {:?}", default_config);
-            return default_config;
-        }
-        
+    if let Some(config) = CONFIG.read().unwrap().as_ref() {
         // Return a clone of the config
-        let config = CONFIG.as_ref().unwrap().clone();
         debug!("Config::get() - Returning existing config with storage_path: {:?}", config.storage_path);
-        config
+        return config.clone();
     }
+
+    // If CONFIG is not initialized, initialize it with default values
+    let mut guard = CONFIG.write().unwrap();
+    let config = guard.get_or_insert_with(|| {
+        debug!("Config::get() - No config found, creating default");
+        Config::default()
+    });
+    debug!("Config::get() - Using config: {:?}", config);
+    config.clone()
 }
@@ -22,6 +22,11 @@ pub enum Error {
         reason: String,
     },
     
+    #[error("Duplicate client order ID: {client_order_id}")]
+    DuplicateClientOrderId {
+        client_order_id: String,
+    },
+    
     #[error("Order not found: {order_id}")]
     OrderNotFound {
         order_id: OrderId,
@@ -52,9 +57,17 @@ pub enum Error {
         reason: String,
     },
     
+    #[error("Analytics error: {reason}")]
+    AnalyticsError {
+        reason: String,
+    },
+    
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
     
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+    
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
     
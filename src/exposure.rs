@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Price, Quantity, Symbol};
+
+/// Group name for positions whose instrument has no asset class or sector
+pub const UNCLASSIFIED: &str = "unclassified";
+
+/// Market value of one open position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionExposure {
+    /// Symbol of the position
+    pub symbol: Symbol,
+    /// Signed quantity (negative when short)
+    pub quantity: Quantity,
+    /// Price the position is marked at
+    pub price: Price,
+    /// Signed market value
+    pub market_value: Decimal,
+    /// Market value as a percentage of equity (zero if equity isn't positive)
+    pub weight: Decimal,
+    /// Asset class from the instrument metadata
+    pub asset_class: Option<String>,
+    /// Sector from the instrument metadata
+    pub sector: Option<String>,
+}
+
+/// Exposure of a group of positions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupExposure {
+    /// Market value of long positions
+    pub long_market_value: Decimal,
+    /// Absolute market value of short positions
+    pub short_market_value: Decimal,
+    /// Long plus short market value
+    pub gross_exposure: Decimal,
+    /// Long minus short market value
+    pub net_exposure: Decimal,
+    /// Gross exposure as a percentage of equity (zero if equity isn't positive)
+    pub percent_of_equity: Decimal,
+}
+
+impl GroupExposure {
+    fn add(&mut self, market_value: Decimal) {
+        if market_value >= Decimal::ZERO {
+            self.long_market_value += market_value;
+        } else {
+            self.short_market_value -= market_value;
+        }
+        self.gross_exposure += market_value.abs();
+        self.net_exposure += market_value;
+    }
+}
+
+/// Account-level exposure, by position and grouped by instrument metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExposureReport {
+    /// Time the report was taken
+    pub timestamp: DateTime<Utc>,
+    /// Total equity (cash + positions)
+    pub equity: Decimal,
+    /// Cash balance
+    pub cash: Decimal,
+    /// Exposure across all positions
+    pub total: GroupExposure,
+    /// Gross exposure divided by equity (`None` if equity isn't positive)
+    pub leverage: Option<Decimal>,
+    /// Symbol of the largest position by absolute market value
+    pub largest_position: Option<Symbol>,
+    /// Largest position's absolute market value as a percentage of equity
+    pub concentration: Option<Decimal>,
+    /// Open positions, largest absolute market value first
+    pub positions: Vec<PositionExposure>,
+    /// Exposure by asset class
+    pub by_asset_class: BTreeMap<String, GroupExposure>,
+    /// Exposure by sector
+    pub by_sector: BTreeMap<String, GroupExposure>,
+}
+
+impl ExposureReport {
+    /// Build a report from cash and open positions
+    ///
+    /// Position weights are filled in from the resulting equity.
+    pub fn new(timestamp: DateTime<Utc>, cash: Decimal, mut positions: Vec<PositionExposure>) -> Self {
+        let mut total = GroupExposure::default();
+        let mut by_asset_class: BTreeMap<String, GroupExposure> = BTreeMap::new();
+        let mut by_sector: BTreeMap<String, GroupExposure> = BTreeMap::new();
+        for position in &positions {
+            total.add(position.market_value);
+            by_asset_class
+                .entry(position.asset_class.clone().unwrap_or_else(|| UNCLASSIFIED.to_string()))
+                .or_default()
+                .add(position.market_value);
+            by_sector
+                .entry(position.sector.clone().unwrap_or_else(|| UNCLASSIFIED.to_string()))
+                .or_default()
+                .add(position.market_value);
+        }
+
+        let equity = cash + total.net_exposure;
+        let percent = |value: Decimal| {
+            if equity > Decimal::ZERO {
+                value / equity * Decimal::from(100)
+            } else {
+                Decimal::ZERO
+            }
+        };
+        for position in &mut positions {
+            position.weight = percent(position.market_value);
+        }
+        for group in std::iter::once(&mut total).chain(by_asset_class.values_mut()).chain(by_sector.values_mut()) {
+            group.percent_of_equity = percent(group.gross_exposure);
+        }
+        positions.sort_by(|a, b| b.market_value.abs().cmp(&a.market_value.abs()).then_with(|| a.symbol.0.cmp(&b.symbol.0)));
+
+        let largest = positions.first();
+        let positive_equity = equity > Decimal::ZERO;
+        Self {
+            timestamp,
+            equity,
+            cash,
+            leverage: positive_equity.then(|| total.gross_exposure / equity),
+            largest_position: largest.map(|position| position.symbol.clone()),
+            concentration: largest
+                .filter(|_| positive_equity)
+                .map(|position| percent(position.market_value.abs())),
+            total,
+            positions,
+            by_asset_class,
+            by_sector,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn position(
+        symbol: &str,
+        quantity: Decimal,
+        price: Decimal,
+        asset_class: Option<&str>,
+        sector: Option<&str>,
+    ) -> PositionExposure {
+        PositionExposure {
+            symbol: Symbol::new(symbol),
+            quantity: Quantity(quantity),
+            price: Price(price),
+            market_value: quantity * price,
+            weight: Decimal::ZERO,
+            asset_class: asset_class.map(str::to_string),
+            sector: sector.map(str::to_string),
+        }
+    }
+
+    fn timestamp() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn exposure_is_grouped_by_instrument_metadata() {
+        let positions = vec![
+            position("CCC", dec!(10), dec!(50), None, None),
+            position("BBB", dec!(-50), dec!(20), Some("equity"), Some("energy")),
+            position("AAA", dec!(100), dec!(10), Some("equity"), Some("technology")),
+        ];
+
+        let report = ExposureReport::new(timestamp(), dec!(9500), positions);
+
+        assert_eq!(report.equity, dec!(10000));
+        assert_eq!(report.total.long_market_value, dec!(1500));
+        assert_eq!(report.total.short_market_value, dec!(1000));
+        assert_eq!((report.total.gross_exposure, report.total.net_exposure), (dec!(2500), dec!(500)));
+        assert_eq!(report.total.percent_of_equity, dec!(25));
+        assert_eq!(report.leverage, Some(dec!(0.25)));
+
+        let weights: Vec<(&str, Decimal)> = report
+            .positions
+            .iter()
+            .map(|position| (position.symbol.0.as_str(), position.weight))
+            .collect();
+        assert_eq!(weights, vec![("AAA", dec!(10)), ("BBB", dec!(-10)), ("CCC", dec!(5))]);
+        assert_eq!(report.largest_position, Some(Symbol::new("AAA")));
+        assert_eq!(report.concentration, Some(dec!(10)));
+
+        let equity = report.by_asset_class["equity"];
+        assert_eq!((equity.gross_exposure, equity.net_exposure), (dec!(2000), dec!(0)));
+        assert_eq!(equity.percent_of_equity, dec!(20));
+        assert_eq!(report.by_asset_class[UNCLASSIFIED].gross_exposure, dec!(500));
+        assert_eq!(report.by_sector["energy"].short_market_value, dec!(1000));
+        assert_eq!(report.by_sector.len(), 3);
+    }
+
+    #[test]
+    fn ratios_are_omitted_without_positive_equity() {
+        let positions = vec![position("AAA", dec!(-100), dec!(10), None, None)];
+
+        let report = ExposureReport::new(timestamp(), dec!(500), positions);
+
+        assert_eq!(report.equity, dec!(-500));
+        assert_eq!(report.leverage, None);
+        assert_eq!(report.concentration, None);
+        assert_eq!(report.largest_position, Some(Symbol::new("AAA")));
+        assert_eq!(report.positions[0].weight, Decimal::ZERO);
+        assert_eq!(report.total.percent_of_equity, Decimal::ZERO);
+    }
+
+    #[test]
+    fn reports_without_positions_are_empty() {
+        let report = ExposureReport::new(timestamp(), dec!(1000), Vec::new());
+
+        assert_eq!(report.total, GroupExposure::default());
+        assert_eq!(report.leverage, Some(Decimal::ZERO));
+        assert_eq!((report.largest_position, report.concentration), (None, None));
+        assert!(report.by_asset_class.is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::book::BookFill;
+use crate::error::Result;
+use crate::market::{AuctionPrint, MarketDataProvider, PricePath, Quote};
+use crate::order::{Auction, OrderSide};
+use crate::types::{Price, Quantity, Symbol};
+
+/// Represents whether a symbol is trading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TradingStatus {
+    /// Trading normally
+    #[default]
+    Trading,
+    /// Trading is halted, with the reason
+    Halted(String),
+}
+
+impl TradingStatus {
+    /// Check if trading is halted
+    pub fn is_halted(&self) -> bool {
+        matches!(self, TradingStatus::Halted(_))
+    }
+}
+
+/// Limit-up/limit-down band of prices a symbol may trade at
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceBands {
+    /// Lowest allowed price
+    pub lower: Price,
+    /// Highest allowed price
+    pub upper: Price,
+}
+
+impl PriceBands {
+    /// Create bands from explicit limits
+    pub fn new(lower: Price, upper: Price) -> Self {
+        Self { lower, upper }
+    }
+
+    /// Create bands a percentage either side of a reference price (e.g., 0.05 for 5%)
+    pub fn around(reference: Price, percent: Decimal) -> Self {
+        let offset = reference.0 * percent;
+        Self {
+            lower: Price(reference.0 - offset),
+            upper: Price(reference.0 + offset),
+        }
+    }
+
+    /// Check if a price is within the bands
+    pub fn contains(&self, price: Price) -> bool {
+        price.0 >= self.lower.0 && price.0 <= self.upper.0
+    }
+
+    /// Get the worst price an order on a side may execute at
+    pub fn limit_for(&self, side: OrderSide) -> Price {
+        match side {
+            OrderSide::Buy => self.upper,
+            OrderSide::Sell => self.lower,
+        }
+    }
+}
+
+/// Halts and price bands by symbol
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketControls {
+    halts: HashMap<String, String>,
+    bands: HashMap<String, PriceBands>,
+}
+
+impl MarketControls {
+    /// Create controls with no halts or bands
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Halt trading in a symbol
+    pub fn halt<S: Into<String>>(&mut self, symbol: &Symbol, reason: S) {
+        self.halts.insert(symbol.0.clone(), reason.into());
+    }
+
+    /// Resume trading in a symbol, returning `true` if it was halted
+    pub fn resume(&mut self, symbol: &Symbol) -> bool {
+        self.halts.remove(&symbol.0).is_some()
+    }
+
+    /// Get the trading status of a symbol
+    pub fn status(&self, symbol: &Symbol) -> TradingStatus {
+        match self.halts.get(&symbol.0) {
+            Some(reason) => TradingStatus::Halted(reason.clone()),
+            None => TradingStatus::Trading,
+        }
+    }
+
+    /// Set the price bands of a symbol
+    pub fn set_bands(&mut self, symbol: &Symbol, bands: PriceBands) {
+        self.bands.insert(symbol.0.clone(), bands);
+    }
+
+    /// Remove the price bands of a symbol
+    pub fn clear_bands(&mut self, symbol: &Symbol) -> Option<PriceBands> {
+        self.bands.remove(&symbol.0)
+    }
+
+    /// Get the price bands of a symbol
+    pub fn bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+        self.bands.get(&symbol.0).copied()
+    }
+}
+
+/// Market data provider that adds halts and price bands to another provider
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker<P> {
+    inner: P,
+    controls: MarketControls,
+}
+
+impl<P: MarketDataProvider> CircuitBreaker<P> {
+    /// Wrap a provider with no halts or bands
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            controls: MarketControls::new(),
+        }
+    }
+
+    /// Get the halts and price bands
+    pub fn controls(&self) -> &MarketControls {
+        &self.controls
+    }
+
+    /// Get the halts and price bands mutably
+    pub fn controls_mut(&mut self) -> &mut MarketControls {
+        &mut self.controls
+    }
+
+    /// Get the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get the wrapped provider mutably
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+impl<P: MarketDataProvider> MarketDataProvider for CircuitBreaker<P> {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        self.inner.get_quote(symbol)
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.inner.is_symbol_supported(symbol)
+    }
+
+    fn price_path(&self, symbol: &Symbol) -> Option<PricePath> {
+        self.inner.price_path(symbol)
+    }
+
+    fn book_fills(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        quantity: Quantity,
+        limit: Option<Price>,
+    ) -> Option<Vec<BookFill>> {
+        self.inner.book_fills(symbol, side, quantity, limit)
+    }
+
+    fn consume_liquidity(&self, symbol: &Symbol, side: OrderSide, fills: &[BookFill]) {
+        self.inner.consume_liquidity(symbol, side, fills);
+    }
+
+    fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+        self.inner.auction_print(symbol, auction)
+    }
+
+    fn trading_status(&self, symbol: &Symbol) -> TradingStatus {
+        match self.controls.status(symbol) {
+            TradingStatus::Trading => self.inner.trading_status(symbol),
+            halted => halted,
+        }
+    }
+
+    fn price_bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+        self.controls.bands(symbol).or_else(|| self.inner.price_bands(symbol))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::SimpleMarketDataProvider;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn bands_around_a_reference_price() {
+        let bands = PriceBands::around(Price(dec!(50)), dec!(0.1));
+        assert_eq!(bands, PriceBands::new(Price(dec!(45)), Price(dec!(55))));
+        assert!(bands.contains(Price(dec!(45))));
+        assert!(bands.contains(Price(dec!(55))));
+        assert!(!bands.contains(Price(dec!(55.01))));
+        assert_eq!(bands.limit_for(OrderSide::Buy), Price(dec!(55)));
+        assert_eq!(bands.limit_for(OrderSide::Sell), Price(dec!(45)));
+    }
+
+    #[test]
+    fn circuit_breaker_overrides_the_inner_provider() {
+        let symbol = Symbol::new("AAA");
+        let mut inner = SimpleMarketDataProvider::new();
+        inner.set_price_bands(&symbol, PriceBands::new(Price(dec!(1)), Price(dec!(2))));
+        let mut breaker = CircuitBreaker::new(inner);
+        assert_eq!(breaker.price_bands(&symbol), Some(PriceBands::new(Price(dec!(1)), Price(dec!(2)))));
+
+        breaker.controls_mut().set_bands(&symbol, PriceBands::new(Price(dec!(3)), Price(dec!(4))));
+        breaker.controls_mut().halt(&symbol, "pending news");
+        assert_eq!(breaker.price_bands(&symbol), Some(PriceBands::new(Price(dec!(3)), Price(dec!(4)))));
+        assert_eq!(breaker.trading_status(&symbol), TradingStatus::Halted("pending news".to_string()));
+
+        assert!(breaker.controls_mut().resume(&symbol));
+        assert!(!breaker.controls_mut().resume(&symbol));
+        assert_eq!(breaker.trading_status(&symbol), TradingStatus::Trading);
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use uuid::{Builder, ContextV7, Timestamp, Uuid};
+
+use crate::clock::{self, SharedClock};
+
+/// Source of unique identifiers for accounts, orders and trades
+pub trait IdGenerator: Debug + Send + Sync {
+    /// Generate the next identifier
+    fn next_uuid(&self) -> Uuid;
+}
+
+/// Shared handle to an ID generator
+pub type SharedIdGenerator = Arc<dyn IdGenerator>;
+
+/// Generates random (version 4) UUIDs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn next_uuid(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Generates version 4 UUIDs from a seeded RNG, so identical runs produce identical IDs
+#[derive(Debug)]
+pub struct SeededIdGenerator {
+    rng: Mutex<ChaCha8Rng>,
+}
+
+impl SeededIdGenerator {
+    /// Create a new seeded ID generator
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(ChaCha8Rng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl IdGenerator for SeededIdGenerator {
+    fn next_uuid(&self) -> Uuid {
+        let bytes: [u8; 16] = self.rng.lock().unwrap().gen();
+        Builder::from_random_bytes(bytes).into_uuid()
+    }
+}
+
+/// Generates monotonically increasing IDs from a counter
+/// (`00000000-0000-0000-0000-000000000001`, `...0002`, ...)
+///
+/// When resuming a saved manager, start the counter past the highest existing ID.
+#[derive(Debug)]
+pub struct SequentialIdGenerator {
+    counter: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    /// Create a new sequential ID generator whose first ID is `start`
+    pub fn new(start: u64) -> Self {
+        Self {
+            counter: AtomicU64::new(start),
+        }
+    }
+}
+
+impl Default for SequentialIdGenerator {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_uuid(&self) -> Uuid {
+        let value = self.counter.fetch_add(1, Ordering::SeqCst);
+        Uuid::from_u128(value as u128)
+    }
+}
+
+/// Generates time-ordered (version 7) UUIDs
+///
+/// Timestamps are taken from the given clock, or the global clock if none is set,
+/// so IDs sort by simulated time in backtests.
+#[derive(Debug)]
+pub struct TimeOrderedIdGenerator {
+    clock: Option<SharedClock>,
+    context: Mutex<ContextV7>,
+}
+
+impl TimeOrderedIdGenerator {
+    /// Create a new time-ordered ID generator using the global clock
+    pub fn new() -> Self {
+        Self {
+            clock: None,
+            context: Mutex::new(ContextV7::new()),
+        }
+    }
+
+    /// Use a specific clock for the UUID timestamps
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+}
+
+impl Default for TimeOrderedIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for TimeOrderedIdGenerator {
+    fn next_uuid(&self) -> Uuid {
+        let now = match &self.clock {
+            Some(clock) => clock.now(),
+            None => clock::now(),
+        };
+        let seconds = now.timestamp().max(0) as u64;
+        let context = self.context.lock().unwrap();
+        Uuid::new_v7(Timestamp::from_unix(&*context, seconds, now.timestamp_subsec_nanos()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequential_ids_count_up_from_the_start() {
+        let generator = SequentialIdGenerator::new(5);
+        assert_eq!(generator.next_uuid(), Uuid::from_u128(5));
+        assert_eq!(generator.next_uuid(), Uuid::from_u128(6));
+    }
+
+    #[test]
+    fn seeded_ids_are_reproducible() {
+        let first = SeededIdGenerator::new(42);
+        let second = SeededIdGenerator::new(42);
+        let ids: Vec<Uuid> = (0..3).map(|_| first.next_uuid()).collect();
+        assert_eq!(ids, (0..3).map(|_| second.next_uuid()).collect::<Vec<_>>());
+        assert_ne!(ids[0], SeededIdGenerator::new(7).next_uuid());
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::calendar::TradingCalendar;
+use crate::types::Symbol;
+
+/// Static data about a tradable instrument
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    /// Symbol of the instrument
+    pub symbol: Symbol,
+    /// Calendar the instrument trades on (trades around the clock if none is set)
+    pub calendar: Option<Arc<TradingCalendar>>,
+    /// Asset class (e.g., "equity", "crypto"), used to group exposure
+    pub asset_class: Option<String>,
+    /// Sector (e.g., "technology"), used to group exposure
+    pub sector: Option<String>,
+}
+
+impl Instrument {
+    /// Create an instrument that trades around the clock
+    pub fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            calendar: None,
+            asset_class: None,
+            sector: None,
+        }
+    }
+
+    /// Set the calendar the instrument trades on
+    pub fn with_calendar(mut self, calendar: Arc<TradingCalendar>) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// Set the asset class of the instrument
+    pub fn with_asset_class<S: Into<String>>(mut self, asset_class: S) -> Self {
+        self.asset_class = Some(asset_class.into());
+        self
+    }
+
+    /// Set the sector of the instrument
+    pub fn with_sector<S: Into<String>>(mut self, sector: S) -> Self {
+        self.sector = Some(sector.into());
+        self
+    }
+}
+
+/// Registry of instruments by symbol
+#[derive(Debug, Clone, Default)]
+pub struct InstrumentRegistry {
+    instruments: HashMap<String, Instrument>,
+    default_calendar: Option<Arc<TradingCalendar>>,
+}
+
+impl InstrumentRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an instrument, replacing any with the same symbol
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.add(instrument);
+        self
+    }
+
+    /// Set the calendar for symbols that aren't in the registry (always open if none is set)
+    ///
+    /// Registered instruments always use their own calendar, so an instrument
+    /// without one trades around the clock.
+    pub fn with_default_calendar(mut self, calendar: Arc<TradingCalendar>) -> Self {
+        self.default_calendar = Some(calendar);
+        self
+    }
+
+    /// Add an instrument, replacing any with the same symbol
+    pub fn add(&mut self, instrument: Instrument) {
+        self.instruments.insert(instrument.symbol.0.clone(), instrument);
+    }
+
+    /// Get an instrument by symbol
+    pub fn get(&self, symbol: &Symbol) -> Option<&Instrument> {
+        self.instruments.get(&symbol.0)
+    }
+
+    /// Get the calendar a symbol trades on
+    ///
+    /// This is the instrument's own calendar if the symbol is registered, and the
+    /// default calendar otherwise; `None` means the symbol trades around the clock.
+    pub fn calendar(&self, symbol: &Symbol) -> Option<&Arc<TradingCalendar>> {
+        match self.get(symbol) {
+            Some(instrument) => instrument.calendar.as_ref(),
+            None => self.default_calendar.as_ref(),
+        }
+    }
+
+    /// Get all instruments
+    pub fn instruments(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_instruments_use_their_own_calendar() {
+        let exchange = Arc::new(TradingCalendar::us_equities());
+        let registry = InstrumentRegistry::new()
+            .with_instrument(Instrument::new(Symbol::new("AAPL")).with_calendar(exchange.clone()))
+            .with_instrument(Instrument::new(Symbol::new("BTC/USD")))
+            .with_default_calendar(exchange.clone());
+
+        assert!(registry.calendar(&Symbol::new("AAPL")).is_some());
+        assert!(registry.calendar(&Symbol::new("BTC/USD")).is_none());
+        assert!(registry.calendar(&Symbol::new("MSFT")).is_some());
+        assert!(InstrumentRegistry::new().calendar(&Symbol::new("MSFT")).is_none());
+    }
+}
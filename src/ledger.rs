@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use crate::types::Symbol;
+
+/// Represents the kind of a cash ledger entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CashEntryKind {
+    /// Interest credited on a positive cash balance
+    InterestCredit,
+    /// Cash deposited into the account
+    Deposit,
+    /// Cash withdrawn from the account
+    Withdrawal,
+    /// Cash transferred in from another account
+    TransferIn,
+    /// Cash transferred out to another account
+    TransferOut,
+}
+
+impl CashEntryKind {
+    /// Check if the entry moves capital in or out of the account, rather than earning or costing it
+    pub fn is_external_flow(&self) -> bool {
+        matches!(
+            self,
+            CashEntryKind::Deposit | CashEntryKind::Withdrawal | CashEntryKind::TransferIn | CashEntryKind::TransferOut
+        )
+    }
+}
+
+/// Represents a single cash movement posted to an account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CashEntry {
+    /// Kind of the entry
+    pub kind: CashEntryKind,
+    /// Signed amount applied to the cash balance (credits are positive, charges negative)
+    pub amount: Decimal,
+    /// Symbol the entry relates to, if any
+    pub symbol: Option<Symbol>,
+    /// Timestamp of the entry
+    pub timestamp: DateTime<Utc>,
+}
+
+impl CashEntry {
+    /// Create a new cash entry
+    pub fn new(
+        kind: CashEntryKind,
+        amount: Decimal,
+        symbol: Option<Symbol>,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            kind,
+            amount,
+            symbol,
+            timestamp,
+        }
+    }
+
+    /// Check if the entry is a credit to the account
+    pub fn is_credit(&self) -> bool {
+        self.amount > Decimal::ZERO
+    }
+
+    /// Check if the entry moves capital in or out of the account
+    pub fn is_external_flow(&self) -> bool {
+        self.kind.is_external_flow()
+    }
+}
@@ -4,10 +4,31 @@
 //! It provides functionality for managing a paper trading account, including:
 //! 
 //! - Account creation and management
-//! - Order placement (market, limit)
+//! - Order placement (market, limit, stop, and on-open/on-close auction orders)
 //! - Position tracking
 //! - Portfolio valuation
 //! - Trade history
+//! - Historical bar loading from CSV and JSON Lines files
+//! - Bar-by-bar replay of historical data
+//! - Tick-level replay of quotes and trades across many symbols
+//! - Seedable synthetic market generators
+//! - Level-2 order books that orders walk for multi-level fills
+//! - Validation of quotes before execution
+//! - Composable market data providers (fallback, routing, overrides, caching)
+//! - Push-based order processing through per-symbol subscriptions
+//! - HTTP and WebSocket quote adapters with a local stub server (`remote` feature)
+//! - Trading calendars with extended hours, early closes and holidays
+//! - Trading halts and limit-up/limit-down price bands
+//! - Equity history snapshots on fills and at clock intervals, exportable as CSV or JSON Lines
+//! - Risk analytics: annualized return and volatility, Sharpe, Sortino and Calmar ratios, and drawdowns
+//! - Time- and money-weighted returns that exclude deposits, withdrawals and transfers
+//! - Benchmark-relative alpha, beta, tracking error, information ratio and capture ratios
+//! - Round-trip reconstruction with win rate, expectancy, profit factor, streaks and excursions
+//! - P&L attribution by symbol, long/short side, day and trade strategy tag
+//! - Unique client order IDs and key/value metadata on orders and trades
+//! - Order and trade queries with filters, sorting and cursor pagination, per account or across accounts
+//! - Exposure reports with leverage, concentration and grouping by asset class and sector
+//! - Daily interest accrual on cash balances
 
 extern crate log;
 extern crate env_logger;
@@ -23,15 +44,52 @@ pub mod types;
 pub mod market;
 pub mod config;
 pub mod manager;
+pub mod ledger;
+pub mod clock;
+pub mod id;
+pub mod loader;
+pub mod bars;
+pub mod replay;
+pub mod tick;
+pub mod synthetic;
+pub mod book;
+pub mod composite;
+pub mod calendar;
+pub mod instrument;
+pub mod halt;
+pub mod snapshot;
+pub mod analytics;
+pub mod roundtrip;
+pub mod attribution;
+pub mod query;
+pub mod exposure;
+#[cfg(feature = "remote")]
+pub mod remote;
 
 // Re-export commonly used types
-pub use account::Account;
-pub use order::{Order, OrderType, OrderSide, OrderStatus};
+pub use account::{Account, OrderMut};
+pub use order::{Auction, Order, OrderType, OrderSide, OrderStatus, TimeInForce};
 pub use position::Position;
 pub use error::Error;
 pub use types::{Symbol, Quantity, Price, TradeId, OrderId, AccountId};
-pub use config::Config;
+pub use config::{ClosedMarketAction, Config, InvalidQuoteAction};
 pub use manager::AccountManager;
+pub use ledger::{CashEntry, CashEntryKind};
+pub use bars::{BarInterval, SessionBoundary};
+pub use calendar::{Session, SessionPhase, TradingCalendar};
+pub use instrument::{Instrument, InstrumentRegistry};
+pub use halt::{CircuitBreaker, MarketControls, PriceBands, TradingStatus};
+pub use snapshot::{EquityHistory, EquitySnapshot, SnapshotKind};
+pub use analytics::{BenchmarkReport, Drawdown, RelativePoint, ReturnReport, RiskAnalytics, RiskReport};
+pub use roundtrip::{RoundTrip, RoundTripReport, RoundTripStats};
+pub use attribution::{PnlAttribution, PnlBreakdown};
+pub use query::{OrderQuery, Page, QueryCursor, SortDirection};
+pub use exposure::{ExposureReport, GroupExposure, PositionExposure};
+pub use clock::{Clock, SharedClock, SimulatedClock, SystemClock};
+pub use id::{
+    IdGenerator, SharedIdGenerator, RandomIdGenerator, SeededIdGenerator,
+    SequentialIdGenerator, TimeOrderedIdGenerator,
+};
 
 // Initialize configuration when the library is loaded
 #[allow(unused_variables)]
@@ -0,0 +1,567 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+pub use chrono_tz::Tz;
+use csv::StringRecord;
+use log::{debug, info};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::market::HistoricalDataPoint;
+use crate::types::{Price, Symbol};
+
+/// Format of the timestamp column in a bar file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 / ISO 8601 timestamp with an offset (e.g., `2019-01-02T14:30:00Z`)
+    Rfc3339,
+    /// Seconds since the Unix epoch
+    UnixSeconds,
+    /// Milliseconds since the Unix epoch
+    UnixMillis,
+    /// `chrono` format string for a date and time without an offset
+    /// (e.g., `%Y-%m-%d %H:%M:%S`), interpreted in the loader's time zone
+    DateTime(String),
+    /// `chrono` format string for a date only (e.g., `%Y-%m-%d`),
+    /// interpreted as midnight in the loader's time zone
+    Date(String),
+}
+
+/// Mapping from bar fields to column (CSV header) or field (JSON) names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    /// Timestamp column
+    pub timestamp: String,
+    /// Open price column
+    pub open: String,
+    /// High price column
+    pub high: String,
+    /// Low price column
+    pub low: String,
+    /// Close price column
+    pub close: String,
+    /// Volume column
+    pub volume: String,
+    /// Symbol column (if not set, the symbol passed to the reader is used)
+    pub symbol: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            timestamp: "timestamp".to_string(),
+            open: "open".to_string(),
+            high: "high".to_string(),
+            low: "low".to_string(),
+            close: "close".to_string(),
+            volume: "volume".to_string(),
+            symbol: None,
+        }
+    }
+}
+
+/// File format of a bar file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarFileFormat {
+    /// Comma (or other delimiter) separated values with a header row
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl BarFileFormat {
+    /// Detect the format from a file extension (`csv`, `txt`, `jsonl`, `ndjson`, `json`)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" | "txt" => Some(BarFileFormat::Csv),
+            "jsonl" | "ndjson" | "json" => Some(BarFileFormat::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// Streaming iterator over bars
+pub type BarIterator = Box<dyn Iterator<Item = Result<HistoricalDataPoint>>>;
+
+/// Loads OHLCV bars from CSV and JSON Lines files
+///
+/// Readers stream one bar at a time, so large files never have to fit in memory.
+#[derive(Debug, Clone)]
+pub struct BarLoader {
+    columns: ColumnMapping,
+    timestamp_format: TimestampFormat,
+    time_zone: Tz,
+    delimiter: u8,
+}
+
+impl Default for BarLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BarLoader {
+    /// Create a new bar loader with default columns, RFC 3339 timestamps and UTC
+    pub fn new() -> Self {
+        Self {
+            columns: ColumnMapping::default(),
+            timestamp_format: TimestampFormat::Rfc3339,
+            time_zone: Tz::UTC,
+            delimiter: b',',
+        }
+    }
+
+    /// Set the column mapping
+    pub fn with_columns(mut self, columns: ColumnMapping) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Set the timestamp format
+    pub fn with_timestamp_format(mut self, timestamp_format: TimestampFormat) -> Self {
+        self.timestamp_format = timestamp_format;
+        self
+    }
+
+    /// Set the time zone used for timestamps without an offset
+    pub fn with_time_zone(mut self, time_zone: Tz) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    /// Set the CSV delimiter
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Open a CSV file for streaming
+    pub fn csv_file<P: AsRef<Path>>(&self, path: P, symbol: &Symbol) -> Result<CsvBarReader<File>> {
+        debug!("BarLoader::csv_file() - Opening: {:?}", path.as_ref());
+        self.csv_reader(File::open(path)?, symbol)
+    }
+
+    /// Stream bars from any CSV source
+    pub fn csv_reader<R: Read>(&self, reader: R, symbol: &Symbol) -> Result<CsvBarReader<R>> {
+        CsvBarReader::new(self.clone(), reader, symbol.clone())
+    }
+
+    /// Open a JSON Lines file for streaming
+    pub fn json_lines_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        symbol: &Symbol,
+    ) -> Result<JsonLinesBarReader<BufReader<File>>> {
+        debug!("BarLoader::json_lines_file() - Opening: {:?}", path.as_ref());
+        Ok(self.json_lines_reader(BufReader::new(File::open(path)?), symbol))
+    }
+
+    /// Stream bars from any JSON Lines source
+    pub fn json_lines_reader<R: BufRead>(&self, reader: R, symbol: &Symbol) -> JsonLinesBarReader<R> {
+        JsonLinesBarReader {
+            loader: self.clone(),
+            lines: reader.lines(),
+            symbol: symbol.clone(),
+        }
+    }
+
+    /// Open a file for streaming, detecting its format from the extension
+    pub fn file<P: AsRef<Path>>(&self, path: P, symbol: &Symbol) -> Result<BarIterator> {
+        let path = path.as_ref();
+        match BarFileFormat::from_path(path) {
+            Some(BarFileFormat::Csv) => Ok(Box::new(self.csv_file(path, symbol)?)),
+            Some(BarFileFormat::JsonLines) => Ok(Box::new(self.json_lines_file(path, symbol)?)),
+            None => Err(Error::MarketDataError {
+                reason: format!("Unsupported bar file: {}", path.display()),
+            }),
+        }
+    }
+
+    /// List the symbols in a directory-per-symbol layout (`root/<SYMBOL>/*.csv`)
+    pub fn symbols<P: AsRef<Path>>(&self, root: P) -> Result<Vec<Symbol>> {
+        let mut symbols = Vec::new();
+        for entry in fs::read_dir(root)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                symbols.push(Symbol::new(entry.file_name().to_string_lossy()));
+            }
+        }
+        symbols.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(symbols)
+    }
+
+    /// Stream all bar files for a symbol in a directory-per-symbol layout
+    ///
+    /// Files in `root/<SYMBOL>/` are read in file name order, one at a time,
+    /// so they should be named to sort chronologically (e.g., `2019-01.csv`).
+    pub fn symbol_directory<P: AsRef<Path>>(&self, root: P, symbol: &Symbol) -> Result<DirectoryBarReader> {
+        let directory = root.as_ref().join(&symbol.0);
+        let mut files: Vec<PathBuf> = fs::read_dir(&directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && BarFileFormat::from_path(path).is_some())
+            .collect();
+        files.sort();
+
+        info!("BarLoader::symbol_directory() - Found {} files for {} in {:?}", files.len(), symbol, directory);
+        files.reverse();
+
+        Ok(DirectoryBarReader {
+            loader: self.clone(),
+            symbol: symbol.clone(),
+            files,
+            current: None,
+        })
+    }
+
+    /// Build a bar from raw field values
+    fn build_bar(&self, fields: RawBar<'_>, default_symbol: &Symbol) -> Result<HistoricalDataPoint> {
+        Ok(HistoricalDataPoint {
+            symbol: fields.symbol.map(Symbol::new).unwrap_or_else(|| default_symbol.clone()),
+            open: Price(parse_decimal(&self.columns.open, fields.open)?),
+            high: Price(parse_decimal(&self.columns.high, fields.high)?),
+            low: Price(parse_decimal(&self.columns.low, fields.low)?),
+            close: Price(parse_decimal(&self.columns.close, fields.close)?),
+            volume: parse_decimal(&self.columns.volume, fields.volume)?
+                .to_f64()
+                .unwrap_or_default(),
+            timestamp: parse_timestamp(fields.timestamp, &self.timestamp_format, self.time_zone)?,
+        })
+    }
+}
+
+/// Parse a timestamp according to a format, interpreting local times in a time zone
+pub(crate) fn parse_timestamp(value: &str, format: &TimestampFormat, time_zone: Tz) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    let invalid = |reason: String| Error::MarketDataError {
+        reason: format!("Invalid timestamp '{}': {}", value, reason),
+    };
+    // Convert a local time to UTC (earliest match during DST overlaps)
+    let localize = |naive: NaiveDateTime| {
+        time_zone
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+            .ok_or_else(|| invalid(format!("does not exist in {}", time_zone)))
+    };
+
+    match format {
+        TimestampFormat::Rfc3339 => DateTime::parse_from_rfc3339(value)
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+            .map_err(|e| invalid(e.to_string())),
+        TimestampFormat::UnixSeconds => {
+            let seconds = i64::from_str(value).map_err(|e| invalid(e.to_string()))?;
+            DateTime::from_timestamp(seconds, 0).ok_or_else(|| invalid("out of range".to_string()))
+        }
+        TimestampFormat::UnixMillis => {
+            let millis = i64::from_str(value).map_err(|e| invalid(e.to_string()))?;
+            DateTime::from_timestamp_millis(millis).ok_or_else(|| invalid("out of range".to_string()))
+        }
+        TimestampFormat::DateTime(format) => {
+            let naive = NaiveDateTime::parse_from_str(value, format).map_err(|e| invalid(e.to_string()))?;
+            localize(naive)
+        }
+        TimestampFormat::Date(format) => {
+            let date = NaiveDate::parse_from_str(value, format).map_err(|e| invalid(e.to_string()))?;
+            let naive = date.and_hms_opt(0, 0, 0).ok_or_else(|| invalid("out of range".to_string()))?;
+            localize(naive)
+        }
+    }
+}
+
+/// Raw field values of a single bar
+struct RawBar<'a> {
+    timestamp: &'a str,
+    open: &'a str,
+    high: &'a str,
+    low: &'a str,
+    close: &'a str,
+    volume: &'a str,
+    symbol: Option<&'a str>,
+}
+
+/// Parse a decimal field value
+pub(crate) fn parse_decimal(column: &str, value: &str) -> Result<Decimal> {
+    let value = value.trim();
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .map_err(|e| Error::MarketDataError {
+            reason: format!("Invalid value '{}' in column '{}': {}", value, column, e),
+        })
+}
+
+/// Column positions resolved from a CSV header row
+#[derive(Debug, Clone)]
+struct ColumnIndices {
+    timestamp: usize,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    volume: usize,
+    symbol: Option<usize>,
+}
+
+/// Streaming CSV bar reader
+pub struct CsvBarReader<R: Read> {
+    loader: BarLoader,
+    reader: csv::Reader<R>,
+    indices: ColumnIndices,
+    record: StringRecord,
+    symbol: Symbol,
+}
+
+impl<R: Read> CsvBarReader<R> {
+    fn new(loader: BarLoader, reader: R, symbol: Symbol) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(loader.delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        let headers = reader.headers()?.clone();
+        let find = |name: &str| -> Result<usize> {
+            headers
+                .iter()
+                .position(|header| header.eq_ignore_ascii_case(name))
+                .ok_or_else(|| Error::MarketDataError {
+                    reason: format!("Missing column '{}' in CSV header", name),
+                })
+        };
+
+        let columns = &loader.columns;
+        let indices = ColumnIndices {
+            timestamp: find(&columns.timestamp)?,
+            open: find(&columns.open)?,
+            high: find(&columns.high)?,
+            low: find(&columns.low)?,
+            close: find(&columns.close)?,
+            volume: find(&columns.volume)?,
+            symbol: match &columns.symbol {
+                Some(name) => Some(find(name)?),
+                None => None,
+            },
+        };
+
+        Ok(Self {
+            loader,
+            reader,
+            indices,
+            record: StringRecord::new(),
+            symbol,
+        })
+    }
+}
+
+impl<R: Read> Iterator for CsvBarReader<R> {
+    type Item = Result<HistoricalDataPoint>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(false) => None,
+            Ok(true) => {
+                let record = &self.record;
+                let indices = &self.indices;
+                let field = |index: usize| record.get(index).unwrap_or_default();
+                let fields = RawBar {
+                    timestamp: field(indices.timestamp),
+                    open: field(indices.open),
+                    high: field(indices.high),
+                    low: field(indices.low),
+                    close: field(indices.close),
+                    volume: field(indices.volume),
+                    symbol: indices.symbol.map(field),
+                };
+                Some(self.loader.build_bar(fields, &self.symbol))
+            }
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
+/// Streaming JSON Lines bar reader
+pub struct JsonLinesBarReader<R: BufRead> {
+    loader: BarLoader,
+    lines: Lines<R>,
+    symbol: Symbol,
+}
+
+impl<R: BufRead> JsonLinesBarReader<R> {
+    /// Parse a single JSON line into a bar
+    fn parse_line(&self, line: &str) -> Result<HistoricalDataPoint> {
+        let value: Value = serde_json::from_str(line)?;
+        let columns = &self.loader.columns;
+
+        let timestamp = json_field(&value, &columns.timestamp)?;
+        let open = json_field(&value, &columns.open)?;
+        let high = json_field(&value, &columns.high)?;
+        let low = json_field(&value, &columns.low)?;
+        let close = json_field(&value, &columns.close)?;
+        let volume = json_field(&value, &columns.volume)?;
+        let symbol = match &columns.symbol {
+            Some(name) => Some(json_field(&value, name)?),
+            None => None,
+        };
+
+        let fields = RawBar {
+            timestamp: &timestamp,
+            open: &open,
+            high: &high,
+            low: &low,
+            close: &close,
+            volume: &volume,
+            symbol: symbol.as_deref(),
+        };
+        self.loader.build_bar(fields, &self.symbol)
+    }
+}
+
+/// Get a JSON field as a string, accepting both string and number values
+pub(crate) fn json_field(value: &Value, name: &str) -> Result<String> {
+    match value.get(name) {
+        Some(Value::String(text)) => Ok(text.clone()),
+        Some(Value::Number(number)) => Ok(number.to_string()),
+        _ => Err(Error::MarketDataError {
+            reason: format!("Missing field '{}' in JSON bar", name),
+        }),
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLinesBarReader<R> {
+    type Item = Result<HistoricalDataPoint>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(self.parse_line(&line));
+        }
+    }
+}
+
+/// Streaming reader over all bar files of one symbol, opened one at a time
+pub struct DirectoryBarReader {
+    loader: BarLoader,
+    symbol: Symbol,
+    /// Remaining files, in reverse order so the next file can be popped
+    files: Vec<PathBuf>,
+    current: Option<BarIterator>,
+}
+
+impl Iterator for DirectoryBarReader {
+    type Item = Result<HistoricalDataPoint>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(current) = &mut self.current {
+                if let Some(bar) = current.next() {
+                    return Some(bar);
+                }
+                self.current = None;
+            }
+
+            let path = self.files.pop()?;
+            debug!("DirectoryBarReader::next() - Opening: {:?}", path);
+            match self.loader.file(&path, &self.symbol) {
+                Ok(reader) => self.current = Some(reader),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bars::BarInterval;
+    use crate::market::{HistoricalDataProvider, SimpleHistoricalDataProvider};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn csv_bars_use_the_column_mapping_and_time_zone() {
+        let data = "Date;O;H;L;C;V\n2024-01-02 09:30;10;12;9;11;1e3\n";
+        let loader = BarLoader::new()
+            .with_columns(ColumnMapping {
+                timestamp: "date".to_string(),
+                open: "o".to_string(),
+                high: "h".to_string(),
+                low: "l".to_string(),
+                close: "c".to_string(),
+                volume: "v".to_string(),
+                symbol: None,
+            })
+            .with_timestamp_format(TimestampFormat::DateTime("%Y-%m-%d %H:%M".to_string()))
+            .with_time_zone(Tz::America__New_York)
+            .with_delimiter(b';');
+
+        let bars = loader
+            .csv_reader(data.as_bytes(), &Symbol::new("AAA"))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].symbol, Symbol::new("AAA"));
+        assert_eq!(bars[0].timestamp, Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap());
+        assert_eq!((bars[0].open, bars[0].close), (Price(dec!(10)), Price(dec!(11))));
+        assert_eq!(bars[0].volume, 1000.0);
+    }
+
+    #[test]
+    fn json_lines_bars_accept_strings_and_numbers_and_skip_blank_lines() {
+        let data = "{\"timestamp\": 1704205800, \"open\": \"10\", \"high\": 12, \"low\": 9, \"close\": 11.5, \"volume\": 100, \"ticker\": \"BBB\"}\n\n";
+        let loader = BarLoader::new()
+            .with_columns(ColumnMapping {
+                symbol: Some("ticker".to_string()),
+                ..ColumnMapping::default()
+            })
+            .with_timestamp_format(TimestampFormat::UnixSeconds);
+
+        let bars = loader
+            .json_lines_reader(data.as_bytes(), &Symbol::new("AAA"))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].symbol, Symbol::new("BBB"));
+        assert_eq!(bars[0].timestamp, Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap());
+        assert_eq!(bars[0].close, Price(dec!(11.5)));
+    }
+
+    #[test]
+    fn missing_columns_and_bad_values_are_errors() {
+        let loader = BarLoader::new();
+        let symbol = Symbol::new("AAA");
+
+        assert!(loader.csv_reader("timestamp,open,high,low,close\n".as_bytes(), &symbol).is_err());
+
+        let data = "timestamp,open,high,low,close,volume\n2024-01-02T14:30:00Z,ten,12,9,11,100\n";
+        let mut bars = loader.csv_reader(data.as_bytes(), &symbol).unwrap();
+        assert!(matches!(bars.next(), Some(Err(Error::MarketDataError { .. }))));
+    }
+
+    #[test]
+    fn nothing_is_stored_when_a_bar_fails_to_load() {
+        let symbol = Symbol::new("AAA");
+        let loader = BarLoader::new();
+        let mut provider = SimpleHistoricalDataProvider::new();
+
+        let data = "timestamp,open,high,low,close,volume\n\
+                    2024-01-02T14:30:00Z,10,12,9,11,100\n\
+                    not-a-time,10,12,9,11,100\n";
+        let bars = loader.csv_reader(data.as_bytes(), &symbol).unwrap();
+        assert!(provider.load_bars(&symbol, bars).is_err());
+
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap();
+        assert!(provider.get_historical_data(&symbol, start, end, BarInterval::Minutes(1)).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 use rust_decimal::Decimal;
@@ -8,16 +8,35 @@ use log::{debug, info, warn, trace};
 use crate::account::Account;
 use crate::config::Config;
 use crate::error::{Error, Result};
-use crate::types::AccountId;
+use crate::id::SharedIdGenerator;
+use crate::ledger::CashEntryKind;
+use crate::market::{MarketDataProvider, SimpleMarketDataProvider};
+use crate::order::{Order, Trade};
+use crate::query::{OrderQuery, Page};
+use crate::tick::TickEvent;
+use crate::types::{serialize_sorted, AccountId, Symbol};
 
 /// Manages multiple paper trading accounts
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountManager {
     /// Map of accounts by ID
+    #[serde(serialize_with = "serialize_sorted")]
     accounts: HashMap<String, Account>,
     /// Path to the storage file
     #[serde(skip)]
     storage_path: Option<PathBuf>,
+    /// Generator for account, order and trade IDs
+    #[serde(skip)]
+    id_generator: Option<SharedIdGenerator>,
+    /// Subscribed account IDs by symbol
+    #[serde(default, serialize_with = "serialize_sorted")]
+    subscriptions: HashMap<String, BTreeSet<String>>,
+}
+
+impl Default for AccountManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AccountManager {
@@ -37,7 +56,22 @@ impl AccountManager {
         Self {
             accounts: HashMap::new(),
             storage_path,
+            id_generator: None,
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    /// Set the ID generator for accounts created by this manager
+    ///
+    /// The generator is also attached to any accounts already held by the manager,
+    /// so it can be set again after loading from storage.
+    pub fn with_id_generator(mut self, id_generator: SharedIdGenerator) -> Self {
+        info!("AccountManager::with_id_generator() - Setting ID generator: {:?}", id_generator);
+        for account in self.accounts.values_mut() {
+            account.id_generator = Some(id_generator.clone());
         }
+        self.id_generator = Some(id_generator);
+        self
     }
 
     /// Set the storage path for account persistence
@@ -71,9 +105,7 @@ impl AccountManager {
         initial_deposit: Decimal
     ) -> Result<AccountId> {
         let account = Account::new(name, base_currency, initial_deposit);
-        let id = account.id;
-        self.accounts.insert(id.0.to_string(), account);
-        Ok(id)
+        Ok(self.insert_account(account))
     }
 
     /// Create a new account with custom configuration
@@ -84,10 +116,19 @@ impl AccountManager {
         initial_deposit: Decimal,
         config: Config
     ) -> Result<AccountId> {
-        let account = Account::new(name, base_currency, initial_deposit).with_config(config);
+        let account = Account::new_with_config(name, base_currency, initial_deposit, config);
+        Ok(self.insert_account(account))
+    }
+
+    /// Assign an ID from the manager's generator (if any) and store the account
+    fn insert_account(&mut self, mut account: Account) -> AccountId {
+        if let Some(id_generator) = &self.id_generator {
+            account.id = AccountId::generate(id_generator.as_ref());
+            account.id_generator = Some(id_generator.clone());
+        }
         let id = account.id;
         self.accounts.insert(id.0.to_string(), account);
-        Ok(id)
+        id
     }
 
     /// Get an account by ID
@@ -105,9 +146,109 @@ impl AccountManager {
         &self.accounts
     }
 
-    /// Remove an account by ID
+    /// Remove an account by ID, dropping its subscriptions
     pub fn remove_account(&mut self, id: &AccountId) -> Option<Account> {
-        self.accounts.remove(&id.0.to_string())
+        let id_str = id.0.to_string();
+        self.subscriptions.retain(|_, subscribers| {
+            subscribers.remove(&id_str);
+            !subscribers.is_empty()
+        });
+        self.accounts.remove(&id_str)
+    }
+
+    /// Subscribe an account to quote updates for a symbol
+    pub fn subscribe(&mut self, id: &AccountId, symbol: &Symbol) -> Result<()> {
+        let id_str = id.0.to_string();
+        if !self.accounts.contains_key(&id_str) {
+            return Err(Error::AccountNotFound { account_id: *id });
+        }
+
+        debug!("AccountManager::subscribe() - Subscribing account {} to {}", id_str, symbol);
+        self.subscriptions.entry(symbol.0.clone()).or_default().insert(id_str);
+        Ok(())
+    }
+
+    /// Unsubscribe an account from quote updates for a symbol
+    pub fn unsubscribe(&mut self, id: &AccountId, symbol: &Symbol) -> bool {
+        let id_str = id.0.to_string();
+        let removed = match self.subscriptions.get_mut(&symbol.0) {
+            Some(subscribers) => subscribers.remove(&id_str),
+            None => false,
+        };
+        if self.subscriptions.get(&symbol.0).is_some_and(BTreeSet::is_empty) {
+            self.subscriptions.remove(&symbol.0);
+        }
+        removed
+    }
+
+    /// Get the accounts subscribed to a symbol
+    pub fn subscribers(&self, symbol: &Symbol) -> Vec<AccountId> {
+        self.subscriptions
+            .get(&symbol.0)
+            .map(|subscribers| {
+                subscribers
+                    .iter()
+                    .filter_map(|id| self.accounts.get(id))
+                    .map(|account| account.id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Notify subscribers that a symbol's quote has changed
+    ///
+    /// Each subscribed account evaluates only its open orders for the symbol.
+    /// Every subscriber is processed even if one fails; the first error is
+    /// returned. Returns the number of accounts processed.
+    pub fn publish<M: MarketDataProvider>(&mut self, symbol: &Symbol, market_data: &M) -> Result<usize> {
+        let subscribers = match self.subscriptions.get(&symbol.0) {
+            Some(subscribers) => subscribers.clone(),
+            None => return Ok(0),
+        };
+
+        trace!("AccountManager::publish() - Publishing {} to {} subscribers", symbol, subscribers.len());
+        let mut processed = 0;
+        let mut first_error = None;
+        for id in subscribers {
+            let account = match self.accounts.get_mut(&id) {
+                Some(account) => account,
+                None => continue,
+            };
+            processed += 1;
+            if let Err(e) = account.process_open_orders_for_symbol(symbol, market_data) {
+                warn!("AccountManager::publish() - Account {} failed to process {}: {}", id, symbol, e);
+                first_error.get_or_insert(e);
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(processed),
+        }
+    }
+
+    /// Apply a quote or trade update to a market data provider and notify subscribers
+    pub fn publish_tick(&mut self, market_data: &mut SimpleMarketDataProvider, event: &TickEvent) -> Result<usize> {
+        market_data.apply_tick(event);
+        self.publish(&event.quote.symbol, market_data)
+    }
+
+    /// Find orders matching a query across all accounts
+    pub fn query_orders(&self, query: &OrderQuery) -> Page<(AccountId, &Order)> {
+        query.select_orders(
+            self.accounts
+                .values()
+                .flat_map(|account| account.all_orders().map(move |order| (account.id, order))),
+        )
+    }
+
+    /// Find trades matching a query across all accounts
+    pub fn query_trades(&self, query: &OrderQuery) -> Page<(AccountId, &Trade)> {
+        query.select_trades(
+            self.accounts
+                .values()
+                .flat_map(|account| account.all_orders().map(move |order| (account.id, order))),
+        )
     }
 
     /// Get the number of accounts
@@ -208,9 +349,15 @@ impl AccountManager {
             });
         }
         
-        // Perform transfer
-        self.accounts.get_mut(&from_id.0.to_string()).unwrap().cash_balance -= amount;
-        self.accounts.get_mut(&to_id.0.to_string()).unwrap().cash_balance += amount;
+        // Perform transfer, recording it in both ledgers
+        self.accounts
+            .get_mut(&from_id.0.to_string())
+            .unwrap()
+            .post_external_flow(CashEntryKind::TransferOut, amount)?;
+        self.accounts
+            .get_mut(&to_id.0.to_string())
+            .unwrap()
+            .post_external_flow(CashEntryKind::TransferIn, amount)?;
         
         Ok(())
     }
@@ -220,3 +367,73 @@ impl AccountManager {
         self.storage_path.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::order::OrderSide;
+    use crate::types::{Price, Quantity};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    fn setup() -> (AccountManager, AccountId, AccountId, SimpleMarketDataProvider) {
+        let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap());
+        let config = Config {
+            clock: Some(clock.shared()),
+            ..Config::default()
+        };
+        let mut manager = AccountManager::new();
+        let first = manager
+            .create_account_with_config("First", "USD", dec!(100_000), config.clone())
+            .unwrap();
+        let second = manager
+            .create_account_with_config("Second", "USD", dec!(100_000), config)
+            .unwrap();
+        let market_data = SimpleMarketDataProvider::new().with_clock(clock.shared());
+        (manager, first, second, market_data)
+    }
+
+    fn buy(manager: &mut AccountManager, id: &AccountId, symbol: &str) {
+        let order = Order::market(Symbol::new(symbol), OrderSide::Buy, Quantity(dec!(1)));
+        manager.get_account_mut(id).unwrap().submit_order(order).unwrap();
+    }
+
+    #[test]
+    fn publish_processes_only_subscribed_accounts_for_the_symbol() {
+        let (mut manager, first, second, mut market_data) = setup();
+        let aaa = Symbol::new("AAA");
+        buy(&mut manager, &first, "AAA");
+        buy(&mut manager, &first, "BBB");
+        buy(&mut manager, &second, "AAA");
+        manager.subscribe(&first, &aaa).unwrap();
+
+        market_data.set_price(aaa.clone(), Price(dec!(100)));
+        market_data.set_price(Symbol::new("BBB"), Price(dec!(50)));
+        assert_eq!(manager.publish(&aaa, &market_data).unwrap(), 1);
+        assert_eq!(manager.publish(&Symbol::new("BBB"), &market_data).unwrap(), 0);
+
+        let first_account = manager.get_account(&first).unwrap();
+        assert!(first_account.open_orders_for_symbol(&aaa).is_empty());
+        assert_eq!(first_account.open_orders_for_symbol(&Symbol::new("BBB")).len(), 1);
+        assert_eq!(manager.get_account(&second).unwrap().open_orders_for_symbol(&aaa).len(), 1);
+    }
+
+    #[test]
+    fn subscriptions_follow_accounts() {
+        let (mut manager, first, second, _) = setup();
+        let aaa = Symbol::new("AAA");
+
+        assert!(manager.subscribe(&AccountId::new(), &aaa).is_err());
+        manager.subscribe(&first, &aaa).unwrap();
+        manager.subscribe(&second, &aaa).unwrap();
+        assert_eq!(manager.subscribers(&aaa).len(), 2);
+
+        assert!(manager.unsubscribe(&second, &aaa));
+        assert!(!manager.unsubscribe(&second, &aaa));
+        assert_eq!(manager.subscribers(&aaa), vec![first]);
+
+        manager.remove_account(&first);
+        assert!(manager.subscribers(&aaa).is_empty());
+    }
+}
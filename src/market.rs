@@ -1,8 +1,16 @@
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use crate::types::{Symbol, Price};
+use crate::clock::{self, SharedClock};
+use crate::config::Config;
+use crate::types::{Symbol, Price, Quantity};
+use crate::order::{Auction, OrderSide};
+use crate::book::BookFill;
+use crate::halt::{MarketControls, PriceBands, TradingStatus};
 use crate::error::{Result, Error};
+use crate::bars::{self, BarInterval, SessionBoundary};
+use crate::loader::BarLoader;
+use crate::tick::{TickEvent, TickKind};
 
 /// Represents a market quote for a symbol
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -27,14 +35,98 @@ impl Quote {
             bid,
             ask,
             last,
-            timestamp: Utc::now(),
+            timestamp: clock::now(),
         }
     }
     
+    /// Set the timestamp of the quote
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+    
     /// Get the mid price
     pub fn mid(&self) -> Price {
         Price((self.bid.0 + self.ask.0) / rust_decimal::Decimal::from(2))
     }
+
+    /// Check that the quote is usable for execution at `as_of`
+    ///
+    /// Zero or negative bid and ask prices are always invalid. Staleness, crossed and locked
+    /// quotes are checked according to the configuration.
+    pub fn validate(&self, as_of: DateTime<Utc>, config: &Config) -> Result<()> {
+        let invalid = |problem: String| Error::MarketDataError {
+            reason: format!("Invalid quote for {}: {}", self.symbol, problem),
+        };
+
+        for (name, price) in [("bid", self.bid), ("ask", self.ask)] {
+            if price.0 <= rust_decimal::Decimal::ZERO {
+                return Err(invalid(format!("{} price {} is not positive", name, price)));
+            }
+        }
+
+        if let Some(max_age) = config.max_quote_age {
+            let age = as_of - self.timestamp;
+            if age > max_age {
+                return Err(invalid(format!(
+                    "quote from {} is {}s old (maximum {}s)",
+                    self.timestamp,
+                    age.num_seconds(),
+                    max_age.num_seconds()
+                )));
+            }
+        }
+
+        if config.reject_crossed_quotes && self.bid.0 > self.ask.0 {
+            return Err(invalid(format!("bid {} is above ask {}", self.bid, self.ask)));
+        }
+
+        if config.reject_locked_quotes && self.bid.0 == self.ask.0 {
+            return Err(invalid(format!("bid {} equals ask {}", self.bid, self.ask)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Price set by a session's opening or closing auction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuctionPrint {
+    /// Symbol of the asset
+    pub symbol: Symbol,
+    /// Auction the price was set in
+    pub auction: Auction,
+    /// Auction price
+    pub price: Price,
+    /// Time of the auction
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AuctionPrint {
+    /// Create a new auction print
+    pub fn new(symbol: Symbol, auction: Auction, price: Price) -> Self {
+        Self {
+            symbol,
+            auction,
+            price,
+            timestamp: clock::now(),
+        }
+    }
+
+    /// Set the timestamp of the auction print
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+}
+
+/// Prices traded through over a period (e.g., a bar), in the order they occurred
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricePath {
+    /// Time at which the period began
+    pub timestamp: DateTime<Utc>,
+    /// Prices in traded order
+    pub prices: Vec<Price>,
 }
 
 /// Trait for market data providers
@@ -44,12 +136,125 @@ pub trait MarketDataProvider {
     
     /// Check if a symbol is supported
     fn is_symbol_supported(&self, symbol: &Symbol) -> bool;
+    
+    /// Get the prices traded through since the previous update, if known
+    ///
+    /// Bar-driven providers return the intrabar path so resting orders can fill
+    /// against the bar's high and low. Quote-only providers return `None`.
+    fn price_path(&self, _symbol: &Symbol) -> Option<PricePath> {
+        None
+    }
+
+    /// Walk the order book for an order without consuming liquidity
+    ///
+    /// Providers with depth return the fills at successive prices, stopping at the
+    /// limit price if one is given. Quote-only providers return `None`, and orders
+    /// fill against the quote instead.
+    fn book_fills(
+        &self,
+        _symbol: &Symbol,
+        _side: OrderSide,
+        _quantity: Quantity,
+        _limit: Option<Price>,
+    ) -> Option<Vec<BookFill>> {
+        None
+    }
+
+    /// Remove liquidity taken by executed fills from the order book
+    fn consume_liquidity(&self, _symbol: &Symbol, _side: OrderSide, _fills: &[BookFill]) {}
+
+    /// Get the most recent opening or closing auction price, if known
+    ///
+    /// Auction orders submitted before the print's timestamp execute at its price.
+    fn auction_print(&self, _symbol: &Symbol, _auction: Auction) -> Option<AuctionPrint> {
+        None
+    }
+
+    /// Get whether a symbol is trading or halted
+    ///
+    /// Orders for halted symbols are not filled until trading resumes.
+    fn trading_status(&self, _symbol: &Symbol) -> TradingStatus {
+        TradingStatus::Trading
+    }
+
+    /// Get the limit-up/limit-down bands of a symbol, if any
+    ///
+    /// Fills outside the bands wait for the price to return, and limit orders
+    /// priced outside the bands are rejected by `Account::submit_order_to`.
+    fn price_bands(&self, _symbol: &Symbol) -> Option<PriceBands> {
+        None
+    }
 }
 
+macro_rules! forward_market_data_provider {
+    ($($wrapper:ty),*) => {$(
+        impl<T: MarketDataProvider + ?Sized> MarketDataProvider for $wrapper {
+            fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+                (**self).get_quote(symbol)
+            }
+
+            fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+                (**self).is_symbol_supported(symbol)
+            }
+
+            fn price_path(&self, symbol: &Symbol) -> Option<PricePath> {
+                (**self).price_path(symbol)
+            }
+
+            fn book_fills(
+                &self,
+                symbol: &Symbol,
+                side: OrderSide,
+                quantity: Quantity,
+                limit: Option<Price>,
+            ) -> Option<Vec<BookFill>> {
+                (**self).book_fills(symbol, side, quantity, limit)
+            }
+
+            fn consume_liquidity(&self, symbol: &Symbol, side: OrderSide, fills: &[BookFill]) {
+                (**self).consume_liquidity(symbol, side, fills)
+            }
+
+            fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+                (**self).auction_print(symbol, auction)
+            }
+
+            fn trading_status(&self, symbol: &Symbol) -> TradingStatus {
+                (**self).trading_status(symbol)
+            }
+
+            fn price_bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+                (**self).price_bands(symbol)
+            }
+        }
+    )*};
+}
+
+// Providers behind references and smart pointers can be used and composed directly
+forward_market_data_provider!(&T, Box<T>, std::sync::Arc<T>);
+
 /// Simple in-memory market data provider for paper trading
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimpleMarketDataProvider {
     quotes: HashMap<String, Quote>,
+    /// Latest opening auction prints by symbol
+    #[serde(default)]
+    opening_prints: HashMap<String, AuctionPrint>,
+    /// Latest closing auction prints by symbol
+    #[serde(default)]
+    closing_prints: HashMap<String, AuctionPrint>,
+    /// Halts and price bands
+    #[serde(default)]
+    controls: MarketControls,
+    /// Clock used to timestamp quotes created by `set_price`
+    #[serde(skip)]
+    clock: Option<SharedClock>,
+}
+
+impl Default for SimpleMarketDataProvider {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SimpleMarketDataProvider {
@@ -57,6 +262,24 @@ impl SimpleMarketDataProvider {
     pub fn new() -> Self {
         Self {
             quotes: HashMap::new(),
+            opening_prints: HashMap::new(),
+            closing_prints: HashMap::new(),
+            controls: MarketControls::new(),
+            clock: None,
+        }
+    }
+    
+    /// Use a specific clock to timestamp quotes
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+    
+    /// Get the current time from the provider's clock, or the global clock if none is set
+    fn now(&self) -> DateTime<Utc> {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => clock::now(),
         }
     }
     
@@ -65,6 +288,62 @@ impl SimpleMarketDataProvider {
         self.quotes.insert(quote.symbol.0.clone(), quote);
     }
     
+    /// Apply a tick event to the stored quote for its symbol
+    ///
+    /// Quote updates replace the bid and ask and keep the last traded price;
+    /// trade prints replace the last price and keep the bid and ask.
+    pub fn apply_tick(&mut self, event: &TickEvent) {
+        let update = &event.quote;
+        let quote = match (event.kind, self.quotes.get(&update.symbol.0)) {
+            (TickKind::Quote, Some(existing)) => Quote {
+                last: existing.last,
+                ..update.clone()
+            },
+            (TickKind::Trade, Some(existing)) => Quote {
+                last: update.last,
+                timestamp: update.timestamp,
+                ..existing.clone()
+            },
+            (_, None) => update.clone(),
+        };
+        self.quotes.insert(quote.symbol.0.clone(), quote);
+    }
+    
+    /// Halt trading in a symbol
+    pub fn halt<S: Into<String>>(&mut self, symbol: &Symbol, reason: S) {
+        self.controls.halt(symbol, reason);
+    }
+
+    /// Resume trading in a symbol, returning `true` if it was halted
+    pub fn resume(&mut self, symbol: &Symbol) -> bool {
+        self.controls.resume(symbol)
+    }
+
+    /// Set the limit-up/limit-down bands of a symbol
+    pub fn set_price_bands(&mut self, symbol: &Symbol, bands: PriceBands) {
+        self.controls.set_bands(symbol, bands);
+    }
+
+    /// Remove the price bands of a symbol
+    pub fn clear_price_bands(&mut self, symbol: &Symbol) -> Option<PriceBands> {
+        self.controls.clear_bands(symbol)
+    }
+
+    /// Record an opening or closing auction print
+    pub fn set_auction_print(&mut self, print: AuctionPrint) {
+        let prints = match print.auction {
+            Auction::Open => &mut self.opening_prints,
+            Auction::Close => &mut self.closing_prints,
+        };
+        prints.insert(print.symbol.0.clone(), print);
+    }
+
+    /// Record an auction price for a symbol, timestamped with the provider's clock
+    pub fn set_auction_price(&mut self, symbol: Symbol, auction: Auction, price: Price) {
+        let print = AuctionPrint::new(symbol, auction, price).with_timestamp(self.now());
+        self.set_auction_print(print);
+    }
+
     /// Set a price for a symbol (creates a quote with bid/ask spread based on configuration)
     pub fn set_price(&mut self, symbol: Symbol, price: Price) {
         // Get spread from configuration (use global config as default)
@@ -75,7 +354,7 @@ impl SimpleMarketDataProvider {
         let bid = Price(price.0 - half_spread.0);
         let ask = Price(price.0 + half_spread.0);
         
-        let quote = Quote::new(symbol.clone(), bid, ask, price);
+        let quote = Quote::new(symbol.clone(), bid, ask, price).with_timestamp(self.now());
         self.quotes.insert(symbol.0, quote);
     }
     
@@ -88,7 +367,7 @@ impl SimpleMarketDataProvider {
         let bid = Price(price.0 - half_spread.0);
         let ask = Price(price.0 + half_spread.0);
         
-        let quote = Quote::new(symbol.clone(), bid, ask, price);
+        let quote = Quote::new(symbol.clone(), bid, ask, price).with_timestamp(self.now());
         self.quotes.insert(symbol.0, quote);
     }
 }
@@ -106,6 +385,22 @@ impl MarketDataProvider for SimpleMarketDataProvider {
     fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
         self.quotes.contains_key(&symbol.0)
     }
+
+    fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+        let prints = match auction {
+            Auction::Open => &self.opening_prints,
+            Auction::Close => &self.closing_prints,
+        };
+        prints.get(&symbol.0).cloned()
+    }
+
+    fn trading_status(&self, symbol: &Symbol) -> TradingStatus {
+        self.controls.status(symbol)
+    }
+
+    fn price_bands(&self, symbol: &Symbol) -> Option<PriceBands> {
+        self.controls.bands(symbol)
+    }
 }
 
 /// Historical market data point
@@ -129,13 +424,15 @@ pub struct HistoricalDataPoint {
 
 /// Trait for historical data providers
 pub trait HistoricalDataProvider {
-    /// Get historical data for a symbol
+    /// Get historical data for a symbol, aggregated to the given interval
+    ///
+    /// Returns `Error::MarketDataError` if the interval is not supported.
     fn get_historical_data(
         &self,
         symbol: &Symbol,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
-        interval: &str,
+        interval: BarInterval,
     ) -> Result<Vec<HistoricalDataPoint>>;
 }
 
@@ -143,6 +440,15 @@ pub trait HistoricalDataProvider {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimpleHistoricalDataProvider {
     data: HashMap<String, Vec<HistoricalDataPoint>>,
+    /// Session boundary used to build daily bars
+    #[serde(skip)]
+    session: SessionBoundary,
+}
+
+impl Default for SimpleHistoricalDataProvider {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SimpleHistoricalDataProvider {
@@ -150,13 +456,47 @@ impl SimpleHistoricalDataProvider {
     pub fn new() -> Self {
         Self {
             data: HashMap::new(),
+            session: SessionBoundary::default(),
         }
     }
     
+    /// Set the session boundary used to build daily bars (UTC midnight by default)
+    pub fn with_session_boundary(mut self, session: SessionBoundary) -> Self {
+        self.session = session;
+        self
+    }
+    
     /// Add historical data for a symbol
     pub fn add_data(&mut self, symbol: &Symbol, data: Vec<HistoricalDataPoint>) {
         self.data.insert(symbol.0.clone(), data);
     }
+    
+    /// Append bars from a stream (e.g., a `BarLoader` reader) for a symbol
+    ///
+    /// Bars are kept sorted by timestamp. If any bar fails to load, none are
+    /// added. Returns the number of bars loaded.
+    pub fn load_bars<I>(&mut self, symbol: &Symbol, bars: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = Result<HistoricalDataPoint>>,
+    {
+        let loaded = bars.into_iter().collect::<Result<Vec<_>>>()?;
+        let count = loaded.len();
+        let data = self.data.entry(symbol.0.clone()).or_default();
+        data.extend(loaded);
+        data.sort_by_key(|bar| bar.timestamp);
+        Ok(count)
+    }
+    
+    /// Load every symbol from a directory-per-symbol layout (`root/<SYMBOL>/*.csv`)
+    pub fn load_directory<P: AsRef<std::path::Path>>(&mut self, root: P, loader: &BarLoader) -> Result<usize> {
+        let root = root.as_ref();
+        let mut total = 0;
+        for symbol in loader.symbols(root)? {
+            let bars = loader.symbol_directory(root, &symbol)?;
+            total += self.load_bars(&symbol, bars)?;
+        }
+        Ok(total)
+    }
 }
 
 impl HistoricalDataProvider for SimpleHistoricalDataProvider {
@@ -165,8 +505,10 @@ impl HistoricalDataProvider for SimpleHistoricalDataProvider {
         symbol: &Symbol,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
-        _interval: &str,
+        interval: BarInterval,
     ) -> Result<Vec<HistoricalDataPoint>> {
+        interval.validate()?;
+        
         let data = self.data.get(&symbol.0).ok_or_else(|| Error::SymbolNotFound {
             symbol: symbol.clone(),
         })?;
@@ -177,6 +519,63 @@ impl HistoricalDataProvider for SimpleHistoricalDataProvider {
             .cloned()
             .collect();
         
-        Ok(filtered_data)
+        bars::resample(&filtered_data, interval, &self.session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn quote(bid: Decimal, ask: Decimal) -> Quote {
+        let timestamp = Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap();
+        Quote::new(Symbol::new("AAA"), Price(bid), Price(ask), Price(bid)).with_timestamp(timestamp)
+    }
+
+    #[test]
+    fn quotes_older_than_the_maximum_age_are_stale() {
+        let quote = quote(dec!(99), dec!(101));
+        let config = Config {
+            max_quote_age: Some(Duration::seconds(30)),
+            ..Config::default()
+        };
+
+        assert!(quote.validate(quote.timestamp + Duration::seconds(30), &config).is_ok());
+        assert!(quote.validate(quote.timestamp + Duration::seconds(31), &config).is_err());
+        assert!(quote.validate(quote.timestamp + Duration::days(1), &Config::default()).is_ok());
+    }
+
+    #[test]
+    fn crossed_and_locked_quotes_are_checked_as_configured() {
+        let crossed = quote(dec!(101), dec!(99));
+        let locked = quote(dec!(100), dec!(100));
+        let now = crossed.timestamp;
+
+        let config = Config::default();
+        assert!(crossed.validate(now, &config).is_err());
+        assert!(locked.validate(now, &config).is_ok());
+
+        let config = Config {
+            reject_crossed_quotes: false,
+            reject_locked_quotes: true,
+            ..Config::default()
+        };
+        assert!(crossed.validate(now, &config).is_ok());
+        assert!(locked.validate(now, &config).is_err());
+    }
+
+    #[test]
+    fn non_positive_prices_are_always_invalid() {
+        let config = Config {
+            reject_crossed_quotes: false,
+            ..Config::default()
+        };
+        let now = quote(dec!(1), dec!(1)).timestamp;
+
+        assert!(quote(dec!(0), dec!(101)).validate(now, &config).is_err());
+        assert!(quote(dec!(99), dec!(-1)).validate(now, &config).is_err());
     }
 }
@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use crate::calendar::TradingCalendar;
+use crate::clock;
 use crate::types::{Symbol, Quantity, Price, OrderId, TradeId};
 
 /// Represents the side of an order (buy or sell)
@@ -20,6 +23,34 @@ pub enum OrderType {
     Stop,
     /// Stop-limit order (becomes a limit order when the stop price is reached)
     StopLimit,
+    /// Market-on-open order (executed at the opening auction price)
+    MarketOnOpen,
+    /// Market-on-close order (executed at the closing auction price)
+    MarketOnClose,
+    /// Limit-on-open order (executed at the opening auction price if it is within the limit)
+    LimitOnOpen,
+    /// Limit-on-close order (executed at the closing auction price if it is within the limit)
+    LimitOnClose,
+}
+
+impl OrderType {
+    /// Get the auction an order type executes in, if any
+    pub fn auction(&self) -> Option<Auction> {
+        match self {
+            OrderType::MarketOnOpen | OrderType::LimitOnOpen => Some(Auction::Open),
+            OrderType::MarketOnClose | OrderType::LimitOnClose => Some(Auction::Close),
+            _ => None,
+        }
+    }
+}
+
+/// Represents a session auction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Auction {
+    /// Opening auction
+    Open,
+    /// Closing auction
+    Close,
 }
 
 /// Represents the status of an order
@@ -41,6 +72,18 @@ pub enum OrderStatus {
     Expired,
 }
 
+/// Represents how long an order remains active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TimeInForce {
+    /// Order remains active until filled or canceled
+    #[default]
+    GoodTillCanceled,
+    /// Order expires at the end of the day it was created
+    Day,
+    /// Order expires at the given time
+    GoodTillDate(DateTime<Utc>),
+}
+
 /// Represents a trade execution
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Trade {
@@ -60,6 +103,15 @@ pub struct Trade {
     pub commission: rust_decimal::Decimal,
     /// Timestamp of the trade
     pub timestamp: DateTime<Utc>,
+    /// Client order ID of the order
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+    /// Strategy tag of the order
+    #[serde(default)]
+    pub strategy: Option<String>,
+    /// Metadata of the order
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl Trade {
@@ -80,9 +132,26 @@ impl Trade {
             quantity,
             price,
             commission,
-            timestamp: Utc::now(),
+            timestamp: clock::now(),
+            client_order_id: None,
+            strategy: None,
+            metadata: BTreeMap::new(),
         }
     }
+
+    /// Copy the client order ID, strategy tag and metadata from the order
+    pub fn with_order_tags(mut self, order: &Order) -> Self {
+        self.client_order_id = order.client_order_id.clone();
+        self.strategy = order.strategy.clone();
+        self.metadata = order.metadata.clone();
+        self
+    }
+
+    /// Set the timestamp of the trade
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
     
     /// Calculate the value of the trade
     pub fn value(&self) -> Price {
@@ -111,6 +180,27 @@ pub struct Order {
     pub stop_price: Option<Price>,
     /// Status of the order
     pub status: OrderStatus,
+    /// Time in force of the order
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    /// Timestamp when the stop price was reached (for stop-limit orders)
+    #[serde(default)]
+    pub triggered_at: Option<DateTime<Utc>>,
+    /// Reason the order was rejected, if it was
+    #[serde(default)]
+    pub reject_reason: Option<String>,
+    /// Whether the order may execute in pre- and post-market sessions
+    #[serde(default)]
+    pub extended_hours: bool,
+    /// Strategy or tag the order belongs to, used to attribute P&L
+    #[serde(default)]
+    pub strategy: Option<String>,
+    /// Caller-assigned order ID, unique within an account
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+    /// Free-form key/value metadata
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
     /// Timestamp when the order was created
     pub created_at: DateTime<Utc>,
     /// Timestamp when the order was last updated
@@ -122,7 +212,7 @@ pub struct Order {
 impl Order {
     /// Create a new market order
     pub fn market(symbol: Symbol, side: OrderSide, quantity: Quantity) -> Self {
-        let now = Utc::now();
+        let now = clock::now();
         Self {
             id: OrderId::new(),
             symbol,
@@ -133,6 +223,13 @@ impl Order {
             limit_price: None,
             stop_price: None,
             status: OrderStatus::Created,
+            time_in_force: TimeInForce::GoodTillCanceled,
+            triggered_at: None,
+            reject_reason: None,
+            extended_hours: false,
+            strategy: None,
+            client_order_id: None,
+            metadata: BTreeMap::new(),
             created_at: now,
             updated_at: now,
             trades: Vec::new(),
@@ -141,7 +238,7 @@ impl Order {
     
     /// Create a new limit order
     pub fn limit(symbol: Symbol, side: OrderSide, quantity: Quantity, price: Price) -> Self {
-        let now = Utc::now();
+        let now = clock::now();
         Self {
             id: OrderId::new(),
             symbol,
@@ -152,6 +249,13 @@ impl Order {
             limit_price: Some(price),
             stop_price: None,
             status: OrderStatus::Created,
+            time_in_force: TimeInForce::GoodTillCanceled,
+            triggered_at: None,
+            reject_reason: None,
+            extended_hours: false,
+            strategy: None,
+            client_order_id: None,
+            metadata: BTreeMap::new(),
             created_at: now,
             updated_at: now,
             trades: Vec::new(),
@@ -160,7 +264,7 @@ impl Order {
     
     /// Create a new stop order
     pub fn stop(symbol: Symbol, side: OrderSide, quantity: Quantity, stop_price: Price) -> Self {
-        let now = Utc::now();
+        let now = clock::now();
         Self {
             id: OrderId::new(),
             symbol,
@@ -171,6 +275,13 @@ impl Order {
             limit_price: None,
             stop_price: Some(stop_price),
             status: OrderStatus::Created,
+            time_in_force: TimeInForce::GoodTillCanceled,
+            triggered_at: None,
+            reject_reason: None,
+            extended_hours: false,
+            strategy: None,
+            client_order_id: None,
+            metadata: BTreeMap::new(),
             created_at: now,
             updated_at: now,
             trades: Vec::new(),
@@ -185,7 +296,7 @@ impl Order {
         stop_price: Price,
         limit_price: Price,
     ) -> Self {
-        let now = Utc::now();
+        let now = clock::now();
         Self {
             id: OrderId::new(),
             symbol,
@@ -196,12 +307,122 @@ impl Order {
             limit_price: Some(limit_price),
             stop_price: Some(stop_price),
             status: OrderStatus::Created,
+            time_in_force: TimeInForce::GoodTillCanceled,
+            triggered_at: None,
+            reject_reason: None,
+            extended_hours: false,
+            strategy: None,
+            client_order_id: None,
+            metadata: BTreeMap::new(),
             created_at: now,
             updated_at: now,
             trades: Vec::new(),
         }
     }
     
+    /// Create a new market-on-open order
+    pub fn market_on_open(symbol: Symbol, side: OrderSide, quantity: Quantity) -> Self {
+        Self::auction(symbol, side, quantity, OrderType::MarketOnOpen, None)
+    }
+
+    /// Create a new market-on-close order
+    pub fn market_on_close(symbol: Symbol, side: OrderSide, quantity: Quantity) -> Self {
+        Self::auction(symbol, side, quantity, OrderType::MarketOnClose, None)
+    }
+
+    /// Create a new limit-on-open order
+    pub fn limit_on_open(symbol: Symbol, side: OrderSide, quantity: Quantity, price: Price) -> Self {
+        Self::auction(symbol, side, quantity, OrderType::LimitOnOpen, Some(price))
+    }
+
+    /// Create a new limit-on-close order
+    pub fn limit_on_close(symbol: Symbol, side: OrderSide, quantity: Quantity, price: Price) -> Self {
+        Self::auction(symbol, side, quantity, OrderType::LimitOnClose, Some(price))
+    }
+
+    /// Create an order that executes in a session auction
+    fn auction(
+        symbol: Symbol,
+        side: OrderSide,
+        quantity: Quantity,
+        order_type: OrderType,
+        limit_price: Option<Price>,
+    ) -> Self {
+        Self {
+            order_type,
+            limit_price,
+            ..Self::market(symbol, side, quantity)
+        }
+    }
+
+    /// Allow the order to execute in pre- and post-market sessions
+    pub fn with_extended_hours(mut self, extended_hours: bool) -> Self {
+        self.extended_hours = extended_hours;
+        self
+    }
+
+    /// Tag the order with a strategy for P&L attribution
+    pub fn with_strategy<S: Into<String>>(mut self, strategy: S) -> Self {
+        self.strategy = Some(strategy.into());
+        self
+    }
+
+    /// Set the caller-assigned order ID (must be unique within the account)
+    pub fn with_client_order_id<S: Into<String>>(mut self, client_order_id: S) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    /// Add a metadata entry, replacing any with the same key
+    pub fn with_metadata<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the time in force of the order
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+    
+    /// Get the time at which the order expires, if any
+    ///
+    /// DAY orders expire at midnight UTC following their creation; see `expires_in`
+    /// for expiry at a trading calendar's session close.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        match self.time_in_force {
+            TimeInForce::GoodTillCanceled => None,
+            TimeInForce::Day => self
+                .created_at
+                .date_naive()
+                .succ_opt()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc()),
+            TimeInForce::GoodTillDate(expiry) => Some(expiry),
+        }
+    }
+    
+    /// Get the time the order expires on a trading calendar
+    ///
+    /// DAY orders expire at the close of their session (the post-market close for
+    /// extended-hours orders). Without a calendar this is the same as `expires_at`.
+    pub fn expires_in(&self, calendar: Option<&TradingCalendar>) -> Option<DateTime<Utc>> {
+        match (self.time_in_force, calendar) {
+            (TimeInForce::Day, Some(calendar)) => calendar.day_order_expiry(self.created_at, self.extended_hours),
+            _ => self.expires_at(),
+        }
+    }
+
+    /// Check if the order should be expired at the given time
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.is_expired_in(now, None)
+    }
+
+    /// Check if the order should be expired at the given time on a trading calendar
+    pub fn is_expired_in(&self, now: DateTime<Utc>, calendar: Option<&TradingCalendar>) -> bool {
+        self.is_active() && self.expires_in(calendar).is_some_and(|expiry| now >= expiry)
+    }
+    
     /// Check if the order is active
     pub fn is_active(&self) -> bool {
         matches!(
@@ -235,8 +456,24 @@ impl Order {
         Quantity(self.quantity.0 - self.filled_quantity.0)
     }
     
+    /// Get the volume-weighted average price of the order's fills
+    pub fn average_fill_price(&self) -> Option<Price> {
+        if self.filled_quantity.0 <= rust_decimal::Decimal::ZERO {
+            return None;
+        }
+
+        let notional: rust_decimal::Decimal = self
+            .trades
+            .iter()
+            .map(|trade| trade.price.0 * trade.quantity.0)
+            .sum();
+        Some(Price(notional / self.filled_quantity.0))
+    }
+
     /// Add a trade to the order
     pub fn add_trade(&mut self, trade: Trade) {
+        let timestamp = trade.timestamp;
+        
         // Update filled quantity
         self.filled_quantity = Quantity(self.filled_quantity.0 + trade.quantity.0);
         
@@ -251,7 +488,7 @@ impl Order {
         self.trades.push(trade);
         
         // Update timestamp
-        self.updated_at = Utc::now();
+        self.updated_at = timestamp;
     }
     
     /// Execute the order with a trade
@@ -266,9 +503,14 @@ impl Order {
     
     /// Cancel the order
     pub fn cancel(&mut self) -> bool {
+        self.cancel_at(clock::now())
+    }
+    
+    /// Cancel the order at a specific time
+    pub fn cancel_at(&mut self, now: DateTime<Utc>) -> bool {
         if self.is_active() {
             self.status = OrderStatus::Canceled;
-            self.updated_at = Utc::now();
+            self.updated_at = now;
             true
         } else {
             false
@@ -276,18 +518,48 @@ impl Order {
     }
     
     /// Reject the order
-    pub fn reject(&mut self, _reason: &str) {
+    pub fn reject(&mut self, reason: &str) {
+        self.reject_at(reason, clock::now());
+    }
+    
+    /// Reject the order at a specific time
+    pub fn reject_at(&mut self, reason: &str, now: DateTime<Utc>) {
         if self.status == OrderStatus::Created || self.status == OrderStatus::Submitted {
             self.status = OrderStatus::Rejected;
-            self.updated_at = Utc::now();
+            self.reject_reason = Some(reason.to_string());
+            self.updated_at = now;
+        }
+    }
+    
+    /// Expire the order at a specific time
+    pub fn expire_at(&mut self, now: DateTime<Utc>) -> bool {
+        if self.is_active() {
+            self.status = OrderStatus::Expired;
+            self.updated_at = now;
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// Mark the stop price as reached at a specific time
+    pub fn trigger_at(&mut self, now: DateTime<Utc>) {
+        if self.triggered_at.is_none() {
+            self.triggered_at = Some(now);
+            self.updated_at = now;
         }
     }
     
     /// Submit the order
     pub fn submit(&mut self) {
+        self.submit_at(clock::now());
+    }
+    
+    /// Submit the order at a specific time
+    pub fn submit_at(&mut self, now: DateTime<Utc>) {
         if self.status == OrderStatus::Created {
             self.status = OrderStatus::Submitted;
-            self.updated_at = Utc::now();
+            self.updated_at = now;
         }
     }
 }
@@ -0,0 +1,345 @@
+use std::cmp::Ordering;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::order::{Order, OrderSide, OrderStatus, OrderType, Trade};
+use crate::types::Symbol;
+
+/// Direction results are sorted in, by time and then ID
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortDirection {
+    /// Oldest first
+    #[default]
+    Ascending,
+    /// Newest first
+    Descending,
+}
+
+/// Position in a sorted result set, used to fetch the next page
+///
+/// Orders are keyed by creation time and trades by execution time, with the
+/// ID breaking ties, so a cursor stays valid as new orders and trades arrive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryCursor {
+    /// Time of the last item on the page
+    pub timestamp: DateTime<Utc>,
+    /// ID of the last item on the page
+    pub id: Uuid,
+}
+
+impl QueryCursor {
+    fn cmp_key(&self, timestamp: DateTime<Utc>, id: Uuid) -> Ordering {
+        (timestamp, id).cmp(&(self.timestamp, self.id))
+    }
+}
+
+/// One page of query results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// Matching items on this page
+    pub items: Vec<T>,
+    /// Cursor for the next page, if more items match
+    pub next_cursor: Option<QueryCursor>,
+}
+
+/// Filters, sorting and pagination for orders and trades
+///
+/// Order filters apply to trades through the order they belong to, so a trade
+/// query for `Filled` orders returns the trades of filled orders. The time range
+/// is matched against an order's creation time and a trade's execution time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderQuery {
+    symbol: Option<Symbol>,
+    side: Option<OrderSide>,
+    statuses: Vec<OrderStatus>,
+    order_types: Vec<OrderType>,
+    strategy: Option<String>,
+    client_order_id: Option<String>,
+    metadata: Vec<(String, String)>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    direction: SortDirection,
+    limit: Option<usize>,
+    after: Option<QueryCursor>,
+}
+
+impl OrderQuery {
+    /// Create a query matching every order and trade
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match a symbol
+    pub fn with_symbol(mut self, symbol: Symbol) -> Self {
+        self.symbol = Some(symbol);
+        self
+    }
+
+    /// Only match a side
+    pub fn with_side(mut self, side: OrderSide) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    /// Match an order status (repeat to match any of several)
+    pub fn with_status(mut self, status: OrderStatus) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// Match an order type (repeat to match any of several)
+    pub fn with_order_type(mut self, order_type: OrderType) -> Self {
+        self.order_types.push(order_type);
+        self
+    }
+
+    /// Only match a strategy tag
+    pub fn with_strategy<S: Into<String>>(mut self, strategy: S) -> Self {
+        self.strategy = Some(strategy.into());
+        self
+    }
+
+    /// Only match a client order ID
+    pub fn with_client_order_id<S: Into<String>>(mut self, client_order_id: S) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    /// Only match a metadata entry (repeat to require several)
+    pub fn with_metadata<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// Only match items at or after a time
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only match items before a time
+    pub fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Set the sort direction
+    pub fn with_direction(mut self, direction: SortDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set the maximum number of items per page (at least one)
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit.max(1));
+        self
+    }
+
+    /// Start after a cursor returned with a previous page
+    pub fn with_cursor(mut self, cursor: QueryCursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    /// Check if an order passes every filter except the time range
+    fn matches_order(&self, order: &Order) -> bool {
+        self.symbol.as_ref().is_none_or(|symbol| order.symbol == *symbol)
+            && self.side.is_none_or(|side| order.side == side)
+            && (self.statuses.is_empty() || self.statuses.contains(&order.status))
+            && (self.order_types.is_empty() || self.order_types.contains(&order.order_type))
+            && self.strategy.as_ref().is_none_or(|strategy| order.strategy.as_ref() == Some(strategy))
+            && self
+                .client_order_id
+                .as_ref()
+                .is_none_or(|client_order_id| order.client_order_id.as_ref() == Some(client_order_id))
+            && self
+                .metadata
+                .iter()
+                .all(|(key, value)| order.metadata.get(key) == Some(value))
+    }
+
+    /// Check if a time is within the range
+    fn in_range(&self, timestamp: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| timestamp >= since) && self.until.is_none_or(|until| timestamp < until)
+    }
+
+    /// Select matching orders, each carrying a caller-supplied tag
+    pub(crate) fn select_orders<'a, T, I>(&self, orders: I) -> Page<(T, &'a Order)>
+    where
+        I: IntoIterator<Item = (T, &'a Order)>,
+    {
+        let matches = orders
+            .into_iter()
+            .filter(|(_, order)| self.matches_order(order) && self.in_range(order.created_at))
+            .map(|(tag, order)| (order.created_at, order.id.0, (tag, order)))
+            .collect();
+        self.paginate(matches)
+    }
+
+    /// Select the matching trades of orders, each carrying a caller-supplied tag
+    pub(crate) fn select_trades<'a, T, I>(&self, orders: I) -> Page<(T, &'a Trade)>
+    where
+        T: Clone,
+        I: IntoIterator<Item = (T, &'a Order)>,
+    {
+        let matches = orders
+            .into_iter()
+            .filter(|(_, order)| self.matches_order(order))
+            .flat_map(|(tag, order)| order.trades.iter().map(move |trade| (tag.clone(), trade)))
+            .filter(|(_, trade)| self.in_range(trade.timestamp))
+            .map(|(tag, trade)| (trade.timestamp, trade.id.0, (tag, trade)))
+            .collect();
+        self.paginate(matches)
+    }
+
+    /// Sort keyed items and cut the page that follows the cursor
+    fn paginate<T>(&self, mut items: Vec<(DateTime<Utc>, Uuid, T)>) -> Page<T> {
+        let past_cursor = match self.direction {
+            SortDirection::Ascending => Ordering::Greater,
+            SortDirection::Descending => Ordering::Less,
+        };
+        if let Some(cursor) = &self.after {
+            items.retain(|(timestamp, id, _)| cursor.cmp_key(*timestamp, *id) == past_cursor);
+        }
+        items.sort_by(|a, b| {
+            let ordering = (a.0, a.1).cmp(&(b.0, b.1));
+            match self.direction {
+                SortDirection::Ascending => ordering,
+                SortDirection::Descending => ordering.reverse(),
+            }
+        });
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|(timestamp, id, _)| QueryCursor {
+                timestamp: *timestamp,
+                id: *id,
+            })
+        } else {
+            None
+        };
+
+        Page {
+            items: items.into_iter().map(|(_, _, item)| item).collect(),
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OrderId, Price, Quantity};
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap()
+    }
+
+    fn order(id: u128, minute: i64, symbol: &str, side: OrderSide) -> Order {
+        let mut order = Order::market(Symbol::new(symbol), side, Quantity(dec!(1)));
+        order.id = OrderId(Uuid::from_u128(id));
+        order.created_at = start() + Duration::minutes(minute);
+        order
+    }
+
+    fn ids(page: &Page<((), &Order)>) -> Vec<u128> {
+        page.items.iter().map(|(_, order)| order.id.0.as_u128()).collect()
+    }
+
+    fn all_pages(query: OrderQuery, orders: &[Order]) -> Vec<Vec<u128>> {
+        let mut pages = Vec::new();
+        let mut query = query;
+        loop {
+            let page = query.select_orders(orders.iter().map(|order| ((), order)));
+            pages.push(ids(&page));
+            match page.next_cursor {
+                Some(cursor) => query = query.with_cursor(cursor),
+                None => return pages,
+            }
+        }
+    }
+
+    #[test]
+    fn filters_must_all_match() {
+        let mut orders = [
+            order(1, 0, "AAA", OrderSide::Buy).with_strategy("a").with_metadata("signal", "x"),
+            order(2, 1, "AAA", OrderSide::Sell).with_strategy("a"),
+            order(3, 2, "BBB", OrderSide::Buy).with_client_order_id("c-3"),
+            order(4, 3, "AAA", OrderSide::Buy).with_strategy("a").with_metadata("signal", "y"),
+        ];
+        orders[3].status = OrderStatus::Filled;
+        let select = |query: OrderQuery| ids(&query.select_orders(orders.iter().map(|order| ((), order))));
+
+        assert_eq!(select(OrderQuery::new()), vec![1, 2, 3, 4]);
+        assert_eq!(
+            select(OrderQuery::new().with_symbol(Symbol::new("AAA")).with_side(OrderSide::Buy)),
+            vec![1, 4]
+        );
+        assert_eq!(select(OrderQuery::new().with_strategy("a").with_metadata("signal", "x")), vec![1]);
+        assert_eq!(select(OrderQuery::new().with_client_order_id("c-3")), vec![3]);
+        assert_eq!(
+            select(OrderQuery::new().with_status(OrderStatus::Filled).with_status(OrderStatus::Created)),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(select(OrderQuery::new().with_status(OrderStatus::Filled)), vec![4]);
+        assert!(select(OrderQuery::new().with_order_type(OrderType::Limit)).is_empty());
+        assert_eq!(
+            select(
+                OrderQuery::new()
+                    .with_since(start() + Duration::minutes(1))
+                    .with_until(start() + Duration::minutes(3))
+            ),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn cursors_page_through_ties_in_either_direction() {
+        // Orders 2 and 3 share a creation time, so the ID breaks the tie
+        let orders = [
+            order(3, 1, "AAA", OrderSide::Buy),
+            order(1, 0, "AAA", OrderSide::Buy),
+            order(5, 2, "AAA", OrderSide::Buy),
+            order(2, 1, "AAA", OrderSide::Buy),
+            order(4, 1, "AAA", OrderSide::Buy),
+        ];
+
+        assert_eq!(
+            all_pages(OrderQuery::new().with_limit(2), &orders),
+            vec![vec![1, 2], vec![3, 4], vec![5]]
+        );
+        assert_eq!(
+            all_pages(OrderQuery::new().with_limit(2).with_direction(SortDirection::Descending), &orders),
+            vec![vec![5, 4], vec![3, 2], vec![1]]
+        );
+        assert_eq!(all_pages(OrderQuery::new().with_limit(0), &orders).len(), 5);
+        assert_eq!(all_pages(OrderQuery::new().with_limit(5), &orders), vec![vec![1, 2, 3, 4, 5]]);
+    }
+
+    #[test]
+    fn trades_match_through_their_order_and_their_own_time() {
+        let mut filled = order(1, 0, "AAA", OrderSide::Buy);
+        filled.status = OrderStatus::Filled;
+        for minute in [5, 10] {
+            filled.trades.push(Trade {
+                timestamp: start() + Duration::minutes(minute),
+                ..Trade::new(filled.id, Symbol::new("AAA"), OrderSide::Buy, Quantity(dec!(1)), Price(dec!(100)), dec!(0))
+            });
+        }
+        let open = order(2, 0, "AAA", OrderSide::Buy);
+        let orders = [filled, open];
+
+        let query = OrderQuery::new()
+            .with_status(OrderStatus::Filled)
+            .with_since(start() + Duration::minutes(6));
+        let page = query.select_trades(orders.iter().map(|order| ((), order)));
+
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].1.timestamp, start() + Duration::minutes(10));
+        assert_eq!(page.next_cursor, None);
+    }
+}
@@ -0,0 +1,632 @@
+//! Market data from JSON HTTP endpoints and WebSocket streams
+//!
+//! Requires the `remote` feature. The bundled [`StubServer`] serves recorded
+//! quotes over both transports, so code written against a live feed can be run
+//! and tested offline.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chrono_tz::Tz;
+use log::{debug, info, warn};
+use serde_json::Value;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::Message;
+
+use crate::clock;
+use crate::error::{Error, Result};
+use crate::loader::{parse_decimal, parse_timestamp, TimestampFormat};
+use crate::market::{MarketDataProvider, Quote};
+use crate::types::{Price, Symbol};
+
+/// How long blocking reads wait before checking for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Field names used to read quotes from JSON messages
+///
+/// Names starting with `/` are JSON pointers (e.g., `/quote/bid`), so nested
+/// schemas can be mapped without preprocessing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteFields {
+    /// Symbol field
+    pub symbol: String,
+    /// Bid price field
+    pub bid: String,
+    /// Ask price field
+    pub ask: String,
+    /// Last price field (the mid price is used if not set or missing)
+    pub last: Option<String>,
+    /// Timestamp field (the receive time is used if not set)
+    pub timestamp: Option<String>,
+    /// Format of the timestamp field
+    pub timestamp_format: TimestampFormat,
+    /// Time zone of timestamps without an offset
+    pub time_zone: Tz,
+}
+
+impl Default for QuoteFields {
+    fn default() -> Self {
+        Self {
+            symbol: "symbol".to_string(),
+            bid: "bid".to_string(),
+            ask: "ask".to_string(),
+            last: Some("last".to_string()),
+            timestamp: Some("timestamp".to_string()),
+            timestamp_format: TimestampFormat::Rfc3339,
+            time_zone: Tz::UTC,
+        }
+    }
+}
+
+impl QuoteFields {
+    /// Get a field of a JSON message as text
+    fn field<'a>(&self, value: &'a Value, name: &str) -> Option<&'a Value> {
+        if name.starts_with('/') {
+            value.pointer(name)
+        } else {
+            value.get(name)
+        }
+    }
+
+    /// Get a required field of a JSON message as text
+    fn text(&self, value: &Value, name: &str) -> Result<String> {
+        match self.field(value, name) {
+            Some(Value::String(text)) => Ok(text.clone()),
+            Some(Value::Number(number)) => Ok(number.to_string()),
+            _ => Err(Error::MarketDataError {
+                reason: format!("Missing field '{}' in JSON quote", name),
+            }),
+        }
+    }
+
+    /// Get the symbol of a JSON message
+    pub fn symbol_of(&self, value: &Value) -> Result<Symbol> {
+        self.text(value, &self.symbol).map(Symbol::new)
+    }
+
+    /// Read a quote from a JSON message
+    pub fn parse(&self, value: &Value) -> Result<Quote> {
+        let symbol = self.symbol_of(value)?;
+        let bid = Price(parse_decimal(&self.bid, &self.text(value, &self.bid)?)?);
+        let ask = Price(parse_decimal(&self.ask, &self.text(value, &self.ask)?)?);
+        let last = match &self.last {
+            Some(name) if self.field(value, name).is_some() => Price(parse_decimal(name, &self.text(value, name)?)?),
+            _ => Price((bid.0 + ask.0) / rust_decimal::Decimal::from(2)),
+        };
+        let timestamp = match &self.timestamp {
+            Some(name) => parse_timestamp(&self.text(value, name)?, &self.timestamp_format, self.time_zone)?,
+            None => clock::now(),
+        };
+
+        Ok(Quote::new(symbol, bid, ask, last).with_timestamp(timestamp))
+    }
+
+    /// Read every quote in a message holding a single object or an array of objects
+    ///
+    /// Elements that aren't quotes (e.g., heartbeats) are skipped.
+    pub fn parse_message(&self, text: &str) -> Result<Vec<Quote>> {
+        let value: Value = serde_json::from_str(text)?;
+        let quotes = match &value {
+            Value::Array(items) => items.iter().filter_map(|item| self.parse(item).ok()).collect(),
+            _ => self.parse(&value).into_iter().collect(),
+        };
+        Ok(quotes)
+    }
+}
+
+/// Market data provider that requests each quote from a JSON HTTP endpoint
+#[derive(Debug)]
+pub struct HttpQuoteProvider {
+    url_template: String,
+    fields: QuoteFields,
+    agent: ureq::Agent,
+    symbols: Option<HashSet<String>>,
+}
+
+impl HttpQuoteProvider {
+    /// Create a provider for a URL template, where `{symbol}` is replaced by the symbol
+    pub fn new<S: Into<String>>(url_template: S) -> Self {
+        Self {
+            url_template: url_template.into(),
+            fields: QuoteFields::default(),
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build(),
+            symbols: None,
+        }
+    }
+
+    /// Set the fields used to read quotes
+    pub fn with_fields(mut self, fields: QuoteFields) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.agent = ureq::AgentBuilder::new().timeout(timeout).build();
+        self
+    }
+
+    /// Limit the provider to a set of symbols (all symbols are attempted if not set)
+    pub fn with_symbols(mut self, symbols: &[Symbol]) -> Self {
+        self.symbols = Some(symbols.iter().map(|symbol| symbol.0.clone()).collect());
+        self
+    }
+
+    /// Get the URL quotes for a symbol are requested from
+    pub fn url_for(&self, symbol: &Symbol) -> String {
+        self.url_template.replace("{symbol}", &symbol.0)
+    }
+}
+
+impl MarketDataProvider for HttpQuoteProvider {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        let url = self.url_for(symbol);
+        debug!("HttpQuoteProvider::get_quote() - Requesting {}", url);
+
+        let body = match self.agent.get(&url).call() {
+            Ok(response) => response.into_string()?,
+            Err(ureq::Error::Status(404, _)) => {
+                return Err(Error::SymbolNotFound {
+                    symbol: symbol.clone(),
+                })
+            }
+            Err(e) => {
+                return Err(Error::MarketDataError {
+                    reason: format!("Request to {} failed: {}", url, e),
+                })
+            }
+        };
+
+        let value: Value = serde_json::from_str(&body)?;
+        let quote = self.fields.parse(&value)?;
+        if quote.symbol != *symbol {
+            return Err(Error::MarketDataError {
+                reason: format!("Requested {} from {} but received {}", symbol, url, quote.symbol),
+            });
+        }
+        Ok(quote)
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        match &self.symbols {
+            Some(symbols) => symbols.contains(&symbol.0),
+            None => true,
+        }
+    }
+}
+
+/// Market data provider that keeps the latest quotes received from a WebSocket stream
+///
+/// A background thread reads the stream until it closes or the provider is
+/// dropped. Each text message may hold one quote object or an array of them.
+#[derive(Debug)]
+pub struct WebSocketQuoteProvider {
+    quotes: Arc<RwLock<HashMap<String, Quote>>>,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl WebSocketQuoteProvider {
+    /// Connect to a WebSocket stream
+    ///
+    /// If `subscribe` is given it is sent as a text message once connected, for
+    /// feeds that require a subscription request.
+    pub fn connect(url: &str, fields: QuoteFields, subscribe: Option<&str>) -> Result<Self> {
+        info!("WebSocketQuoteProvider::connect() - Connecting to {}", url);
+        let (mut socket, _) = tungstenite::connect(url).map_err(|e| Error::MarketDataError {
+            reason: format!("Could not connect to {}: {}", url, e),
+        })?;
+
+        if let Some(message) = subscribe {
+            socket
+                .send(Message::Text(message.to_string()))
+                .map_err(|e| Error::MarketDataError {
+                    reason: format!("Could not subscribe to {}: {}", url, e),
+                })?;
+        }
+
+        // Time out reads so the thread notices when the provider is dropped
+        if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+            stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        }
+
+        let quotes = Arc::new(RwLock::new(HashMap::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = {
+            let quotes = Arc::clone(&quotes);
+            let running = Arc::clone(&running);
+            let url = url.to_string();
+            thread::spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    let text = match socket.read() {
+                        Ok(Message::Text(text)) => text,
+                        Ok(Message::Close(_)) => break,
+                        Ok(_) => continue,
+                        Err(tungstenite::Error::Io(e))
+                            if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                        {
+                            continue
+                        }
+                        Err(e) => {
+                            warn!("WebSocketQuoteProvider::connect() - Stream from {} ended: {}", url, e);
+                            break;
+                        }
+                    };
+
+                    match fields.parse_message(&text) {
+                        Ok(received) => {
+                            let mut quotes = quotes.write().unwrap();
+                            for quote in received {
+                                quotes.insert(quote.symbol.0.clone(), quote);
+                            }
+                        }
+                        Err(e) => warn!("WebSocketQuoteProvider::connect() - Skipping message: {}", e),
+                    }
+                }
+                let _ = socket.close(None);
+                running.store(false, Ordering::SeqCst);
+            })
+        };
+
+        Ok(Self {
+            quotes,
+            running,
+            handle: Some(handle),
+        })
+    }
+
+    /// Check if the stream is still being read
+    pub fn is_connected(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Wait until a quote for a symbol has been received
+    pub fn wait_for(&self, symbol: &Symbol, timeout: Duration) -> Result<Quote> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Ok(quote) = self.get_quote(symbol) {
+                return Ok(quote);
+            }
+            if Instant::now() >= deadline || !self.is_connected() {
+                return self.get_quote(symbol);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Stop reading the stream, keeping the quotes received so far
+    pub fn close(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for WebSocketQuoteProvider {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl MarketDataProvider for WebSocketQuoteProvider {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        self.quotes
+            .read()
+            .unwrap()
+            .get(&symbol.0)
+            .cloned()
+            .ok_or_else(|| Error::MarketDataError {
+                reason: format!("No quote received for {}", symbol),
+            })
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.quotes.read().unwrap().contains_key(&symbol.0)
+    }
+}
+
+/// Recorded JSON quote messages, in the order they were captured
+#[derive(Debug, Clone, Default)]
+pub struct QuoteRecording {
+    messages: Vec<Value>,
+}
+
+impl QuoteRecording {
+    /// Create a recording from messages
+    pub fn new(messages: Vec<Value>) -> Self {
+        Self { messages }
+    }
+
+    /// Load a recording from a JSON Lines file with one message per line
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Load a recording from a JSON Lines reader with one message per line
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut messages = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                messages.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self { messages })
+    }
+
+    /// Get the recorded messages
+    pub fn messages(&self) -> &[Value] {
+        &self.messages
+    }
+}
+
+/// Local server that replays a quote recording over HTTP and WebSocket
+///
+/// - `GET /quotes/{symbol}` returns the symbol's next recorded message, repeating
+///   the last one once the recording is exhausted (404 for unknown symbols)
+/// - A WebSocket connection to `/stream` receives every recorded message in order
+///   as text messages, then the server closes the stream
+#[derive(Debug)]
+pub struct StubServer {
+    address: SocketAddr,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Recorded messages by symbol with the index of the next one to serve
+type SymbolCursors = HashMap<String, (Vec<Value>, usize)>;
+
+impl StubServer {
+    /// Start serving a recording on a free local port
+    pub fn start(recording: QuoteRecording, fields: &QuoteFields) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        info!("StubServer::start() - Serving {} messages on {}", recording.messages.len(), address);
+
+        let mut by_symbol: SymbolCursors = HashMap::new();
+        for message in &recording.messages {
+            if let Ok(symbol) = fields.symbol_of(message) {
+                by_symbol.entry(symbol.0).or_default().0.push(message.clone());
+            }
+        }
+
+        let messages = Arc::new(recording.messages);
+        let by_symbol = Arc::new(Mutex::new(by_symbol));
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = {
+            let running = Arc::clone(&running);
+            thread::spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let messages = Arc::clone(&messages);
+                            let by_symbol = Arc::clone(&by_symbol);
+                            thread::spawn(move || {
+                                if let Err(e) = handle_connection(stream, &messages, &by_symbol) {
+                                    debug!("StubServer::start() - Connection failed: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(5)),
+                        Err(e) => {
+                            warn!("StubServer::start() - Accept failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            address,
+            running,
+            handle: Some(handle),
+        })
+    }
+
+    /// Get the address the server is listening on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Get the HTTP URL template for quotes, for use with `HttpQuoteProvider`
+    pub fn quote_url(&self) -> String {
+        format!("http://{}/quotes/{{symbol}}", self.address)
+    }
+
+    /// Get the WebSocket URL of the quote stream
+    pub fn stream_url(&self) -> String {
+        format!("ws://{}/stream", self.address)
+    }
+
+    /// Stop accepting connections
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Serve one HTTP request or WebSocket stream
+fn handle_connection(
+    mut stream: TcpStream,
+    messages: &[Value],
+    by_symbol: &Mutex<SymbolCursors>,
+) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    // Look at the request head without consuming it, so a WebSocket handshake can still be read
+    let mut head = [0u8; 4096];
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut length = stream.peek(&mut head)?;
+    while !head[..length].windows(4).any(|window| window == b"\r\n\r\n")
+        && length < head.len()
+        && Instant::now() < deadline
+    {
+        thread::sleep(Duration::from_millis(1));
+        length = stream.peek(&mut head)?;
+    }
+    let request = String::from_utf8_lossy(&head[..length]).to_string();
+    let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+    let is_upgrade = request
+        .lines()
+        .any(|line| line.to_ascii_lowercase().starts_with("upgrade:") && line.to_ascii_lowercase().contains("websocket"));
+
+    if is_upgrade {
+        if path != "/stream" {
+            return write_response(&mut stream, "404 Not Found", "{\"error\":\"unknown stream\"}");
+        }
+        let mut socket = tungstenite::accept(stream).map_err(|e| Error::MarketDataError {
+            reason: format!("WebSocket handshake failed: {}", e),
+        })?;
+        for message in messages {
+            socket
+                .send(Message::Text(message.to_string()))
+                .map_err(|e| Error::MarketDataError {
+                    reason: format!("WebSocket send failed: {}", e),
+                })?;
+        }
+        let _ = socket.close(None);
+        // Wait for the client to acknowledge the close
+        while socket.read().is_ok() {}
+        return Ok(());
+    }
+
+    // Consume the request head before replying
+    let mut buffer = vec![0u8; request.find("\r\n\r\n").map_or(length, |end| end + 4)];
+    stream.read_exact(&mut buffer)?;
+
+    let symbol = match path.strip_prefix("/quotes/") {
+        Some(symbol) => symbol.to_string(),
+        None => return write_response(&mut stream, "404 Not Found", "{\"error\":\"unknown path\"}"),
+    };
+
+    let body = {
+        let mut by_symbol = by_symbol.lock().unwrap();
+        by_symbol.get_mut(&symbol).map(|(recorded, next)| {
+            let message = recorded[(*next).min(recorded.len() - 1)].to_string();
+            *next += 1;
+            message
+        })
+    };
+
+    match body {
+        Some(body) => write_response(&mut stream, "200 OK", &body),
+        None => write_response(&mut stream, "404 Not Found", "{\"error\":\"unknown symbol\"}"),
+    }
+}
+
+/// Write a JSON HTTP response and close the connection
+fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    fn recording() -> QuoteRecording {
+        QuoteRecording::new(vec![
+            json!({"symbol": "AAA", "bid": 99, "ask": 101, "last": 100, "timestamp": "2024-01-02T15:00:00Z"}),
+            json!({"symbol": "BBB", "bid": "49", "ask": "51", "timestamp": "2024-01-02T15:00:00Z"}),
+            json!({"symbol": "AAA", "bid": 100, "ask": 102, "last": 101, "timestamp": "2024-01-02T15:00:01Z"}),
+        ])
+    }
+
+    #[test]
+    fn fields_map_nested_schemas_and_fall_back_to_the_mid_price() {
+        let fields = QuoteFields {
+            symbol: "/instrument/ticker".to_string(),
+            bid: "/book/bid".to_string(),
+            ask: "/book/ask".to_string(),
+            timestamp: Some("t".to_string()),
+            timestamp_format: TimestampFormat::UnixMillis,
+            ..QuoteFields::default()
+        };
+        let message = json!({
+            "instrument": {"ticker": "AAA"},
+            "book": {"bid": "99.5", "ask": 100.5},
+            "t": 1704207600000i64
+        });
+
+        let quote = fields.parse(&message).unwrap();
+        assert_eq!(quote.symbol, Symbol::new("AAA"));
+        assert_eq!((quote.bid, quote.ask), (Price(dec!(99.5)), Price(dec!(100.5))));
+        assert_eq!(quote.last, Price(dec!(100)));
+        assert_eq!(quote.timestamp, Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap());
+
+        assert!(fields.parse(&json!({"instrument": {"ticker": "AAA"}})).is_err());
+    }
+
+    #[test]
+    fn messages_may_hold_arrays_and_skip_elements_that_are_not_quotes() {
+        let fields = QuoteFields::default();
+        let text = r#"[{"type": "heartbeat"}, {"symbol": "AAA", "bid": 1, "ask": 2, "timestamp": "2024-01-02T15:00:00Z"}]"#;
+
+        let quotes = fields.parse_message(text).unwrap();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].symbol, Symbol::new("AAA"));
+        assert!(fields.parse_message("not json").is_err());
+    }
+
+    #[test]
+    fn stub_server_serves_recorded_quotes_over_http() {
+        let fields = QuoteFields::default();
+        let server = StubServer::start(recording(), &fields).unwrap();
+        let http = HttpQuoteProvider::new(server.quote_url()).with_fields(fields);
+        let aaa = Symbol::new("AAA");
+
+        let bids: Vec<Price> = (0..3).map(|_| http.get_quote(&aaa).unwrap().bid).collect();
+        assert_eq!(bids, vec![Price(dec!(99)), Price(dec!(100)), Price(dec!(100))]);
+        assert_eq!(http.get_quote(&Symbol::new("BBB")).unwrap().last, Price(dec!(50)));
+        assert!(matches!(
+            http.get_quote(&Symbol::new("CCC")),
+            Err(Error::SymbolNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn stub_server_streams_recorded_quotes_over_websocket() {
+        let fields = QuoteFields::default();
+        let server = StubServer::start(recording(), &fields).unwrap();
+        let stream = WebSocketQuoteProvider::connect(&server.stream_url(), fields, None).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while stream.is_connected() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert!(!stream.is_connected());
+        assert_eq!(stream.get_quote(&Symbol::new("AAA")).unwrap().bid, Price(dec!(100)));
+        assert!(stream.is_symbol_supported(&Symbol::new("BBB")));
+        assert!(stream.get_quote(&Symbol::new("CCC")).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::bars::BarInterval;
+use crate::clock::{self, SharedClock, SimulatedClock};
+use crate::error::{Error, Result};
+use crate::market::{AuctionPrint, HistoricalDataPoint, HistoricalDataProvider, MarketDataProvider, PricePath, Quote};
+use crate::order::Auction;
+use crate::types::{Price, Symbol};
+
+/// Assumed order in which a bar's prices were traded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum IntrabarPath {
+    /// Open, high, low, close
+    #[default]
+    OpenHighLowClose,
+    /// Open, low, high, close
+    OpenLowHighClose,
+    /// Open, then the extreme nearest the open, then the other extreme, then close
+    NearestExtremeFirst,
+}
+
+impl IntrabarPath {
+    /// Get the prices of a bar in the assumed traded order
+    pub fn prices(&self, bar: &HistoricalDataPoint) -> Vec<Price> {
+        let high_first = match self {
+            IntrabarPath::OpenHighLowClose => true,
+            IntrabarPath::OpenLowHighClose => false,
+            IntrabarPath::NearestExtremeFirst => {
+                (bar.high.0 - bar.open.0) <= (bar.open.0 - bar.low.0)
+            }
+        };
+
+        if high_first {
+            vec![bar.open, bar.high, bar.low, bar.close]
+        } else {
+            vec![bar.open, bar.low, bar.high, bar.close]
+        }
+    }
+}
+
+/// Market data provider that replays historical bars under a clock
+///
+/// Bars are stamped with the time they start, and a bar's prices are only known
+/// once it ends. For each symbol, the current bar is the latest bar that ended at
+/// or before the clock's time. Quotes are derived from the bar's close plus the
+/// configured spread, and resting orders are evaluated against the bar's
+/// intrabar price path.
+#[derive(Debug, Clone)]
+pub struct BarReplayProvider {
+    series: HashMap<String, BarSeries>,
+    clock: Option<SharedClock>,
+    spread: Decimal,
+    intrabar_path: IntrabarPath,
+    auction_prints: bool,
+}
+
+impl Default for BarReplayProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BarReplayProvider {
+    /// Create a new bar replay provider using the global clock
+    pub fn new() -> Self {
+        Self {
+            series: HashMap::new(),
+            clock: None,
+            spread: Decimal::ZERO,
+            intrabar_path: IntrabarPath::default(),
+            auction_prints: false,
+        }
+    }
+
+    /// Use a specific clock to select the current bar
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Set the bid/ask spread around the close (as a decimal, e.g., 0.0005 for 0.05%)
+    pub fn with_spread(mut self, spread: Decimal) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// Set the intrabar path assumption used to fill resting orders
+    pub fn with_intrabar_path(mut self, intrabar_path: IntrabarPath) -> Self {
+        self.intrabar_path = intrabar_path;
+        self
+    }
+
+    /// Set whether each bar's open and close are reported as auction prints
+    ///
+    /// Off by default. This suits daily bars, where a bar is a session; with
+    /// intraday bars every bar would print, filling auction orders at the next
+    /// bar instead of the session's auction.
+    pub fn with_auction_prints(mut self, auction_prints: bool) -> Self {
+        self.auction_prints = auction_prints;
+        self
+    }
+
+    /// Add a bar series for a symbol, replacing any existing series
+    pub fn add_series(&mut self, symbol: &Symbol, interval: BarInterval, mut bars: Vec<HistoricalDataPoint>) {
+        bars.sort_by_key(|bar| bar.timestamp);
+        self.series.insert(symbol.0.clone(), BarSeries { interval, bars });
+    }
+
+    /// Load bar series for several symbols from a historical data provider
+    ///
+    /// Auction prints are enabled for daily bars.
+    pub fn from_historical<H: HistoricalDataProvider>(
+        historical: &H,
+        symbols: &[Symbol],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        interval: BarInterval,
+    ) -> Result<Self> {
+        let mut provider = Self::new().with_auction_prints(interval.is_daily());
+        for symbol in symbols {
+            let bars = historical.get_historical_data(symbol, start, end, interval)?;
+            provider.add_series(symbol, interval, bars);
+        }
+        Ok(provider)
+    }
+
+    /// Get the current time from the provider's clock, or the global clock if none is set
+    fn now(&self) -> DateTime<Utc> {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => clock::now(),
+        }
+    }
+
+    /// Get the current bar for a symbol: the latest bar that ended at or before the current time
+    pub fn current_bar(&self, symbol: &Symbol) -> Result<&HistoricalDataPoint> {
+        self.current(symbol).map(|(bar, _)| bar)
+    }
+
+    /// Get the current bar for a symbol and the time it ended
+    fn current(&self, symbol: &Symbol) -> Result<(&HistoricalDataPoint, DateTime<Utc>)> {
+        let series = self.series.get(&symbol.0).ok_or_else(|| Error::SymbolNotFound {
+            symbol: symbol.clone(),
+        })?;
+
+        let now = self.now();
+        let index = series.closed_before(now);
+        if index == 0 {
+            return Err(Error::MarketDataError {
+                reason: format!("No bar for {} ended at or before {}", symbol, now),
+            });
+        }
+
+        let bar = &series.bars[index - 1];
+        Ok((bar, series.end(bar)))
+    }
+
+    /// Get the time the next bar of any symbol ends after the current time
+    pub fn next_timestamp(&self) -> Option<DateTime<Utc>> {
+        let now = self.now();
+        self.series
+            .values()
+            .filter_map(|series| {
+                let index = series.closed_before(now);
+                series.bars.get(index).map(|bar| series.end(bar))
+            })
+            .min()
+    }
+
+    /// Advance a simulated clock to the end of the next bar, returning the new time
+    ///
+    /// Returns `None` once every series has been replayed.
+    pub fn advance(&self, clock: &SimulatedClock) -> Option<DateTime<Utc>> {
+        let next = self.next_timestamp()?;
+        clock.set(next);
+        Some(next)
+    }
+}
+
+/// Bars for a symbol, sorted by start time
+#[derive(Debug, Clone)]
+struct BarSeries {
+    interval: BarInterval,
+    bars: Vec<HistoricalDataPoint>,
+}
+
+impl BarSeries {
+    /// Get the time a bar ends and its prices become known
+    fn end(&self, bar: &HistoricalDataPoint) -> DateTime<Utc> {
+        bar.timestamp + self.interval.duration()
+    }
+
+    /// Get the number of bars that ended at or before `now`
+    fn closed_before(&self, now: DateTime<Utc>) -> usize {
+        self.bars.partition_point(|bar| self.end(bar) <= now)
+    }
+}
+
+impl MarketDataProvider for BarReplayProvider {
+    fn get_quote(&self, symbol: &Symbol) -> Result<Quote> {
+        let (bar, end) = self.current(symbol)?;
+        let half_spread = bar.close.0 * self.spread / Decimal::from(2);
+
+        Ok(Quote::new(
+            symbol.clone(),
+            Price(bar.close.0 - half_spread),
+            Price(bar.close.0 + half_spread),
+            bar.close,
+        )
+        .with_timestamp(end))
+    }
+
+    fn is_symbol_supported(&self, symbol: &Symbol) -> bool {
+        self.series.contains_key(&symbol.0)
+    }
+
+    fn price_path(&self, symbol: &Symbol) -> Option<PricePath> {
+        self.current_bar(symbol).ok().map(|bar| PricePath {
+            timestamp: bar.timestamp,
+            prices: self.intrabar_path.prices(bar),
+        })
+    }
+
+    fn auction_print(&self, symbol: &Symbol, auction: Auction) -> Option<AuctionPrint> {
+        if !self.auction_prints {
+            return None;
+        }
+
+        let (bar, end) = self.current(symbol).ok()?;
+        let (price, timestamp) = match auction {
+            Auction::Open => (bar.open, bar.timestamp),
+            Auction::Close => (bar.close, end),
+        };
+        Some(AuctionPrint::new(symbol.clone(), auction, price).with_timestamp(timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap()
+    }
+
+    fn bar(minutes: i64, open: Decimal, close: Decimal) -> HistoricalDataPoint {
+        HistoricalDataPoint {
+            symbol: Symbol::new("AAA"),
+            open: Price(open),
+            high: Price(open.max(close)),
+            low: Price(open.min(close)),
+            close: Price(close),
+            volume: 1000.0,
+            timestamp: start() + Duration::minutes(minutes),
+        }
+    }
+
+    fn provider(clock: &SimulatedClock) -> BarReplayProvider {
+        let mut provider = BarReplayProvider::new().with_clock(clock.shared());
+        provider.add_series(
+            &Symbol::new("AAA"),
+            BarInterval::Minutes(5),
+            vec![bar(0, dec!(100), dec!(101)), bar(5, dec!(101), dec!(110))],
+        );
+        provider
+    }
+
+    #[test]
+    fn bars_are_not_visible_before_they_close() {
+        let clock = SimulatedClock::new(start());
+        let provider = provider(&clock);
+        let symbol = Symbol::new("AAA");
+
+        assert!(provider.get_quote(&symbol).is_err());
+        assert!(provider.price_path(&symbol).is_none());
+
+        // Part way through the second bar, only the first bar's close is known
+        clock.set(start() + Duration::minutes(7));
+        let quote = provider.get_quote(&symbol).unwrap();
+        assert_eq!(quote.last, Price(dec!(101)));
+        assert_eq!(quote.timestamp, start() + Duration::minutes(5));
+        assert_eq!(provider.price_path(&symbol).unwrap().timestamp, start());
+
+        clock.set(start() + Duration::minutes(10));
+        assert_eq!(provider.get_quote(&symbol).unwrap().last, Price(dec!(110)));
+    }
+
+    #[test]
+    fn advance_steps_to_each_bar_close() {
+        let clock = SimulatedClock::new(start());
+        let provider = provider(&clock);
+
+        assert_eq!(provider.advance(&clock), Some(start() + Duration::minutes(5)));
+        assert_eq!(provider.advance(&clock), Some(start() + Duration::minutes(10)));
+        assert_eq!(provider.advance(&clock), None);
+    }
+
+    #[test]
+    fn intrabar_path_follows_the_assumed_order() {
+        let bar = HistoricalDataPoint {
+            high: Price(dec!(105)),
+            low: Price(dec!(99)),
+            ..bar(0, dec!(100), dec!(101))
+        };
+        let prices = |path: IntrabarPath| path.prices(&bar).into_iter().map(|p| p.0).collect::<Vec<_>>();
+        assert_eq!(prices(IntrabarPath::OpenHighLowClose), vec![dec!(100), dec!(105), dec!(99), dec!(101)]);
+        assert_eq!(prices(IntrabarPath::OpenLowHighClose), vec![dec!(100), dec!(99), dec!(105), dec!(101)]);
+        assert_eq!(prices(IntrabarPath::NearestExtremeFirst), vec![dec!(100), dec!(99), dec!(105), dec!(101)]);
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::bars::BarInterval;
+use crate::error::{Error, Result};
+use crate::market::{HistoricalDataPoint, HistoricalDataProvider};
+use crate::order::{OrderSide, Trade};
+use crate::types::{duration_seconds, Price, Symbol, TradeId};
+
+/// Position taken over a round trip, from flat back to flat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundTrip {
+    /// Symbol traded
+    pub symbol: Symbol,
+    /// Side of the entry (buy for long round trips, sell for short)
+    pub side: OrderSide,
+    /// Time of the first entry
+    pub opened_at: DateTime<Utc>,
+    /// Time the position returned to flat (none while open)
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Total quantity entered, including scale-ins
+    pub entry_quantity: Decimal,
+    /// Total value of the entries
+    pub entry_value: Decimal,
+    /// Total quantity exited, including partial exits
+    pub exit_quantity: Decimal,
+    /// Total value of the exits
+    pub exit_value: Decimal,
+    /// Commission paid on entries and exits
+    pub commission: Decimal,
+    /// Maximum adverse excursion per unit from the average entry price (needs price history)
+    pub mae: Option<Decimal>,
+    /// Maximum favorable excursion per unit from the average entry price (needs price history)
+    pub mfe: Option<Decimal>,
+    /// Trades making up the round trip (trades that flip the position appear in both)
+    pub trades: Vec<TradeId>,
+}
+
+impl RoundTrip {
+    /// Start a round trip
+    fn open(symbol: Symbol, side: OrderSide, opened_at: DateTime<Utc>) -> Self {
+        Self {
+            symbol,
+            side,
+            opened_at,
+            closed_at: None,
+            entry_quantity: Decimal::ZERO,
+            entry_value: Decimal::ZERO,
+            exit_quantity: Decimal::ZERO,
+            exit_value: Decimal::ZERO,
+            commission: Decimal::ZERO,
+            mae: None,
+            mfe: None,
+            trades: Vec::new(),
+        }
+    }
+
+    /// Check if the position has returned to flat
+    pub fn is_closed(&self) -> bool {
+        self.closed_at.is_some()
+    }
+
+    /// Get the quantity still held
+    pub fn open_quantity(&self) -> Decimal {
+        self.entry_quantity - self.exit_quantity
+    }
+
+    /// Get the average entry price
+    pub fn average_entry_price(&self) -> Option<Price> {
+        (self.entry_quantity > Decimal::ZERO).then(|| Price(self.entry_value / self.entry_quantity))
+    }
+
+    /// Get the average exit price
+    pub fn average_exit_price(&self) -> Option<Price> {
+        (self.exit_quantity > Decimal::ZERO).then(|| Price(self.exit_value / self.exit_quantity))
+    }
+
+    /// Get the realized profit/loss on the quantity exited, before commission
+    pub fn gross_pnl(&self) -> Decimal {
+        let (entry, exit) = match (self.average_entry_price(), self.average_exit_price()) {
+            (Some(entry), Some(exit)) => (entry.0, exit.0),
+            _ => return Decimal::ZERO,
+        };
+        match self.side {
+            OrderSide::Buy => (exit - entry) * self.exit_quantity,
+            OrderSide::Sell => (entry - exit) * self.exit_quantity,
+        }
+    }
+
+    /// Get the realized profit/loss net of commission
+    pub fn net_pnl(&self) -> Decimal {
+        self.gross_pnl() - self.commission
+    }
+
+    /// Get the time from the first entry to the final exit (none while open)
+    pub fn holding_period(&self) -> Option<Duration> {
+        self.closed_at.map(|closed_at| closed_at - self.opened_at)
+    }
+
+    /// Set the maximum adverse and favorable excursions from bars covering the round trip
+    pub fn apply_excursions(&mut self, bars: &[HistoricalDataPoint]) {
+        let entry = match self.average_entry_price() {
+            Some(entry) => entry.0,
+            None => return,
+        };
+
+        // The entry price bounds the range, so excursions are never on the wrong side of zero
+        let high = bars.iter().map(|bar| bar.high.0).fold(entry, Decimal::max);
+        let low = bars.iter().map(|bar| bar.low.0).fold(entry, Decimal::min);
+        let (mae, mfe) = match self.side {
+            OrderSide::Buy => (low - entry, high - entry),
+            OrderSide::Sell => (entry - high, entry - low),
+        };
+        self.mae = Some(mae);
+        self.mfe = Some(mfe);
+    }
+
+    /// Add an entry fill
+    fn enter(&mut self, trade_id: TradeId, quantity: Decimal, price: Decimal, commission: Decimal) {
+        self.entry_quantity += quantity;
+        self.entry_value += quantity * price;
+        self.commission += commission;
+        self.trades.push(trade_id);
+    }
+
+    /// Add an exit fill
+    fn exit(&mut self, trade_id: TradeId, quantity: Decimal, price: Decimal, commission: Decimal) {
+        self.exit_quantity += quantity;
+        self.exit_value += quantity * price;
+        self.commission += commission;
+        self.trades.push(trade_id);
+    }
+}
+
+/// Pair entries with exits per symbol, in trade time order
+///
+/// A round trip runs from the first fill out of a flat position until the
+/// position is flat again, so scale-ins and partial exits belong to the same
+/// round trip. A fill that flips the position closes the round trip and opens
+/// a new one with the remainder, splitting the commission pro rata. Returns
+/// the closed round trips in the order they closed, followed by any still open.
+pub fn reconstruct<'a, I: IntoIterator<Item = &'a Trade>>(trades: I) -> Vec<RoundTrip> {
+    let mut trades: Vec<&Trade> = trades.into_iter().collect();
+    trades.sort_by_key(|trade| trade.timestamp);
+
+    let mut closed = Vec::new();
+    let mut open: HashMap<String, RoundTrip> = HashMap::new();
+
+    for trade in trades {
+        let mut quantity = trade.quantity.0;
+        let mut commission = trade.commission;
+        while quantity > Decimal::ZERO {
+            let trip = open
+                .entry(trade.symbol.0.clone())
+                .or_insert_with(|| RoundTrip::open(trade.symbol.clone(), trade.side, trade.timestamp));
+
+            if trip.side == trade.side {
+                trip.enter(trade.id, quantity, trade.price.0, commission);
+                break;
+            }
+
+            let exit_quantity = quantity.min(trip.open_quantity());
+            let exit_commission = commission * exit_quantity / quantity;
+            trip.exit(trade.id, exit_quantity, trade.price.0, exit_commission);
+            quantity -= exit_quantity;
+            commission -= exit_commission;
+
+            if trip.open_quantity() <= Decimal::ZERO {
+                if let Some(mut trip) = open.remove(&trade.symbol.0) {
+                    trip.closed_at = Some(trade.timestamp);
+                    closed.push(trip);
+                }
+            }
+        }
+    }
+
+    let mut open: Vec<RoundTrip> = open.into_values().collect();
+    open.sort_by(|a, b| a.opened_at.cmp(&b.opened_at).then_with(|| a.symbol.0.cmp(&b.symbol.0)));
+    closed.extend(open);
+    closed
+}
+
+/// Set excursions on each round trip from a provider's bars
+///
+/// Round trips for symbols the provider has no data for are left without excursions.
+pub fn apply_excursions<H: HistoricalDataProvider>(
+    round_trips: &mut [RoundTrip],
+    history: &H,
+    interval: BarInterval,
+) -> Result<()> {
+    for trip in round_trips {
+        let end = trip.closed_at.unwrap_or(DateTime::<Utc>::MAX_UTC);
+        match history.get_historical_data(&trip.symbol, trip.opened_at, end, interval) {
+            Ok(bars) => trip.apply_excursions(&bars),
+            Err(Error::SymbolNotFound { .. }) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+/// Aggregate statistics over closed round trips
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundTripStats {
+    /// Number of closed round trips
+    pub count: usize,
+    /// Number with a positive net profit
+    pub winners: usize,
+    /// Number with a negative net profit
+    pub losers: usize,
+    /// Fraction of round trips that won
+    pub win_rate: f64,
+    /// Average net profit of winners
+    pub average_win: Decimal,
+    /// Average net loss of losers (negative)
+    pub average_loss: Decimal,
+    /// Average net profit per round trip
+    pub expectancy: Decimal,
+    /// Gross profit of winners over gross loss of losers (none without losers)
+    pub profit_factor: Option<f64>,
+    /// Largest net profit
+    pub largest_win: Decimal,
+    /// Largest net loss (negative)
+    pub largest_loss: Decimal,
+    /// Most consecutive winners
+    pub longest_winning_streak: usize,
+    /// Most consecutive losers
+    pub longest_losing_streak: usize,
+    /// Average time from entry to exit (seconds when serialized)
+    #[serde(with = "duration_seconds")]
+    pub average_holding_period: Duration,
+    /// Total net profit
+    pub total_net_pnl: Decimal,
+    /// Total commission paid
+    pub total_commission: Decimal,
+}
+
+impl RoundTripStats {
+    /// Compute statistics over the closed round trips, in the order given
+    pub fn from_round_trips(round_trips: &[RoundTrip]) -> Self {
+        let closed: Vec<&RoundTrip> = round_trips.iter().filter(|trip| trip.is_closed()).collect();
+        let pnls: Vec<Decimal> = closed.iter().map(|trip| trip.net_pnl()).collect();
+        let wins: Vec<Decimal> = pnls.iter().copied().filter(|pnl| *pnl > Decimal::ZERO).collect();
+        let losses: Vec<Decimal> = pnls.iter().copied().filter(|pnl| *pnl < Decimal::ZERO).collect();
+
+        let gross_win: Decimal = wins.iter().sum();
+        let gross_loss: Decimal = losses.iter().sum();
+        let total_net_pnl: Decimal = pnls.iter().sum();
+        let average = |total: Decimal, count: usize| {
+            if count > 0 {
+                total / Decimal::from(count)
+            } else {
+                Decimal::ZERO
+            }
+        };
+
+        let mut longest_winning_streak = 0;
+        let mut longest_losing_streak = 0;
+        let (mut winning, mut losing) = (0, 0);
+        for pnl in &pnls {
+            winning = if *pnl > Decimal::ZERO { winning + 1 } else { 0 };
+            losing = if *pnl < Decimal::ZERO { losing + 1 } else { 0 };
+            longest_winning_streak = longest_winning_streak.max(winning);
+            longest_losing_streak = longest_losing_streak.max(losing);
+        }
+
+        let holding: Duration = closed.iter().filter_map(|trip| trip.holding_period()).sum();
+        let average_holding_period = if closed.is_empty() {
+            Duration::zero()
+        } else {
+            holding / closed.len() as i32
+        };
+
+        Self {
+            count: closed.len(),
+            winners: wins.len(),
+            losers: losses.len(),
+            win_rate: if closed.is_empty() { 0.0 } else { wins.len() as f64 / closed.len() as f64 },
+            average_win: average(gross_win, wins.len()),
+            average_loss: average(gross_loss, losses.len()),
+            expectancy: average(total_net_pnl, closed.len()),
+            profit_factor: (gross_loss < Decimal::ZERO)
+                .then(|| (gross_win / -gross_loss).to_f64().unwrap_or(0.0)),
+            largest_win: wins.iter().copied().max().unwrap_or_default(),
+            largest_loss: losses.iter().copied().min().unwrap_or_default(),
+            longest_winning_streak,
+            longest_losing_streak,
+            average_holding_period,
+            total_net_pnl,
+            total_commission: closed.iter().map(|trip| trip.commission).sum(),
+        }
+    }
+}
+
+/// Round trips with overall and per-symbol statistics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundTripReport {
+    /// All round trips, closed ones first in the order they closed
+    pub round_trips: Vec<RoundTrip>,
+    /// Statistics over all closed round trips
+    pub stats: RoundTripStats,
+    /// Statistics over the closed round trips of each symbol
+    pub by_symbol: BTreeMap<String, RoundTripStats>,
+}
+
+impl RoundTripReport {
+    /// Build a report from reconstructed round trips
+    pub fn new(round_trips: Vec<RoundTrip>) -> Self {
+        let mut symbols: BTreeMap<String, Vec<RoundTrip>> = BTreeMap::new();
+        for trip in &round_trips {
+            symbols.entry(trip.symbol.0.clone()).or_default().push(trip.clone());
+        }
+
+        Self {
+            stats: RoundTripStats::from_round_trips(&round_trips),
+            by_symbol: symbols
+                .into_iter()
+                .map(|(symbol, trips)| (symbol, RoundTripStats::from_round_trips(&trips)))
+                .collect(),
+            round_trips,
+        }
+    }
+
+    /// Get the round trips still open
+    pub fn open_round_trips(&self) -> impl Iterator<Item = &RoundTrip> {
+        self.round_trips.iter().filter(|trip| !trip.is_closed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OrderId, Quantity};
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap()
+    }
+
+    fn trade(hour: i64, side: OrderSide, quantity: Decimal, price: Decimal, commission: Decimal) -> Trade {
+        Trade {
+            timestamp: start() + Duration::hours(hour),
+            ..Trade::new(OrderId::new(), Symbol::new("AAA"), side, Quantity(quantity), Price(price), commission)
+        }
+    }
+
+    fn bar(high: Decimal, low: Decimal) -> HistoricalDataPoint {
+        HistoricalDataPoint {
+            symbol: Symbol::new("AAA"),
+            timestamp: start(),
+            open: Price(low),
+            high: Price(high),
+            low: Price(low),
+            close: Price(high),
+            volume: 0.0,
+        }
+    }
+
+    #[test]
+    fn scale_ins_and_partial_exits_form_one_round_trip() {
+        let trades = vec![
+            trade(0, OrderSide::Buy, dec!(10), dec!(100), dec!(1)),
+            trade(1, OrderSide::Buy, dec!(10), dec!(110), dec!(1)),
+            trade(2, OrderSide::Sell, dec!(5), dec!(120), dec!(1)),
+            trade(3, OrderSide::Sell, dec!(15), dec!(115), dec!(1)),
+        ];
+
+        let trips = reconstruct(&trades);
+
+        assert_eq!(trips.len(), 1);
+        let trip = &trips[0];
+        assert!(trip.is_closed());
+        assert_eq!(trip.average_entry_price(), Some(Price(dec!(105))));
+        assert_eq!(trip.average_exit_price(), Some(Price(dec!(116.25))));
+        assert_eq!(trip.gross_pnl(), dec!(225));
+        assert_eq!(trip.net_pnl(), dec!(221));
+        assert_eq!(trip.holding_period(), Some(Duration::hours(3)));
+        assert_eq!(trip.trades.len(), 4);
+    }
+
+    #[test]
+    fn a_fill_that_flips_the_position_splits_the_round_trip() {
+        let trades = vec![
+            trade(0, OrderSide::Buy, dec!(10), dec!(100), dec!(2)),
+            trade(1, OrderSide::Sell, dec!(15), dec!(90), dec!(3)),
+        ];
+
+        let trips = reconstruct(&trades);
+
+        assert_eq!(trips.len(), 2);
+        assert_eq!(trips[0].side, OrderSide::Buy);
+        assert_eq!(trips[0].net_pnl(), dec!(-104));
+        assert_eq!(trips[1].side, OrderSide::Sell);
+        assert!(!trips[1].is_closed());
+        assert_eq!(trips[1].open_quantity(), dec!(5));
+        assert_eq!(trips[1].commission, dec!(1));
+        assert!(trips[0].trades.contains(&trades[1].id) && trips[1].trades.contains(&trades[1].id));
+    }
+
+    #[test]
+    fn excursions_are_measured_from_the_average_entry() {
+        let bars = vec![bar(dec!(110), dec!(98)), bar(dec!(104), dec!(95))];
+
+        let mut long = reconstruct(&[trade(0, OrderSide::Buy, dec!(1), dec!(100), dec!(0))]).remove(0);
+        long.apply_excursions(&bars);
+        assert_eq!((long.mae, long.mfe), (Some(dec!(-5)), Some(dec!(10))));
+
+        let mut short = reconstruct(&[trade(0, OrderSide::Sell, dec!(1), dec!(100), dec!(0))]).remove(0);
+        short.apply_excursions(&bars);
+        assert_eq!((short.mae, short.mfe), (Some(dec!(-10)), Some(dec!(5))));
+    }
+
+    #[test]
+    fn stats_summarize_closed_round_trips() {
+        let mut trades = Vec::new();
+        for (index, exit) in [dec!(110), dec!(120), dec!(85)].into_iter().enumerate() {
+            let hour = index as i64 * 2;
+            trades.push(trade(hour, OrderSide::Buy, dec!(1), dec!(100), dec!(0)));
+            trades.push(trade(hour + 1, OrderSide::Sell, dec!(1), exit, dec!(0)));
+        }
+        trades.push(trade(6, OrderSide::Buy, dec!(1), dec!(100), dec!(0)));
+
+        let report = RoundTripReport::new(reconstruct(&trades));
+        let stats = &report.stats;
+
+        assert_eq!(report.open_round_trips().count(), 1);
+        assert_eq!((stats.count, stats.winners, stats.losers), (3, 2, 1));
+        assert!((stats.win_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!((stats.average_win, stats.average_loss), (dec!(15), dec!(-15)));
+        assert_eq!(stats.expectancy, dec!(5));
+        assert_eq!(stats.profit_factor, Some(2.0));
+        assert_eq!((stats.largest_win, stats.largest_loss), (dec!(20), dec!(-15)));
+        assert_eq!((stats.longest_winning_streak, stats.longest_losing_streak), (2, 1));
+        assert_eq!(stats.average_holding_period, Duration::hours(1));
+        assert_eq!(report.by_symbol["AAA"], *stats);
+    }
+}
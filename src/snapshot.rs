@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+/// Represents what caused a snapshot to be taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotKind {
+    /// Taken after an order filled
+    Fill,
+    /// Taken at the configured snapshot interval
+    Interval,
+    /// Taken on request
+    Manual,
+}
+
+/// Point-in-time valuation of an account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EquitySnapshot {
+    /// Time the snapshot was taken
+    pub timestamp: DateTime<Utc>,
+    /// What caused the snapshot
+    pub kind: SnapshotKind,
+    /// Total equity (cash + positions)
+    pub equity: Decimal,
+    /// Cash balance
+    pub cash: Decimal,
+    /// Sum of the absolute market values of all positions
+    pub gross_exposure: Decimal,
+    /// Sum of the signed market values of all positions
+    pub net_exposure: Decimal,
+    /// Market value of each open position by symbol
+    pub positions: BTreeMap<String, Decimal>,
+    /// Net deposits, withdrawals and transfers posted to the account up to the snapshot
+    #[serde(default)]
+    pub external_flows: Decimal,
+}
+
+impl EquitySnapshot {
+    /// Create a snapshot from cash and position market values
+    pub fn new(
+        timestamp: DateTime<Utc>,
+        kind: SnapshotKind,
+        cash: Decimal,
+        positions: BTreeMap<String, Decimal>,
+    ) -> Self {
+        let net_exposure: Decimal = positions.values().sum();
+        let gross_exposure: Decimal = positions.values().map(|value| value.abs()).sum();
+        Self {
+            timestamp,
+            kind,
+            equity: cash + net_exposure,
+            cash,
+            gross_exposure,
+            net_exposure,
+            positions,
+            external_flows: Decimal::ZERO,
+        }
+    }
+
+    /// Set the net external flows posted up to the snapshot
+    pub fn with_external_flows(mut self, external_flows: Decimal) -> Self {
+        self.external_flows = external_flows;
+        self
+    }
+}
+
+/// Time series of equity snapshots for an account
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EquityHistory {
+    snapshots: Vec<EquitySnapshot>,
+}
+
+impl EquityHistory {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a snapshot to the history
+    pub fn push(&mut self, snapshot: EquitySnapshot) {
+        self.snapshots.push(snapshot);
+    }
+
+    /// Get all snapshots in the order they were taken
+    pub fn snapshots(&self) -> &[EquitySnapshot] {
+        &self.snapshots
+    }
+
+    /// Get the most recent snapshot
+    pub fn latest(&self) -> Option<&EquitySnapshot> {
+        self.snapshots.last()
+    }
+
+    /// Get the most recent snapshot of a kind
+    pub fn latest_of(&self, kind: SnapshotKind) -> Option<&EquitySnapshot> {
+        self.snapshots.iter().rev().find(|snapshot| snapshot.kind == kind)
+    }
+
+    /// Get the number of snapshots
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Check if there are no snapshots
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Get the snapshots taken from `start` (inclusive) to `end` (exclusive)
+    pub fn between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> impl Iterator<Item = &EquitySnapshot> {
+        self.snapshots
+            .iter()
+            .filter(move |snapshot| snapshot.timestamp >= start && snapshot.timestamp < end)
+    }
+
+    /// Get the equity curve as (timestamp, equity) points
+    pub fn equity_curve(&self) -> Vec<(DateTime<Utc>, Decimal)> {
+        self.snapshots
+            .iter()
+            .map(|snapshot| (snapshot.timestamp, snapshot.equity))
+            .collect()
+    }
+
+    /// Remove all snapshots
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// Drop the oldest snapshots, keeping at most `count`
+    pub fn retain_latest(&mut self, count: usize) {
+        let excess = self.snapshots.len().saturating_sub(count);
+        self.snapshots.drain(..excess);
+    }
+
+    /// Write the history as CSV, with a market value column per symbol
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let symbols: BTreeSet<&String> = self
+            .snapshots
+            .iter()
+            .flat_map(|snapshot| snapshot.positions.keys())
+            .collect();
+
+        let mut writer = csv::Writer::from_writer(writer);
+        let mut header = vec!["timestamp", "kind", "equity", "cash", "gross_exposure", "net_exposure", "external_flows"];
+        header.extend(symbols.iter().map(|symbol| symbol.as_str()));
+        writer.write_record(&header)?;
+
+        for snapshot in &self.snapshots {
+            let mut record = vec![
+                snapshot.timestamp.to_rfc3339(),
+                format!("{:?}", snapshot.kind),
+                snapshot.equity.normalize().to_string(),
+                snapshot.cash.normalize().to_string(),
+                snapshot.gross_exposure.normalize().to_string(),
+                snapshot.net_exposure.normalize().to_string(),
+                snapshot.external_flows.normalize().to_string(),
+            ];
+            record.extend(
+                symbols
+                    .iter()
+                    .map(|symbol| snapshot.positions.get(*symbol).copied().unwrap_or_default().normalize().to_string()),
+            );
+            writer.write_record(&record)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the history to a CSV file
+    pub fn write_csv_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_csv(File::create(path)?)
+    }
+
+    /// Write the history as JSON Lines, one snapshot per line
+    pub fn write_json_lines<W: Write>(&self, mut writer: W) -> Result<()> {
+        for snapshot in &self.snapshots {
+            serde_json::to_writer(&mut writer, snapshot)?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap()
+    }
+
+    fn snapshot(hours: i64, kind: SnapshotKind, positions: &[(&str, Decimal)]) -> EquitySnapshot {
+        let positions = positions.iter().map(|(symbol, value)| (symbol.to_string(), *value)).collect();
+        EquitySnapshot::new(start() + Duration::hours(hours), kind, dec!(1000), positions)
+    }
+
+    #[test]
+    fn new_snapshot_sums_exposure() {
+        let snapshot = snapshot(0, SnapshotKind::Manual, &[("AAA", dec!(500)), ("BBB", dec!(-200))]);
+        assert_eq!(snapshot.equity, dec!(1300));
+        assert_eq!(snapshot.gross_exposure, dec!(700));
+        assert_eq!(snapshot.net_exposure, dec!(300));
+    }
+
+    #[test]
+    fn retain_latest_drops_the_oldest() {
+        let mut history = EquityHistory::new();
+        for hours in 0..5 {
+            history.push(snapshot(hours, SnapshotKind::Interval, &[]));
+        }
+        history.retain_latest(2);
+        let hours: Vec<i64> = history.snapshots().iter().map(|s| (s.timestamp - start()).num_hours()).collect();
+        assert_eq!(hours, vec![3, 4]);
+    }
+
+    #[test]
+    fn latest_of_finds_the_last_snapshot_of_a_kind() {
+        let mut history = EquityHistory::new();
+        history.push(snapshot(0, SnapshotKind::Interval, &[]));
+        history.push(snapshot(1, SnapshotKind::Fill, &[]));
+        assert_eq!(history.latest_of(SnapshotKind::Interval).unwrap().timestamp, start());
+        assert_eq!(history.latest().unwrap().kind, SnapshotKind::Fill);
+        assert_eq!(history.between(start(), start() + Duration::hours(1)).count(), 1);
+    }
+
+    #[test]
+    fn csv_has_a_column_per_symbol() {
+        let mut history = EquityHistory::new();
+        history.push(snapshot(0, SnapshotKind::Fill, &[("AAA", dec!(500))]));
+        history.push(snapshot(1, SnapshotKind::Interval, &[("BBB", dec!(250))]));
+
+        let mut csv = Vec::new();
+        history.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,kind,equity,cash,gross_exposure,net_exposure,external_flows,AAA,BBB");
+        assert_eq!(lines[1], "2024-01-02T00:00:00+00:00,Fill,1500,1000,500,500,0,500,0");
+        assert_eq!(lines[2], "2024-01-02T01:00:00+00:00,Interval,1250,1000,250,250,0,0,250");
+    }
+}
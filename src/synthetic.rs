@@ -0,0 +1,589 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Poisson, StandardNormal};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::market::{HistoricalDataPoint, Quote};
+use crate::types::{Price, Symbol};
+
+/// Seconds in a year, used to convert annualized parameters to time steps
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// One state of a regime-switching model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Regime {
+    /// Annualized drift of log returns
+    pub drift: f64,
+    /// Annualized volatility
+    pub volatility: f64,
+}
+
+/// Stochastic process driving a synthetic price
+///
+/// All rates and volatilities are annualized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PriceModel {
+    /// Geometric Brownian motion
+    GeometricBrownianMotion {
+        /// Expected return
+        drift: f64,
+        /// Volatility
+        volatility: f64,
+    },
+    /// Merton jump diffusion: GBM plus log-normally distributed jumps
+    JumpDiffusion {
+        /// Expected return (jumps are compensated)
+        drift: f64,
+        /// Diffusion volatility
+        volatility: f64,
+        /// Expected number of jumps per year
+        jump_intensity: f64,
+        /// Mean log jump size
+        jump_mean: f64,
+        /// Volatility of the log jump size
+        jump_volatility: f64,
+    },
+    /// Ornstein-Uhlenbeck mean reversion of the log price
+    OrnsteinUhlenbeck {
+        /// Price level the process reverts to
+        mean: f64,
+        /// Speed of reversion
+        reversion_speed: f64,
+        /// Volatility
+        volatility: f64,
+    },
+    /// Markov regime switching between GBM regimes
+    RegimeSwitching {
+        /// Available regimes (the first is the starting regime)
+        regimes: Vec<Regime>,
+        /// Per-step transition probabilities, `transitions[from][to]`
+        transitions: Vec<Vec<f64>>,
+    },
+}
+
+/// Log-normal volume model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeModel {
+    /// Mean volume per step
+    pub mean: f64,
+    /// Volatility of the log volume
+    pub volatility: f64,
+}
+
+impl Default for VolumeModel {
+    fn default() -> Self {
+        Self {
+            mean: 1_000_000.0,
+            volatility: 0.5,
+        }
+    }
+}
+
+/// Specification of a synthetic asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticAsset {
+    /// Symbol of the asset
+    pub symbol: Symbol,
+    /// Starting price
+    pub initial_price: f64,
+    /// Price process
+    pub model: PriceModel,
+    /// Bid/ask spread (as a decimal, e.g., 0.0005 for 0.05%)
+    pub spread: f64,
+    /// Volume process
+    pub volume: VolumeModel,
+    /// Number of decimal places prices are rounded to
+    pub decimals: u32,
+}
+
+impl SyntheticAsset {
+    /// Create a new synthetic asset with no spread and two decimal places
+    pub fn new<S: Into<String>>(symbol: S, initial_price: f64, model: PriceModel) -> Self {
+        Self {
+            symbol: Symbol::new(symbol),
+            initial_price,
+            model,
+            spread: 0.0,
+            volume: VolumeModel::default(),
+            decimals: 2,
+        }
+    }
+
+    /// Set the bid/ask spread
+    pub fn with_spread(mut self, spread: f64) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// Set the volume model
+    pub fn with_volume(mut self, volume: VolumeModel) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Set the number of decimal places prices are rounded to
+    pub fn with_decimals(mut self, decimals: u32) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Check that every parameter is finite and in range
+    ///
+    /// Prices and volume means must be positive; volatilities, spreads, jump
+    /// intensities and reversion speeds must not be negative.
+    pub fn validate(&self) -> Result<()> {
+        let mut checks = vec![
+            ("initial price", self.initial_price, true),
+            ("spread", self.spread, false),
+            ("volume mean", self.volume.mean, true),
+            ("volume volatility", self.volume.volatility, false),
+        ];
+        let mut drifts = Vec::new();
+        match &self.model {
+            PriceModel::GeometricBrownianMotion { drift, volatility } => {
+                drifts.push(*drift);
+                checks.push(("volatility", *volatility, false));
+            }
+            PriceModel::JumpDiffusion {
+                drift,
+                volatility,
+                jump_intensity,
+                jump_mean,
+                jump_volatility,
+            } => {
+                drifts.extend([*drift, *jump_mean]);
+                checks.extend([
+                    ("volatility", *volatility, false),
+                    ("jump intensity", *jump_intensity, false),
+                    ("jump volatility", *jump_volatility, false),
+                ]);
+            }
+            PriceModel::OrnsteinUhlenbeck {
+                mean,
+                reversion_speed,
+                volatility,
+            } => {
+                checks.extend([
+                    ("mean", *mean, true),
+                    ("reversion speed", *reversion_speed, false),
+                    ("volatility", *volatility, false),
+                ]);
+            }
+            PriceModel::RegimeSwitching { regimes, transitions } => {
+                let size = regimes.len();
+                if size == 0 || transitions.len() != size || transitions.iter().any(|row| row.len() != size) {
+                    return Err(self.invalid(format!(
+                        "needs at least one regime and a {}x{} transition matrix",
+                        size, size
+                    )));
+                }
+                for regime in regimes {
+                    drifts.push(regime.drift);
+                    checks.push(("regime volatility", regime.volatility, false));
+                }
+                for probability in transitions.iter().flatten() {
+                    checks.push(("transition probability", *probability, false));
+                }
+            }
+        }
+
+        if drifts.iter().any(|drift| !drift.is_finite()) {
+            return Err(self.invalid("has a drift that is not finite".to_string()));
+        }
+        for (name, value, positive) in checks {
+            let in_range = if positive { value > 0.0 } else { value >= 0.0 };
+            if !value.is_finite() || !in_range {
+                let bound = if positive { "positive" } else { "non-negative" };
+                return Err(self.invalid(format!("{} must be finite and {}, got {}", name, bound, value)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the error for an invalid parameter
+    fn invalid(&self, reason: String) -> Error {
+        Error::MarketDataError {
+            reason: format!("Synthetic asset {}: {}", self.symbol, reason),
+        }
+    }
+}
+
+/// Simulation state of one asset
+#[derive(Debug, Clone)]
+struct AssetState {
+    asset: SyntheticAsset,
+    log_price: f64,
+    regime: usize,
+}
+
+/// Seedable generator of synthetic, optionally correlated market data
+///
+/// The same seed and parameters always produce the same paths, so strategies
+/// can be Monte-Carlo tested across many seeds.
+#[derive(Debug, Clone)]
+pub struct SyntheticMarket {
+    assets: Vec<AssetState>,
+    /// Lower-triangular Cholesky factor of the correlation matrix
+    cholesky: Option<Vec<Vec<f64>>>,
+    rng: ChaCha8Rng,
+    time: DateTime<Utc>,
+    step: Duration,
+    substeps: usize,
+}
+
+impl SyntheticMarket {
+    /// Create a new synthetic market starting at `start` and advancing by `step`
+    pub fn new(seed: u64, start: DateTime<Utc>, step: Duration) -> Self {
+        Self {
+            assets: Vec::new(),
+            cholesky: None,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            time: start,
+            step,
+            substeps: 16,
+        }
+    }
+
+    /// Add an asset to the market
+    ///
+    /// Fails if the asset's parameters are invalid, or if a correlation has
+    /// already been set, as it wouldn't cover the new asset; call
+    /// `with_correlation` after adding the last asset.
+    pub fn with_asset(mut self, asset: SyntheticAsset) -> Result<Self> {
+        asset.validate()?;
+        if self.cholesky.is_some() {
+            return Err(Error::MarketDataError {
+                reason: format!("Cannot add {} after setting the correlation; add every asset first", asset.symbol),
+            });
+        }
+        self.assets.push(AssetState {
+            log_price: asset.initial_price.ln(),
+            asset,
+            regime: 0,
+        });
+        Ok(self)
+    }
+
+    /// Correlate the assets' shocks (matrix in the order assets were added)
+    pub fn with_correlation(mut self, correlation: Vec<Vec<f64>>) -> Result<Self> {
+        let size = self.assets.len();
+        if correlation.len() != size || correlation.iter().any(|row| row.len() != size) {
+            return Err(Error::MarketDataError {
+                reason: format!("Correlation matrix must be {}x{}", size, size),
+            });
+        }
+        if correlation.iter().flatten().any(|value| !value.is_finite() || value.abs() > 1.0) {
+            return Err(Error::MarketDataError {
+                reason: "Correlations must be finite and between -1 and 1".to_string(),
+            });
+        }
+        self.cholesky = Some(cholesky(&correlation)?);
+        Ok(self)
+    }
+
+    /// Set the number of simulated price points per bar (used for bar highs and lows)
+    pub fn with_substeps(mut self, substeps: usize) -> Self {
+        self.substeps = substeps.max(1);
+        self
+    }
+
+    /// Get the current simulated time
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// Advance one step and return a quote per asset
+    pub fn next_quotes(&mut self) -> Vec<Quote> {
+        let dt = self.step.num_milliseconds() as f64 / 1000.0 / SECONDS_PER_YEAR;
+        self.transition_regimes();
+        self.advance(dt);
+        self.time += self.step;
+
+        let time = self.time;
+        self.assets
+            .iter()
+            .map(|state| {
+                let asset = &state.asset;
+                let price = state.log_price.exp();
+                let half_spread = price * asset.spread / 2.0;
+                Quote::new(
+                    asset.symbol.clone(),
+                    to_price(price - half_spread, asset.decimals),
+                    to_price(price + half_spread, asset.decimals),
+                    to_price(price, asset.decimals),
+                )
+                .with_timestamp(time)
+            })
+            .collect()
+    }
+
+    /// Advance one step and return a bar per asset, stamped with the start of the step
+    pub fn next_bars(&mut self) -> Vec<HistoricalDataPoint> {
+        let dt = self.step.num_milliseconds() as f64 / 1000.0 / SECONDS_PER_YEAR / self.substeps as f64;
+        let opens: Vec<f64> = self.assets.iter().map(|state| state.log_price).collect();
+        let mut highs = opens.clone();
+        let mut lows = opens.clone();
+
+        self.transition_regimes();
+        for _ in 0..self.substeps {
+            self.advance(dt);
+            for (index, state) in self.assets.iter().enumerate() {
+                highs[index] = highs[index].max(state.log_price);
+                lows[index] = lows[index].min(state.log_price);
+            }
+        }
+
+        let timestamp = self.time;
+        self.time += self.step;
+
+        let mut bars = Vec::with_capacity(self.assets.len());
+        for index in 0..self.assets.len() {
+            let volume_model = self.assets[index].asset.volume.clone();
+            let volume = self.sample_volume(&volume_model);
+            let state = &self.assets[index];
+            let asset = &state.asset;
+            bars.push(HistoricalDataPoint {
+                symbol: asset.symbol.clone(),
+                open: to_price(opens[index].exp(), asset.decimals),
+                high: to_price(highs[index].exp(), asset.decimals),
+                low: to_price(lows[index].exp(), asset.decimals),
+                close: to_price(state.log_price.exp(), asset.decimals),
+                volume,
+                timestamp,
+            });
+        }
+        bars
+    }
+
+    /// Generate `count` bars per asset
+    pub fn generate_bars(&mut self, count: usize) -> Vec<Vec<HistoricalDataPoint>> {
+        let mut series: Vec<Vec<HistoricalDataPoint>> = vec![Vec::with_capacity(count); self.assets.len()];
+        for _ in 0..count {
+            for (index, bar) in self.next_bars().into_iter().enumerate() {
+                series[index].push(bar);
+            }
+        }
+        series
+    }
+
+    /// Advance every asset's log price by `dt` years
+    fn advance(&mut self, dt: f64) {
+        let shocks = self.correlated_normals();
+
+        for (index, shock) in shocks.into_iter().enumerate() {
+            let model = self.assets[index].asset.model.clone();
+
+            let change = match &model {
+                PriceModel::GeometricBrownianMotion { drift, volatility } => {
+                    (drift - volatility * volatility / 2.0) * dt + volatility * dt.sqrt() * shock
+                }
+                PriceModel::JumpDiffusion {
+                    drift,
+                    volatility,
+                    jump_intensity,
+                    jump_mean,
+                    jump_volatility,
+                } => {
+                    // Compensate the drift for the expected jump so `drift` stays the expected return
+                    let expected_jump = (jump_mean + jump_volatility * jump_volatility / 2.0).exp() - 1.0;
+                    let diffusion = (drift - volatility * volatility / 2.0 - jump_intensity * expected_jump) * dt
+                        + volatility * dt.sqrt() * shock;
+                    diffusion + self.sample_jumps(jump_intensity * dt, *jump_mean, *jump_volatility)
+                }
+                PriceModel::OrnsteinUhlenbeck {
+                    mean,
+                    reversion_speed,
+                    volatility,
+                } => {
+                    let log_price = self.assets[index].log_price;
+                    reversion_speed * (mean.ln() - log_price) * dt + volatility * dt.sqrt() * shock
+                }
+                PriceModel::RegimeSwitching { regimes, .. } => {
+                    match regimes.get(self.assets[index].regime) {
+                        Some(Regime { drift, volatility }) => {
+                            (drift - volatility * volatility / 2.0) * dt + volatility * dt.sqrt() * shock
+                        }
+                        None => 0.0,
+                    }
+                }
+            };
+
+            self.assets[index].log_price += change;
+        }
+    }
+
+    /// Draw one standard normal shock per asset, correlated if configured
+    fn correlated_normals(&mut self) -> Vec<f64> {
+        let independent: Vec<f64> = (0..self.assets.len())
+            .map(|_| self.rng.sample(StandardNormal))
+            .collect();
+
+        match &self.cholesky {
+            Some(factor) => factor
+                .iter()
+                .map(|row| row.iter().zip(&independent).map(|(a, z)| a * z).sum())
+                .collect(),
+            None => independent,
+        }
+    }
+
+    /// Draw the total log jump over a step with `expected` jumps
+    fn sample_jumps(&mut self, expected: f64, jump_mean: f64, jump_volatility: f64) -> f64 {
+        if expected <= 0.0 {
+            return 0.0;
+        }
+        let count = match Poisson::new(expected) {
+            Ok(poisson) => poisson.sample(&mut self.rng) as u64,
+            Err(_) => 0,
+        };
+        (0..count)
+            .map(|_| {
+                let z: f64 = self.rng.sample(StandardNormal);
+                jump_mean + jump_volatility * z
+            })
+            .sum()
+    }
+
+    /// Move every regime-switching asset to its next regime, once per step
+    fn transition_regimes(&mut self) {
+        for index in 0..self.assets.len() {
+            if let PriceModel::RegimeSwitching { transitions, .. } = &self.assets[index].asset.model {
+                let transitions = transitions.clone();
+                self.next_regime(index, &transitions);
+            }
+        }
+    }
+
+    /// Move an asset to its next regime
+    fn next_regime(&mut self, index: usize, transitions: &[Vec<f64>]) {
+        let current = self.assets[index].regime;
+        if let Some(row) = transitions.get(current) {
+            let draw: f64 = self.rng.gen();
+            let mut cumulative = 0.0;
+            for (next, probability) in row.iter().enumerate() {
+                cumulative += probability;
+                if draw < cumulative {
+                    self.assets[index].regime = next;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Draw a log-normal volume with the model's mean
+    fn sample_volume(&mut self, model: &VolumeModel) -> f64 {
+        let z: f64 = self.rng.sample(StandardNormal);
+        (model.mean * (model.volatility * z - model.volatility * model.volatility / 2.0).exp()).round()
+    }
+}
+
+/// Convert a simulated price to a rounded `Price`
+fn to_price(value: f64, decimals: u32) -> Price {
+    Price(Decimal::from_f64(value).unwrap_or_default().round_dp(decimals))
+}
+
+/// Cholesky decomposition of a symmetric positive semi-definite matrix
+fn cholesky(matrix: &[Vec<f64>]) -> Result<Vec<Vec<f64>>> {
+    let size = matrix.len();
+    let mut factor = vec![vec![0.0; size]; size];
+
+    for i in 0..size {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| factor[i][k] * factor[j][k]).sum();
+            if i == j {
+                let value = matrix[i][i] - sum;
+                if value < -1e-12 {
+                    return Err(Error::MarketDataError {
+                        reason: "Correlation matrix is not positive semi-definite".to_string(),
+                    });
+                }
+                factor[i][j] = value.max(0.0).sqrt();
+            } else if factor[j][j] > 0.0 {
+                factor[i][j] = (matrix[i][j] - sum) / factor[j][j];
+            }
+        }
+    }
+
+    Ok(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap()
+    }
+
+    fn gbm(symbol: &str, volatility: f64) -> SyntheticAsset {
+        SyntheticAsset::new(symbol, 100.0, PriceModel::GeometricBrownianMotion { drift: 0.05, volatility })
+    }
+
+    fn market(seed: u64) -> SyntheticMarket {
+        SyntheticMarket::new(seed, start(), Duration::days(1))
+            .with_asset(gbm("AAA", 0.2))
+            .unwrap()
+            .with_asset(gbm("BBB", 0.3))
+            .unwrap()
+    }
+
+    #[test]
+    fn same_seed_produces_the_same_bars() {
+        assert_eq!(market(1).generate_bars(20), market(1).generate_bars(20));
+        assert_ne!(market(1).generate_bars(20), market(2).generate_bars(20));
+    }
+
+    #[test]
+    fn bars_contain_their_open_and_close() {
+        let mut market = market(3);
+        for bar in market.generate_bars(50).into_iter().flatten() {
+            assert!(bar.low.0 <= bar.open.0.min(bar.close.0));
+            assert!(bar.high.0 >= bar.open.0.max(bar.close.0));
+        }
+        assert_eq!(market.time(), start() + Duration::days(50));
+    }
+
+    #[test]
+    fn invalid_asset_parameters_are_rejected() {
+        let new_market = || SyntheticMarket::new(1, start(), Duration::days(1));
+        assert!(new_market().with_asset(gbm("AAA", -0.2)).is_err());
+        assert!(new_market().with_asset(gbm("AAA", f64::NAN)).is_err());
+        assert!(new_market().with_asset(SyntheticAsset::new("AAA", 0.0, gbm("AAA", 0.2).model)).is_err());
+        assert!(new_market().with_asset(gbm("AAA", 0.2).with_spread(-0.01)).is_err());
+
+        let regimes = PriceModel::RegimeSwitching {
+            regimes: vec![Regime { drift: 0.1, volatility: 0.2 }],
+            transitions: vec![vec![0.5, 0.5]],
+        };
+        assert!(new_market().with_asset(SyntheticAsset::new("AAA", 100.0, regimes)).is_err());
+    }
+
+    #[test]
+    fn correlation_must_cover_every_asset() {
+        assert!(market(1).with_correlation(vec![vec![1.0]]).is_err());
+        assert!(market(1).with_correlation(vec![vec![1.0, 2.0], vec![2.0, 1.0]]).is_err());
+
+        let correlated = market(1).with_correlation(vec![vec![1.0, 0.5], vec![0.5, 1.0]]).unwrap();
+        assert!(correlated.with_asset(gbm("CCC", 0.2)).is_err());
+    }
+
+    #[test]
+    fn perfectly_correlated_assets_move_together() {
+        let mut market = SyntheticMarket::new(5, start(), Duration::days(1))
+            .with_asset(gbm("AAA", 0.2))
+            .unwrap()
+            .with_asset(gbm("BBB", 0.2))
+            .unwrap()
+            .with_correlation(vec![vec![1.0, 1.0], vec![1.0, 1.0]])
+            .unwrap();
+        for _ in 0..10 {
+            let quotes = market.next_quotes();
+            assert_eq!(quotes[0].last, quotes[1].last);
+        }
+    }
+}
@@ -0,0 +1,641 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use csv::StringRecord;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::account::Account;
+use crate::manager::AccountManager;
+use crate::clock::SimulatedClock;
+use crate::error::{Error, Result};
+use crate::loader::{json_field, parse_decimal, parse_timestamp, TimestampFormat};
+use crate::market::{Quote, SimpleMarketDataProvider};
+use crate::types::{Price, Symbol};
+
+/// Represents the kind of a tick event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TickKind {
+    /// Top-of-book quote update (bid and ask)
+    Quote,
+    /// Trade print (last price)
+    Trade,
+}
+
+/// A quote update or trade print
+///
+/// For trade prints only `quote.last` is meaningful; bid and ask are set to the trade price.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickEvent {
+    /// Kind of the event
+    pub kind: TickKind,
+    /// Event payload
+    pub quote: Quote,
+}
+
+impl TickEvent {
+    /// Create a quote update event
+    pub fn quote(quote: Quote) -> Self {
+        Self {
+            kind: TickKind::Quote,
+            quote,
+        }
+    }
+
+    /// Create a trade print event
+    pub fn trade(symbol: Symbol, price: Price, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            kind: TickKind::Trade,
+            quote: Quote::new(symbol, price, price, price).with_timestamp(timestamp),
+        }
+    }
+
+    /// Get the timestamp of the event
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.quote.timestamp
+    }
+}
+
+/// Streaming iterator over tick events
+pub type TickIterator = Box<dyn Iterator<Item = Result<TickEvent>>>;
+
+/// Mapping from tick fields to column (CSV header) or field (JSON) names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickColumns {
+    /// Timestamp column
+    pub timestamp: String,
+    /// Bid price column (quote updates)
+    pub bid: String,
+    /// Ask price column (quote updates)
+    pub ask: String,
+    /// Trade price column (trade prints)
+    pub price: String,
+    /// Column holding the event kind (`quote`/`q` or `trade`/`t`); if not set,
+    /// every row of a file has the kind passed to the reader
+    pub kind: Option<String>,
+}
+
+impl Default for TickColumns {
+    fn default() -> Self {
+        Self {
+            timestamp: "timestamp".to_string(),
+            bid: "bid".to_string(),
+            ask: "ask".to_string(),
+            price: "price".to_string(),
+            kind: None,
+        }
+    }
+}
+
+/// Loads tick events from CSV and JSON Lines files
+#[derive(Debug, Clone)]
+pub struct TickLoader {
+    columns: TickColumns,
+    timestamp_format: TimestampFormat,
+    time_zone: Tz,
+    delimiter: u8,
+}
+
+impl Default for TickLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TickLoader {
+    /// Create a new tick loader with default columns, RFC 3339 timestamps and UTC
+    pub fn new() -> Self {
+        Self {
+            columns: TickColumns::default(),
+            timestamp_format: TimestampFormat::Rfc3339,
+            time_zone: Tz::UTC,
+            delimiter: b',',
+        }
+    }
+
+    /// Set the column mapping
+    pub fn with_columns(mut self, columns: TickColumns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Set the timestamp format
+    pub fn with_timestamp_format(mut self, timestamp_format: TimestampFormat) -> Self {
+        self.timestamp_format = timestamp_format;
+        self
+    }
+
+    /// Set the time zone used for timestamps without an offset
+    pub fn with_time_zone(mut self, time_zone: Tz) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    /// Set the CSV delimiter
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Open a tick file for streaming, detecting CSV or JSON Lines from the extension
+    ///
+    /// `kind` is the kind of every row unless the loader has a kind column.
+    pub fn file<P: AsRef<Path>>(&self, path: P, symbol: &Symbol, kind: TickKind) -> Result<TickIterator> {
+        let path = path.as_ref();
+        debug!("TickLoader::file() - Opening: {:?}", path);
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match extension.as_str() {
+            "csv" | "txt" => Ok(Box::new(self.csv_reader(File::open(path)?, symbol, kind)?)),
+            "jsonl" | "ndjson" | "json" => {
+                let reader = BufReader::new(File::open(path)?);
+                Ok(Box::new(self.json_lines_reader(reader, symbol, kind)))
+            }
+            _ => Err(Error::MarketDataError {
+                reason: format!("Unsupported tick file: {}", path.display()),
+            }),
+        }
+    }
+
+    /// Stream tick events from any CSV source
+    pub fn csv_reader<R: Read>(&self, reader: R, symbol: &Symbol, kind: TickKind) -> Result<CsvTickReader<R>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        let headers = reader.headers()?.clone();
+        let find = |name: &str| headers.iter().position(|header| header.eq_ignore_ascii_case(name));
+        let timestamp = find(&self.columns.timestamp).ok_or_else(|| Error::MarketDataError {
+            reason: format!("Missing column '{}' in CSV header", self.columns.timestamp),
+        })?;
+
+        Ok(CsvTickReader {
+            loader: self.clone(),
+            reader,
+            record: StringRecord::new(),
+            timestamp,
+            bid: find(&self.columns.bid),
+            ask: find(&self.columns.ask),
+            price: find(&self.columns.price),
+            kind_column: self.columns.kind.as_deref().and_then(find),
+            symbol: symbol.clone(),
+            kind,
+        })
+    }
+
+    /// Stream tick events from any JSON Lines source
+    pub fn json_lines_reader<R: BufRead>(&self, reader: R, symbol: &Symbol, kind: TickKind) -> JsonLinesTickReader<R> {
+        JsonLinesTickReader {
+            loader: self.clone(),
+            lines: reader.lines(),
+            symbol: symbol.clone(),
+            kind,
+        }
+    }
+
+    /// Build an event from raw field values
+    fn build_event(&self, fields: RawTick<'_>, symbol: &Symbol, default_kind: TickKind) -> Result<TickEvent> {
+        let kind = match fields.kind {
+            Some(kind) => parse_kind(kind)?,
+            None => default_kind,
+        };
+        let timestamp = parse_timestamp(fields.timestamp, &self.timestamp_format, self.time_zone)?;
+        let missing = |column: &str| Error::MarketDataError {
+            reason: format!("Missing value for column '{}'", column),
+        };
+
+        match kind {
+            TickKind::Quote => {
+                let columns = &self.columns;
+                let bid = parse_decimal(&columns.bid, fields.bid.ok_or_else(|| missing(&columns.bid))?)?;
+                let ask = parse_decimal(&columns.ask, fields.ask.ok_or_else(|| missing(&columns.ask))?)?;
+                let mid = Price((bid + ask) / rust_decimal::Decimal::from(2));
+                let quote = Quote::new(symbol.clone(), Price(bid), Price(ask), mid).with_timestamp(timestamp);
+                Ok(TickEvent::quote(quote))
+            }
+            TickKind::Trade => {
+                let column = &self.columns.price;
+                let price = parse_decimal(column, fields.price.ok_or_else(|| missing(column))?)?;
+                Ok(TickEvent::trade(symbol.clone(), Price(price), timestamp))
+            }
+        }
+    }
+}
+
+/// Parse an event kind value
+fn parse_kind(value: &str) -> Result<TickKind> {
+    match value.trim().to_lowercase().as_str() {
+        "quote" | "q" => Ok(TickKind::Quote),
+        "trade" | "t" => Ok(TickKind::Trade),
+        other => Err(Error::MarketDataError {
+            reason: format!("Unknown tick kind '{}'", other),
+        }),
+    }
+}
+
+/// Raw field values of a single tick
+struct RawTick<'a> {
+    timestamp: &'a str,
+    bid: Option<&'a str>,
+    ask: Option<&'a str>,
+    price: Option<&'a str>,
+    kind: Option<&'a str>,
+}
+
+/// Streaming CSV tick reader
+pub struct CsvTickReader<R: Read> {
+    loader: TickLoader,
+    reader: csv::Reader<R>,
+    record: StringRecord,
+    timestamp: usize,
+    bid: Option<usize>,
+    ask: Option<usize>,
+    price: Option<usize>,
+    kind_column: Option<usize>,
+    symbol: Symbol,
+    kind: TickKind,
+}
+
+impl<R: Read> Iterator for CsvTickReader<R> {
+    type Item = Result<TickEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(false) => None,
+            Ok(true) => {
+                let record = &self.record;
+                // Empty cells count as missing, so mixed files can leave unused columns blank
+                let field = |index: Option<usize>| {
+                    index
+                        .and_then(|index| record.get(index))
+                        .filter(|value| !value.is_empty())
+                };
+                let fields = RawTick {
+                    timestamp: record.get(self.timestamp).unwrap_or_default(),
+                    bid: field(self.bid),
+                    ask: field(self.ask),
+                    price: field(self.price),
+                    kind: field(self.kind_column),
+                };
+                Some(self.loader.build_event(fields, &self.symbol, self.kind))
+            }
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
+/// Streaming JSON Lines tick reader
+pub struct JsonLinesTickReader<R: BufRead> {
+    loader: TickLoader,
+    lines: Lines<R>,
+    symbol: Symbol,
+    kind: TickKind,
+}
+
+impl<R: BufRead> JsonLinesTickReader<R> {
+    /// Parse a single JSON line into an event
+    fn parse_line(&self, line: &str) -> Result<TickEvent> {
+        let value: Value = serde_json::from_str(line)?;
+        let columns = &self.loader.columns;
+        let optional = |name: &str| json_field(&value, name).ok();
+
+        let timestamp = json_field(&value, &columns.timestamp)?;
+        let bid = optional(&columns.bid);
+        let ask = optional(&columns.ask);
+        let price = optional(&columns.price);
+        let kind = columns.kind.as_deref().and_then(optional);
+
+        let fields = RawTick {
+            timestamp: &timestamp,
+            bid: bid.as_deref(),
+            ask: ask.as_deref(),
+            price: price.as_deref(),
+            kind: kind.as_deref(),
+        };
+        self.loader.build_event(fields, &self.symbol, self.kind)
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLinesTickReader<R> {
+    type Item = Result<TickEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(self.parse_line(&line));
+        }
+    }
+}
+
+/// Next pending event of one source, ordered by timestamp then source position
+struct PendingEvent {
+    event: TickEvent,
+    source: usize,
+}
+
+impl PartialEq for PendingEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PendingEvent {}
+
+impl PartialOrd for PendingEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.event
+            .timestamp()
+            .cmp(&other.event.timestamp())
+            .then(self.source.cmp(&other.source))
+    }
+}
+
+/// Merges several time-ordered tick streams into one stream in timestamp order
+///
+/// Only the next event of each source is held in memory, so a full day of
+/// many symbols can be replayed from files. Events with equal timestamps are
+/// yielded in source order.
+pub struct MergedTickStream {
+    sources: Vec<TickIterator>,
+    pending: BinaryHeap<Reverse<PendingEvent>>,
+    /// Read errors, with the source to pull again once each is yielded (if any)
+    errors: VecDeque<(Error, Option<usize>)>,
+}
+
+impl MergedTickStream {
+    /// Create a merged stream from several sources
+    pub fn new(sources: Vec<TickIterator>) -> Self {
+        let mut stream = Self {
+            sources,
+            pending: BinaryHeap::new(),
+            errors: VecDeque::new(),
+        };
+        for source in 0..stream.sources.len() {
+            stream.pull(source);
+        }
+        stream
+    }
+
+    /// Read the next event of a source into the pending queue
+    ///
+    /// An error is queued instead. After a bad row the source is pulled again
+    /// when the error is yielded, so one bad row doesn't end the source; after an
+    /// I/O error the source is treated as exhausted, as it can't be read further.
+    fn pull(&mut self, source: usize) {
+        match self.sources[source].next() {
+            Some(Ok(event)) => self.pending.push(Reverse(PendingEvent { event, source })),
+            Some(Err(e)) => {
+                let resume = (!is_io_error(&e)).then_some(source);
+                self.errors.push_back((e, resume));
+            }
+            None => {}
+        }
+    }
+}
+
+impl Iterator for MergedTickStream {
+    type Item = Result<TickEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((error, resume)) = self.errors.pop_front() {
+            if let Some(source) = resume {
+                self.pull(source);
+            }
+            return Some(Err(error));
+        }
+
+        let Reverse(PendingEvent { event, source }) = self.pending.pop()?;
+        self.pull(source);
+        Some(Ok(event))
+    }
+}
+
+/// Check if an error means a source can't be read any further
+fn is_io_error(error: &Error) -> bool {
+    match error {
+        Error::IoError(_) => true,
+        Error::CsvError(error) => error.is_io_error(),
+        _ => false,
+    }
+}
+
+/// Replays merged tick streams into a market data provider and accounts
+pub struct TickReplay {
+    stream: MergedTickStream,
+    market_data: SimpleMarketDataProvider,
+    clock: Option<SimulatedClock>,
+}
+
+impl TickReplay {
+    /// Create a new tick replay over several sources
+    pub fn new(sources: Vec<TickIterator>) -> Self {
+        Self {
+            stream: MergedTickStream::new(sources),
+            market_data: SimpleMarketDataProvider::new(),
+            clock: None,
+        }
+    }
+
+    /// Advance a simulated clock to each event's timestamp as it is replayed
+    pub fn with_clock(mut self, clock: SimulatedClock) -> Self {
+        self.market_data = self.market_data.with_clock(clock.shared());
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Get the market data built from the events replayed so far
+    pub fn market_data(&self) -> &SimpleMarketDataProvider {
+        &self.market_data
+    }
+
+    /// Replay the next event into the market data, returning it
+    pub fn step(&mut self) -> Option<Result<TickEvent>> {
+        let event = match self.stream.next()? {
+            Ok(event) => event,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if let Some(clock) = &self.clock {
+            clock.set(event.timestamp());
+        }
+        self.market_data.apply_tick(&event);
+
+        Some(Ok(event))
+    }
+
+    /// Replay the next event and process the account's open orders for its symbol
+    pub fn step_account(&mut self, account: &mut Account) -> Option<Result<TickEvent>> {
+        let event = match self.step()? {
+            Ok(event) => event,
+            Err(e) => return Some(Err(e)),
+        };
+
+        match account.process_open_orders_for_symbol(&event.quote.symbol, &self.market_data) {
+            Ok(()) => Some(Ok(event)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Replay the next event and publish it to the manager's subscribed accounts
+    pub fn step_manager(&mut self, manager: &mut AccountManager) -> Option<Result<TickEvent>> {
+        let event = match self.step()? {
+            Ok(event) => event,
+            Err(e) => return Some(Err(e)),
+        };
+
+        match manager.publish(&event.quote.symbol, &self.market_data) {
+            Ok(_) => Some(Ok(event)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Replay every remaining event against an account
+    ///
+    /// Rows that fail to read are skipped and reported in the summary, as are I/O
+    /// errors, which end their source; an error processing the account's orders
+    /// stops the replay.
+    pub fn run(&mut self, account: &mut Account) -> Result<TickReplaySummary> {
+        let mut summary = TickReplaySummary::default();
+        while let Some(event) = self.step() {
+            match event {
+                Ok(event) => {
+                    account.process_open_orders_for_symbol(&event.quote.symbol, &self.market_data)?;
+                    summary.events += 1;
+                }
+                Err(e) => {
+                    warn!("TickReplay::run() - Skipping bad row: {}", e);
+                    summary.errors.push(e);
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// Outcome of a tick replay run
+#[derive(Debug, Default)]
+pub struct TickReplaySummary {
+    /// Number of events replayed
+    pub events: usize,
+    /// Rows that failed to read and were skipped, and sources that stopped on a read error
+    pub errors: Vec<Error>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Account;
+    use crate::clock::SimulatedClock;
+    use crate::config::Config;
+    use crate::order::{Order, OrderSide};
+    use crate::types::Quantity;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+    use std::io::Cursor;
+
+    fn source(symbol: &str, csv: &'static str) -> TickIterator {
+        let loader = TickLoader::new().with_columns(TickColumns {
+            kind: Some("kind".to_string()),
+            ..TickColumns::default()
+        });
+        Box::new(loader.csv_reader(Cursor::new(csv), &Symbol::new(symbol), TickKind::Quote).unwrap())
+    }
+
+    fn timestamps(stream: MergedTickStream) -> Vec<(String, u32)> {
+        use chrono::Timelike;
+        stream
+            .map(|event| {
+                let event = event.unwrap();
+                (event.quote.symbol.0.clone(), event.timestamp().second())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn csv_rows_become_quotes_and_trades() {
+        let events: Vec<TickEvent> = source(
+            "AAA",
+            "timestamp,kind,bid,ask,price\n2024-01-02T15:00:00Z,quote,99,101,\n2024-01-02T15:00:01Z,t,,,100.5\n",
+        )
+        .map(|event| event.unwrap())
+        .collect();
+
+        assert_eq!(events[0].kind, TickKind::Quote);
+        assert_eq!(events[0].quote.last, Price(dec!(100)));
+        assert_eq!(events[1].kind, TickKind::Trade);
+        assert_eq!(events[1].quote.last, Price(dec!(100.5)));
+    }
+
+    #[test]
+    fn merged_streams_yield_events_in_timestamp_order() {
+        let stream = MergedTickStream::new(vec![
+            source("AAA", "timestamp,kind,bid,ask,price\n2024-01-02T15:00:01Z,q,1,2,\n2024-01-02T15:00:03Z,q,1,2,\n"),
+            source("BBB", "timestamp,kind,bid,ask,price\n2024-01-02T15:00:01Z,q,1,2,\n2024-01-02T15:00:02Z,q,1,2,\n"),
+        ]);
+
+        let expected = vec![("AAA".to_string(), 1), ("BBB".to_string(), 1), ("BBB".to_string(), 2), ("AAA".to_string(), 3)];
+        assert_eq!(timestamps(stream), expected);
+    }
+
+    #[test]
+    fn bad_rows_are_skipped_but_failing_sources_end() {
+        let failing: TickIterator = Box::new(std::iter::repeat_with(|| {
+            Err(Error::IoError(std::io::Error::other("disconnected")))
+        }));
+        let mut stream = MergedTickStream::new(vec![
+            failing,
+            source("AAA", "timestamp,kind,bid,ask,price\nnot a time,q,1,2,\n2024-01-02T15:00:01Z,q,1,2,\n"),
+        ]);
+
+        assert!(matches!(stream.next(), Some(Err(Error::IoError(_)))));
+        assert!(matches!(stream.next(), Some(Err(Error::MarketDataError { .. }))));
+        assert!(matches!(stream.next(), Some(Ok(_))));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn run_fills_orders_and_reports_skipped_rows() {
+        let clock = SimulatedClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 14, 0, 0).unwrap());
+        let config = Config {
+            clock: Some(clock.shared()),
+            ..Config::default()
+        };
+        let mut account = Account::new_with_config("Test", "USD", dec!(10_000), config);
+        account
+            .submit_order(Order::market(Symbol::new("AAA"), OrderSide::Buy, Quantity(dec!(10))))
+            .unwrap();
+
+        let mut replay = TickReplay::new(vec![source(
+            "AAA",
+            "timestamp,kind,bid,ask,price\n2024-01-02T15:00:00Z,q,,101,\n2024-01-02T15:00:01Z,q,99,101,\n",
+        )])
+        .with_clock(clock);
+        let summary = replay.run(&mut account).unwrap();
+
+        assert_eq!(summary.events, 1);
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(account.order_history[0].average_fill_price(), Some(Price(dec!(101))));
+    }
+}
@@ -1,9 +1,12 @@
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use uuid::Uuid;
 
+use crate::id::IdGenerator;
+
 /// Represents a trading symbol (e.g., "AAPL", "BTC/USD")
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Symbol(pub String);
@@ -30,7 +33,7 @@ impl Quantity {
     }
     
     pub fn from_f64(quantity: f64) -> Self {
-        Quantity(Decimal::from_f64(quantity).unwrap_or_else(|| Decimal::ZERO))
+        Quantity(Decimal::from_f64(quantity).unwrap_or(Decimal::ZERO))
     }
     
     pub fn zero() -> Self {
@@ -70,7 +73,7 @@ impl Price {
     }
     
     pub fn from_f64(price: f64) -> Self {
-        Price(Decimal::from_f64(price).unwrap_or_else(|| Decimal::ZERO))
+        Price(Decimal::from_f64(price).unwrap_or(Decimal::ZERO))
     }
     
     pub fn zero() -> Self {
@@ -92,6 +95,17 @@ impl TradeId {
     pub fn new() -> Self {
         TradeId(Uuid::new_v4())
     }
+    
+    /// Create an identifier from an ID generator
+    pub fn generate(generator: &dyn IdGenerator) -> Self {
+        TradeId(generator.next_uuid())
+    }
+}
+
+impl Default for TradeId {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl fmt::Display for TradeId {
@@ -108,6 +122,17 @@ impl OrderId {
     pub fn new() -> Self {
         OrderId(Uuid::new_v4())
     }
+    
+    /// Create an identifier from an ID generator
+    pub fn generate(generator: &dyn IdGenerator) -> Self {
+        OrderId(generator.next_uuid())
+    }
+}
+
+impl Default for OrderId {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl fmt::Display for OrderId {
@@ -124,6 +149,17 @@ impl AccountId {
     pub fn new() -> Self {
         AccountId(Uuid::new_v4())
     }
+    
+    /// Create an identifier from an ID generator
+    pub fn generate(generator: &dyn IdGenerator) -> Self {
+        AccountId(generator.next_uuid())
+    }
+}
+
+impl Default for AccountId {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl fmt::Display for AccountId {
@@ -131,3 +167,44 @@ impl fmt::Display for AccountId {
         write!(f, "{}", self.0)
     }
 }
+
+/// Serialize a map with its keys in sorted order, so saved files are stable across runs
+pub(crate) fn serialize_sorted<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    let sorted: BTreeMap<&String, &V> = map.iter().collect();
+    sorted.serialize(serializer)
+}
+
+/// Serialize durations as whole seconds, so reports are easy to consume
+pub(crate) mod duration_seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        i64::deserialize(deserializer).map(Duration::seconds)
+    }
+
+    /// Serialize optional durations as whole seconds
+    pub mod option {
+        use chrono::Duration;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+            match duration {
+                Some(duration) => serializer.serialize_some(&duration.num_seconds()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+            Option::<i64>::deserialize(deserializer).map(|seconds| seconds.map(Duration::seconds))
+        }
+    }
+}